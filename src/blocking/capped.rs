@@ -0,0 +1,66 @@
+use futures::Future;
+
+use super::List;
+use crate::{collections, Error};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A redis-backed list that never holds more than a fixed number of elements, whose methods block the calling thread
+/// until they complete. See `collections::CappedList`.
+pub struct CappedList<T: Serialize + DeserializeOwned> {
+    list: List<T>,
+    limit: u32,
+}
+
+impl<T: Serialize + DeserializeOwned> Clone for CappedList<T> {
+    fn clone(&self) -> Self {
+        CappedList {
+            list: self.list.clone(),
+            limit: self.limit,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> CappedList<T> {
+    /// Wraps `list` so that it holds at most `limit` elements. A limit of zero is treated as a limit of one.
+    pub fn new(list: List<T>, limit: u32) -> Self {
+        CappedList {
+            list,
+            limit: limit.max(1),
+        }
+    }
+    /// Returns the maximum number of elements held by the list.
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+    /// Returns the underlying list, for example to read from it.
+    pub fn list(&mut self) -> &mut List<T> {
+        &mut self.list
+    }
+    /// Unwraps the underlying list.
+    pub fn into_inner(self) -> List<T> {
+        self.list
+    }
+    /// Returns an asynchronous capped list sharing the connection of the underlying list.
+    fn capped(&self) -> collections::CappedList<T> {
+        collections::CappedList::new(self.list.inner.clone(), self.limit)
+    }
+    /// Pushes an element to the front/right/tail/end of the list, evicting elements from the rear of the list
+    /// if it would exceed its limit.
+    pub fn push_front(&mut self, item: T) -> Result<(), Error> {
+        self.capped().push_front(item).wait()
+    }
+    /// Pushes an element to the rear/left/head/start of the list, evicting elements from the front of the list
+    /// if it would exceed its limit.
+    pub fn push_back(&mut self, item: T) -> Result<(), Error> {
+        self.capped().push_back(item).wait()
+    }
+    /// Behaves as `push_front` but returns the evicted elements, starting with the first element of the list.
+    pub fn push_front_evicting(&mut self, item: T) -> Result<Vec<T>, Error> {
+        self.capped().push_front_evicting(item).wait()
+    }
+    /// Behaves as `push_back` but returns the evicted elements, starting with the last element of the list.
+    pub fn push_back_evicting(&mut self, item: T) -> Result<Vec<T>, Error> {
+        self.capped().push_back_evicting(item).wait()
+    }
+}
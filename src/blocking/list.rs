@@ -0,0 +1,288 @@
+use futures::{stream, Future, Sink, Stream};
+
+use super::{CappedList, Collection};
+use crate::{
+    collections::{self, list, End, Sort},
+    Codec, Error, Migrate, ReadFrom,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+/// A redis-backed list whose methods block the calling thread until they complete. See `collections::List` for the
+/// behaviour and complexity of each operation, and for the connection shared by clones.
+pub struct List<T: Serialize + DeserializeOwned> {
+    pub(super) inner: collections::List<T>,
+}
+
+impl<T: Serialize + DeserializeOwned> Clone for List<T> {
+    fn clone(&self) -> Self {
+        List {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned + 'static> Collection for List<T> {
+    type Async = collections::List<T>;
+    fn from_async(inner: collections::List<T>) -> Self {
+        List { inner }
+    }
+    fn as_async(&self) -> &collections::List<T> {
+        &self.inner
+    }
+    fn into_async(self) -> collections::List<T> {
+        self.inner
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> List<T> {
+    /// Sets the server from which this handle performs reads.
+    pub fn set_read_from(&mut self, read_from: ReadFrom) {
+        self.inner.set_read_from(read_from)
+    }
+    /// Returns the server from which this handle performs reads.
+    pub fn read_from(&self) -> ReadFrom {
+        self.inner.read_from()
+    }
+    /// Returns a handle to the same list, sharing this handle's connection, that performs reads from the provided
+    /// server.
+    pub fn with_read_from(&self, read_from: ReadFrom) -> List<T> {
+        List {
+            inner: self.inner.with_read_from(read_from),
+        }
+    }
+    /// Transforms the serialized elements of the list with `codec`. See `collections::List::with_codec`.
+    pub fn with_codec<C: Codec>(self, codec: C) -> List<T> {
+        List {
+            inner: self.inner.with_codec(codec),
+        }
+    }
+    /// Stores the elements of the list in a versioned envelope at the current version of `T`. See
+    /// `collections::List::versioned`.
+    pub fn versioned(self) -> List<T>
+    where
+        T: Migrate,
+    {
+        List {
+            inner: self.inner.versioned(),
+        }
+    }
+    /// Rewrites elements of a versioned list that were upgraded as they were read. See
+    /// `collections::List::write_back_upgrades`.
+    pub fn write_back_upgrades(self) -> List<T> {
+        List {
+            inner: self.inner.write_back_upgrades(),
+        }
+    }
+    /// Rewrites every element of a versioned list that is stored at an earlier version at the current version,
+    /// returning the number of elements rewritten. See `collections::List::migrate_all`.
+    pub fn migrate_all(&mut self, page_size: u32) -> Result<u32, Error> {
+        self.inner.migrate_all(page_size).wait()
+    }
+    /// Pops an element from the front/right/tail/end of the list.
+    pub fn pop_front(&mut self) -> Result<Option<T>, Error> {
+        self.inner.pop_front().wait()
+    }
+    /// Pops an element from the rear/left/head/start of the list.
+    pub fn pop_back(&mut self) -> Result<Option<T>, Error> {
+        self.inner.pop_back().wait()
+    }
+    /// Gets the element from the list at the provided index.
+    pub fn index(&mut self, index: i64) -> Result<T, Error> {
+        self.inner.index(index).wait()
+    }
+    /// Gets the element from the list at the provided index, or `None` if the index is out of range.
+    pub fn get(&mut self, index: i64) -> Result<Option<T>, Error> {
+        self.inner.get(index).wait()
+    }
+    /// Sets the list element at `index` to `value`.
+    pub fn set_index(&mut self, index: i64, value: T) -> Result<(), Error> {
+        self.inner.set_index(index, value).wait()
+    }
+    /// Returns elements of the list starting at `start` and stopping at `stop`, inclusive.
+    pub fn range(&mut self, start: i64, stop: i64) -> Result<Vec<T>, Error> {
+        self.inner.range(start, stop).wait()
+    }
+    /// Trims the list to the specified range of values.
+    pub fn trim(&mut self, start: i64, stop: i64) -> Result<(), Error> {
+        self.inner.trim(start, stop).wait()
+    }
+    /// Pushes an element to the front/right/tail/end of the list.
+    pub fn push_front(&mut self, item: T) -> Result<(), Error> {
+        self.inner.push_front(item).wait()
+    }
+    /// Pushes an element to the rear/left/head/start of the list.
+    pub fn push_back(&mut self, item: T) -> Result<(), Error> {
+        self.inner.push_back(item).wait()
+    }
+    /// Returns the length of the list.
+    pub fn len(&mut self) -> Result<u32, Error> {
+        self.inner.len().wait()
+    }
+    /// Returns true if the list has no elements, i.e. its key does not exist.
+    pub fn is_empty(&mut self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+    /// Removes the first `count` occurrences of elements equal to `item` from the list, or every occurrence if
+    /// `count` is zero, returning the number of elements removed.
+    pub fn remove(&mut self, count: u32, item: T) -> Result<u32, Error> {
+        self.inner.remove(count, item).wait()
+    }
+    /// Inserts `value` into the list before the first occurrence of `pivot`.
+    pub fn insert_before(&mut self, pivot: T, value: T) -> Result<bool, Error> {
+        self.inner.insert_before(pivot, value).wait()
+    }
+    /// Inserts `value` into the list after the first occurrence of `pivot`.
+    pub fn insert_after(&mut self, pivot: T, value: T) -> Result<bool, Error> {
+        self.inner.insert_after(pivot, value).wait()
+    }
+    /// Returns the indices of elements equal to `item`.
+    pub fn position(&mut self, item: T, rank: i64, count: u32) -> Result<Vec<u32>, Error> {
+        self.inner.position(item, rank, count).wait()
+    }
+    /// Returns true if the list contains an element equal to `item`.
+    pub fn contains(&mut self, item: T) -> Result<bool, Error> {
+        self.inner.contains(item).wait()
+    }
+    /// Searches the list on the server for elements containing a field equal to `value`.
+    pub fn find_by_field<V: Serialize>(
+        &mut self,
+        path: &[&str],
+        value: V,
+        count: u32,
+    ) -> Result<Vec<(u32, T)>, Error> {
+        self.inner.find_by_field(path, value, count).wait()
+    }
+    /// Atomically pops an element from the `from` end of this list and pushes it onto the `to` end of `other`.
+    pub fn move_to(&mut self, other: &List<T>, from: End, to: End) -> Result<Option<T>, Error> {
+        self.inner.move_to(&other.inner, from, to).wait()
+    }
+    /// Blocking variant of `move_to`, which waits up to `timeout` for an element to be pushed onto this list.
+    pub fn move_to_blocking(
+        &mut self,
+        other: &List<T>,
+        from: End,
+        to: End,
+        timeout: Duration,
+    ) -> Result<Option<T>, Error> {
+        self.inner
+            .move_to_blocking(&other.inner, from, to, timeout)
+            .wait()
+    }
+    /// Converts this list into a capped list that holds at most `limit` elements.
+    pub fn with_capacity_limit(self, limit: u32) -> CappedList<T> {
+        CappedList::new(self, limit)
+    }
+    /// Returns an iterator over the elements of the list from the first (head) to the last (tail), fetching
+    /// `page_size` elements at a time.
+    pub fn iter(&mut self, page_size: u32) -> Iter<T> {
+        Iter {
+            elements: self.inner.iter(page_size).wait(),
+        }
+    }
+    /// Returns an iterator over the elements of the list from the last (tail) to the first (head).
+    pub fn iter_rev(&mut self, page_size: u32) -> Iter<T> {
+        Iter {
+            elements: self.inner.iter_rev(page_size).wait(),
+        }
+    }
+    /// Returns a sink that pushes elements onto the provided end of the list in batches.
+    pub fn sink(&mut self, end: End) -> ListSink<T> {
+        ListSink {
+            sink: self.inner.sink(end),
+        }
+    }
+    /// Returns the elements of the list sorted on the server as specified by `sort`.
+    pub fn sorted(&mut self, sort: &Sort) -> Result<Vec<T>, Error> {
+        self.inner.sorted(sort).wait()
+    }
+    /// Sorts the elements of the list and returns the raw values of the external keys given by each of `patterns`.
+    pub fn sorted_get(
+        &mut self,
+        sort: &Sort,
+        patterns: &[&str],
+    ) -> Result<Vec<Vec<Option<Vec<u8>>>>, Error> {
+        self.inner.sorted_get(sort, patterns).wait()
+    }
+    /// Sorts the elements of the list and stores them in `destination`, returning the number of elements stored.
+    pub fn sort_into(&mut self, sort: &Sort, destination: &List<T>) -> Result<u32, Error> {
+        self.inner.sort_into(sort, &destination.inner).wait()
+    }
+    /// Returns the elements of the list sorted on the server by the field designated by `path`.
+    pub fn sort_by_key(&mut self, path: &[&str], descending: bool) -> Result<Vec<T>, Error> {
+        self.inner.sort_by_key(path, descending).wait()
+    }
+    /// Sorts the elements of the list as `sort_by_key` does and stores them in `destination`.
+    pub fn sort_by_key_into(
+        &mut self,
+        path: &[&str],
+        descending: bool,
+        destination: &List<T>,
+    ) -> Result<u32, Error> {
+        self.inner
+            .sort_by_key_into(path, descending, &destination.inner)
+            .wait()
+    }
+}
+
+/// An iterator over the elements of a List that fetches them in pages. See `collections::list::Iter`.
+pub struct Iter<T: DeserializeOwned> {
+    elements: stream::Wait<list::Iter<T>>,
+}
+
+impl<T: DeserializeOwned> Iter<T> {
+    /// Permits the list to be modified while the iterator is in progress. See
+    /// `collections::list::Iter::tolerate_modification`.
+    pub fn tolerate_modification(self) -> Self {
+        Iter {
+            elements: self.elements.into_inner().tolerate_modification().wait(),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for Iter<T> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.elements.next()
+    }
+}
+
+/// A sink that buffers elements and pushes them onto one end of a List in batches. See
+/// `collections::list::ListSink`.
+///
+/// Elements still buffered when the sink is dropped are discarded, so the sink must be closed once the last element has
+/// been sent.
+pub struct ListSink<T: Serialize> {
+    sink: list::ListSink<T>,
+}
+
+impl<T: Serialize> ListSink<T> {
+    /// Sets the maximum number of elements buffered before they are pushed. The default is 128.
+    pub fn batch_size(self, batch_size: usize) -> Self {
+        ListSink {
+            sink: self.sink.batch_size(batch_size),
+        }
+    }
+    /// Sets the maximum time for which elements are buffered while further elements are being sent. As nothing polls the
+    /// sink between sends, elements are otherwise only pushed when the batch is full or the sink is flushed or closed.
+    pub fn flush_interval(self, flush_interval: Duration) -> Self {
+        ListSink {
+            sink: self.sink.flush_interval(flush_interval),
+        }
+    }
+    /// Sends an element to the sink, pushing the buffered elements if the batch is full or the flush interval
+    /// has elapsed.
+    pub fn send(&mut self, item: T) -> Result<(), Error> {
+        (&mut self.sink).wait().send(item)
+    }
+    /// Pushes every buffered element.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.sink.push_buffered()
+    }
+    /// Pushes every buffered element and consumes the sink.
+    pub fn close(mut self) -> Result<(), Error> {
+        self.sink.push_buffered()
+    }
+}
@@ -0,0 +1,323 @@
+/// A capped list with blocking methods.
+pub mod capped;
+/// A list with blocking methods.
+pub mod list;
+/// A reliable queue with blocking methods.
+pub mod queue;
+
+use futures::{stream, Future, Stream};
+
+use redis::{FromRedisValue, IntoConnectionInfo};
+
+use crate::{
+    collections::{self, WatchEvent},
+    Codec, Error, KeyInfo, Namespace, OpenMode, ReplicaSelection, RetryPolicy,
+};
+
+use std::{fmt::Debug, time::Duration};
+
+pub use capped::CappedList;
+pub use list::{Iter, List, ListSink};
+pub use queue::ReliableQueue;
+
+/// A redis database connection whose methods block the calling thread until they complete.
+///
+/// This wraps a `crate::Database` and shares its key naming, serialization and errors, so collections stored through
+/// either are interchangeable. The constructors and builder methods behave as those of `crate::Database`, and like
+/// it the database is cheap to clone.
+#[derive(Clone)]
+pub struct Database {
+    inner: crate::Database,
+}
+
+impl Database {
+    /// Connects to a database at the provided address. See `crate::Database::new`.
+    pub fn new<T: IntoConnectionInfo>(addr: T) -> Result<Database, Error> {
+        crate::Database::new(addr).wait().map(Database::from)
+    }
+    /// Connects to a redis cluster through the provided addresses of some of its nodes. See `crate::Database::cluster`.
+    pub fn cluster<T: IntoConnectionInfo>(addrs: Vec<T>) -> Result<Database, Error> {
+        crate::Database::cluster(addrs).wait().map(Database::from)
+    }
+    /// Connects to the master named `master_name` of a set of redis servers monitored by the provided sentinels. See
+    /// `crate::Database::sentinel`.
+    pub fn sentinel<T: IntoConnectionInfo>(
+        sentinels: Vec<T>,
+        master_name: &str,
+    ) -> Result<Database, Error> {
+        crate::Database::sentinel(sentinels, master_name)
+            .wait()
+            .map(Database::from)
+    }
+    /// Connects to a primary server and its read replicas. See `crate::Database::replicated`.
+    pub fn replicated<T: IntoConnectionInfo>(
+        primary: T,
+        replicas: Vec<T>,
+        selection: ReplicaSelection,
+    ) -> Result<Database, Error> {
+        crate::Database::replicated(primary, replicas, selection)
+            .wait()
+            .map(Database::from)
+    }
+    /// Creates an empty database held in the memory of this process. See `crate::Database::in_memory`.
+    pub fn in_memory() -> Database {
+        Database::from(crate::Database::in_memory())
+    }
+    /// Sets the namespace by which collection names are mapped to redis keys. See `Namespace`.
+    pub fn with_namespace(self, namespace: Namespace) -> Self {
+        Database::from(self.inner.with_namespace(namespace))
+    }
+    /// Sets the policy by which requests are retried. See `RetryPolicy`.
+    pub fn with_retry_policy(self, retry: RetryPolicy) -> Self {
+        Database::from(self.inner.with_retry_policy(retry))
+    }
+    /// Returns the policy by which requests are retried.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        self.inner.retry_policy()
+    }
+    /// Sets the time within which connections must be established. See `crate::Database::with_connect_timeout`.
+    pub fn with_connect_timeout(self, timeout: Duration) -> Self {
+        Database::from(self.inner.with_connect_timeout(timeout))
+    }
+    /// Sets the time within which each reply must be received. See `crate::Database::with_read_timeout`.
+    pub fn with_read_timeout(self, timeout: Duration) -> Self {
+        Database::from(self.inner.with_read_timeout(timeout))
+    }
+    /// Sets the time within which each request must be sent. See `crate::Database::with_write_timeout`.
+    pub fn with_write_timeout(self, timeout: Duration) -> Self {
+        Database::from(self.inner.with_write_timeout(timeout))
+    }
+    /// Sets the codec by which serialized elements are transformed. See `crate::Database::with_codec`.
+    pub fn with_codec<C: Codec>(self, codec: C) -> Self {
+        Database::from(self.inner.with_codec(codec))
+    }
+    /// Returns the namespace by which collection names are mapped to redis keys.
+    pub fn namespace(&self) -> &Namespace {
+        self.inner.namespace()
+    }
+    /// Gets a data structure of the provided type with the specified name. See `crate::Database::get`.
+    pub fn get<T: Collection>(&self, name: &str) -> Result<T, Error> {
+        self.inner.get::<T::Async>(name).wait().map(T::from_async)
+    }
+    /// Gets a data structure of the provided type stored at exactly the specified key. See `crate::Database::get_raw`.
+    pub fn get_raw<T: Collection, K: Into<String>>(&self, key: K) -> Result<T, Error> {
+        self.inner
+            .get_raw::<T::Async, K>(key)
+            .wait()
+            .map(T::from_async)
+    }
+    /// Gets a data structure of the provided type with the specified name, verifying the type of the value stored
+    /// at its key. See `crate::Database::open`.
+    pub fn open<T: Collection>(&self, name: &str, mode: OpenMode) -> Result<T, Error> {
+        self.inner
+            .open::<T::Async>(name, mode)
+            .wait()
+            .map(T::from_async)
+    }
+    /// Type-checked variant of `get_raw`. See `crate::Database::open_raw`.
+    pub fn open_raw<T: Collection, K: Into<String>>(
+        &self,
+        key: K,
+        mode: OpenMode,
+    ) -> Result<T, Error> {
+        self.inner
+            .open_raw::<T::Async, K>(key, mode)
+            .wait()
+            .map(T::from_async)
+    }
+    /// Prepares a Lua script for invocation on a dedicated connection. See `crate::Script`.
+    pub fn script(&self, code: &str) -> Result<Script, Error> {
+        self.inner.script(code).wait().map(|inner| Script { inner })
+    }
+    /// Returns an iterator over the logical names of the collections of the provided type whose names match the
+    /// glob-style `pattern`. See `crate::Database::keys`.
+    pub fn keys<T: Collection>(&self, pattern: &str) -> CollectionNames {
+        CollectionNames {
+            names: self.inner.keys::<T::Async>(pattern).wait(),
+        }
+    }
+    /// Returns an iterator over the keys matching the glob-style `pattern` within the application prefix of the
+    /// namespace of the database along with their types. See `crate::Database::all_keys`.
+    pub fn all_keys(&self, pattern: &str) -> Keys {
+        Keys {
+            keys: self.inner.all_keys(pattern).wait(),
+        }
+    }
+    /// Unwraps the underlying asynchronous database.
+    pub fn into_inner(self) -> crate::Database {
+        self.inner
+    }
+}
+
+impl From<crate::Database> for Database {
+    fn from(inner: crate::Database) -> Database {
+        Database { inner }
+    }
+}
+
+/// A redis-backed data structure with blocking methods, wrapping the asynchronous collection `Async`.
+pub trait Collection: Clone + Send + Sync + 'static {
+    /// The asynchronous collection wrapped by this collection.
+    type Async: collections::Collection;
+    /// Wraps an asynchronous collection.
+    fn from_async(collection: Self::Async) -> Self;
+    /// Returns the wrapped asynchronous collection.
+    fn as_async(&self) -> &Self::Async;
+    /// Unwraps the asynchronous collection.
+    fn into_async(self) -> Self::Async;
+}
+
+/// A redis key with a variety of generic blocking operations.
+pub trait Key<T: Send + Debug> {
+    /// Removes this key from the database.
+    fn remove(self) -> Result<(), Error>;
+    /// Begins watching this key for changes and updates.
+    fn watch(&self) -> Result<Watcher<T>, Error>;
+}
+
+impl<T: Collection> Key<<T::Async as collections::Collection>::WatchEvent> for T {
+    /// Removes the collection from the database. This operation is O(1).
+    fn remove(self) -> Result<(), Error> {
+        collections::Key::remove(self.into_async()).wait()
+    }
+    fn watch(&self) -> Result<Watcher<<T::Async as collections::Collection>::WatchEvent>, Error> {
+        collections::Key::watch(self.as_async())
+            .wait()
+            .map(|watcher| Watcher {
+                events: watcher.wait(),
+            })
+    }
+}
+
+/// An iterator over the update notifications of a watched key, which blocks until the next notification is
+/// received. See `collections::Watcher`.
+pub struct Watcher<T: Send + Debug> {
+    events: stream::Wait<collections::Watcher<T>>,
+}
+
+impl<T: Send + Debug> Iterator for Watcher<T> {
+    type Item = Result<WatchEvent<T>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.next()
+    }
+}
+
+/// An iterator over the logical names of the collections of a given type in a database, produced by
+/// `Database::keys`. See `crate::CollectionNames`.
+pub struct CollectionNames {
+    names: stream::Wait<crate::CollectionNames>,
+}
+
+impl CollectionNames {
+    /// Sets the COUNT hint, i.e. the amount of work performed by the server for each batch of keys. The default is 100.
+    pub fn count(self, count: u32) -> Self {
+        CollectionNames {
+            names: self.names.into_inner().count(count).wait(),
+        }
+    }
+}
+
+impl Iterator for CollectionNames {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.names.next()
+    }
+}
+
+/// An iterator over the keys in a database along with their types, produced by `Database::all_keys`. See
+/// `crate::Keys`.
+pub struct Keys {
+    keys: stream::Wait<crate::Keys>,
+}
+
+impl Keys {
+    /// Sets the COUNT hint, i.e. the amount of work performed by the server for each batch of keys. The default is 100.
+    pub fn count(self, count: u32) -> Self {
+        Keys {
+            keys: self.keys.into_inner().count(count).wait(),
+        }
+    }
+}
+
+impl Iterator for Keys {
+    type Item = Result<KeyInfo, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.keys.next()
+    }
+}
+
+/// A Lua script that runs atomically on the server. See `crate::Script`.
+#[derive(Clone)]
+pub struct Script {
+    inner: crate::Script,
+}
+
+impl Script {
+    /// Returns the SHA1 digest of the script in hexadecimal.
+    pub fn hash(&self) -> &str {
+        self.inner.hash()
+    }
+    /// Caches the script on the server with SCRIPT LOAD.
+    pub fn load(&self) -> Result<(), Error> {
+        self.inner.load().wait()
+    }
+    /// Begins an invocation of the script with no keys or arguments.
+    pub fn invocation(&self) -> Invocation {
+        Invocation {
+            inner: self.inner.invocation(),
+        }
+    }
+}
+
+/// A pending invocation of a Script, built up by adding keys and arguments. See `crate::Invocation`.
+pub struct Invocation {
+    inner: crate::Invocation,
+}
+
+impl Invocation {
+    /// Appends the key of `collection` to `KEYS`.
+    pub fn key<C: Collection>(self, collection: &C) -> Self {
+        Invocation {
+            inner: self.inner.key(collection.as_async()),
+        }
+    }
+    /// Appends a raw key name to `KEYS`.
+    pub fn raw_key(self, key: &str) -> Self {
+        Invocation {
+            inner: self.inner.raw_key(key),
+        }
+    }
+    /// Appends `arg` to `ARGV` serialized in the same manner as collection elements.
+    pub fn arg<A: serde::Serialize>(self, arg: &A) -> Self {
+        Invocation {
+            inner: self.inner.arg(arg),
+        }
+    }
+    /// Appends `arg` to `ARGV` in its redis representation.
+    pub fn raw_arg<A: redis::ToRedisArgs>(self, arg: A) -> Self {
+        Invocation {
+            inner: self.inner.raw_arg(arg),
+        }
+    }
+    /// Decodes the elements in the reply of `invoke_decoded` in the manner of `list`. See
+    /// `crate::Invocation::elements_of`.
+    pub fn elements_of<T: serde::Serialize + serde::de::DeserializeOwned + 'static>(
+        self,
+        list: &List<T>,
+    ) -> Self {
+        Invocation {
+            inner: self.inner.elements_of(list.as_async()),
+        }
+    }
+    /// Runs the script and converts its reply to `R`. See `crate::Invocation::invoke`.
+    pub fn invoke<R: FromRedisValue>(self) -> Result<R, Error> {
+        self.inner.invoke().wait()
+    }
+    /// Runs the script and decodes the elements in its reply. See `crate::Invocation::invoke_decoded`.
+    pub fn invoke_decoded<T: serde::de::DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+        self.inner.invoke_decoded().wait()
+    }
+}
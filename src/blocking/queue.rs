@@ -0,0 +1,62 @@
+use futures::Future;
+
+use super::List;
+use crate::{
+    collections::{self, Receipt},
+    Error,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+/// A reliable work queue built on a pair of redis-backed lists, whose methods block the calling thread until they
+/// complete. See `collections::ReliableQueue`.
+pub struct ReliableQueue<T: Serialize + DeserializeOwned> {
+    inner: collections::ReliableQueue<T>,
+}
+
+impl<T: Serialize + DeserializeOwned> Clone for ReliableQueue<T> {
+    fn clone(&self) -> Self {
+        ReliableQueue {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> ReliableQueue<T> {
+    /// Creates a reliable queue that takes elements from `queue` and holds them in `processing` until they are
+    /// acknowledged. See `collections::ReliableQueue::new`.
+    pub fn new(queue: List<T>, processing: List<T>, visibility_timeout: Duration) -> Self {
+        ReliableQueue {
+            inner: collections::ReliableQueue::new(
+                queue.inner,
+                processing.inner,
+                visibility_timeout,
+            ),
+        }
+    }
+    /// Pushes an element onto the back of the queue.
+    pub fn push(&mut self, item: T) -> Result<(), Error> {
+        self.inner.push(item).wait()
+    }
+    /// Takes the element at the front of the queue, moving it onto the processing list and leasing it for the
+    /// visibility timeout of the queue. Returns a receipt for the element to be passed to `ack`, or `None` if the queue
+    /// is empty.
+    pub fn take(&mut self) -> Result<Option<Receipt<T>>, Error> {
+        self.inner.take().wait()
+    }
+    /// Blocking variant of `take`, which waits up to `timeout` for an element to be pushed if the queue is empty.
+    pub fn take_blocking(&mut self, timeout: Duration) -> Result<Option<Receipt<T>>, Error> {
+        self.inner.take_blocking(timeout).wait()
+    }
+    /// Acknowledges that processing of the element taken with `receipt` has completed. Returns false if the element was
+    /// not being processed.
+    pub fn ack(&mut self, receipt: &Receipt<T>) -> Result<bool, Error> {
+        self.inner.ack(receipt).wait()
+    }
+    /// Returns every element whose lease has expired from the processing list to the front of the queue, returning
+    /// the number of elements requeued.
+    pub fn requeue_expired(&mut self) -> Result<u32, Error> {
+        self.inner.requeue_expired().wait()
+    }
+}
@@ -0,0 +1,393 @@
+use redis::{
+    Client, ConnectionAddr, ConnectionInfo, ConnectionLike, ErrorKind, RedisError, RedisResult,
+    Value,
+};
+
+use crate::{resp::parse_commands, retry::disconnected, timeout::Timeouts};
+
+use std::collections::HashMap;
+
+pub(crate) const SLOTS: u16 = 16384;
+const MAX_REDIRECTIONS: usize = 16;
+
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for byte in data {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Returns the cluster hash slot of `key`. Only the hash tag of the key, i.e. the contents of the first non-empty
+/// `{...}` section, is hashed if it has one.
+pub(crate) fn key_slot(key: &[u8]) -> u16 {
+    let hashed = key
+        .iter()
+        .position(|byte| *byte == b'{')
+        .and_then(|open| {
+            key[open + 1..]
+                .iter()
+                .position(|byte| *byte == b'}')
+                .filter(|length| *length > 0)
+                .map(|length| &key[open + 1..open + 1 + length])
+        })
+        .unwrap_or(key);
+    crc16(hashed) % SLOTS
+}
+
+/// Returns the keys accessed by a command, as far as the commands issued by redis-backed are concerned.
+pub(crate) fn command_keys(args: &[Vec<u8>]) -> Vec<&[u8]> {
+    let name = match args.first() {
+        Some(name) => name.to_ascii_uppercase(),
+        None => return vec![],
+    };
+    match name.as_slice() {
+        b"EVAL" | b"EVALSHA" => {
+            let count = std::str::from_utf8(args.get(2).map_or(&[][..], Vec::as_slice))
+                .ok()
+                .and_then(|count| count.parse::<usize>().ok())
+                .unwrap_or(0);
+            args.iter().skip(3).take(count).map(Vec::as_slice).collect()
+        }
+        b"LMOVE" | b"BLMOVE" | b"RPOPLPUSH" | b"BRPOPLPUSH" => {
+            args.iter().skip(1).take(2).map(Vec::as_slice).collect()
+        }
+        b"SORT" => {
+            let mut keys: Vec<&[u8]> = args.iter().skip(1).take(1).map(Vec::as_slice).collect();
+            if let Some(store) = args
+                .iter()
+                .position(|arg| arg.eq_ignore_ascii_case(b"STORE"))
+            {
+                keys.extend(args.get(store + 1).map(Vec::as_slice));
+            }
+            keys
+        }
+        b"SCAN" | b"SCRIPT" | b"MULTI" | b"EXEC" | b"DISCARD" | b"PING" | b"SELECT" | b"AUTH"
+        | b"ASKING" | b"CLUSTER" | b"INFO" | b"PUBLISH" => vec![],
+        _ => args.iter().skip(1).take(1).map(Vec::as_slice).collect(),
+    }
+}
+
+enum Redirection {
+    Moved(String),
+    Ask(String),
+}
+
+fn redirection(error: &RedisError) -> Option<Redirection> {
+    let code = error.extension_error_code()?;
+    let address = error.to_string().rsplit(' ').next()?.to_owned();
+    match code {
+        "MOVED" => Some(Redirection::Moved(address)),
+        "ASK" => Some(Redirection::Ask(address)),
+        _ => None,
+    }
+}
+
+/// A connection to a redis cluster that routes each request to the node serving the slot of its keys.
+pub(crate) struct ClusterConnection {
+    seeds: Vec<ConnectionInfo>,
+    passwd: Option<String>,
+    slots: Vec<(u16, u16, String)>,
+    connections: HashMap<String, redis::Connection>,
+    timeouts: Timeouts,
+}
+
+impl ClusterConnection {
+    /// Connects to the cluster through the first reachable of `seeds` and discovers its slots.
+    pub(crate) fn connect(seeds: Vec<ConnectionInfo>, timeouts: Timeouts) -> RedisResult<Self> {
+        let passwd = seeds.first().and_then(|seed| seed.passwd.clone());
+        let mut connection = ClusterConnection {
+            seeds,
+            passwd,
+            slots: vec![],
+            connections: HashMap::new(),
+            timeouts,
+        };
+        connection.refresh_slots()?;
+        Ok(connection)
+    }
+    fn node_connection(&mut self, address: &str) -> RedisResult<&mut redis::Connection> {
+        if !self.connections.contains_key(address) {
+            let mut parts = address.rsplitn(2, ':');
+            let port = parts.next().and_then(|port| port.parse().ok());
+            let host = parts.next();
+            let (host, port) = match (host, port) {
+                (Some(host), Some(port)) => (host.to_owned(), port),
+                _ => {
+                    return Err(RedisError::from((
+                        ErrorKind::ResponseError,
+                        "Invalid cluster node address",
+                        address.to_owned(),
+                    )))
+                }
+            };
+            let connection = self.timeouts.connect(&Client::open(ConnectionInfo {
+                addr: Box::new(ConnectionAddr::Tcp(host, port)),
+                db: 0,
+                passwd: self.passwd.clone(),
+            })?)?;
+            self.connections.insert(address.to_owned(), connection);
+        }
+        Ok(self.connections.get_mut(address).unwrap())
+    }
+    /// Rediscovers the slots of the cluster with CLUSTER SLOTS, asking the nodes already connected before the seeds.
+    fn refresh_slots(&mut self) -> RedisResult<()> {
+        let mut last_error = None;
+        let mut reply: Option<Value> = None;
+        for connection in self.connections.values_mut() {
+            match redis::cmd("CLUSTER").arg("SLOTS").query(connection) {
+                Ok(value) => {
+                    reply = Some(value);
+                    break;
+                }
+                Err(err) => last_error = Some(err),
+            }
+        }
+        if reply.is_none() {
+            for seed in &self.seeds {
+                let timeouts = &self.timeouts;
+                match Client::open(seed.clone()).and_then(|client| {
+                    redis::cmd("CLUSTER")
+                        .arg("SLOTS")
+                        .query(&mut timeouts.connect(&client)?)
+                }) {
+                    Ok(value) => {
+                        reply = Some(value);
+                        break;
+                    }
+                    Err(err) => last_error = Some(err),
+                }
+            }
+        }
+        let reply = match reply {
+            Some(reply) => reply,
+            None => {
+                return Err(last_error.unwrap_or_else(|| {
+                    RedisError::from((
+                        ErrorKind::InvalidClientConfig,
+                        "No cluster nodes were provided",
+                    ))
+                }))
+            }
+        };
+        // Each range is followed by its master and then its replicas, whose entries also carry the node ID on newer
+        // servers, so the nested arrays are parsed by position rather than as tuples of a fixed size.
+        let ranges: Vec<Vec<Value>> = redis::from_redis_value(&reply)?;
+        let mut slots = ranges
+            .iter()
+            .map(|range| {
+                let master: Vec<Value> =
+                    redis::from_redis_value(range.get(2).unwrap_or(&Value::Nil))?;
+                let (host, port): (String, u16) = match master.as_slice() {
+                    [host, port, ..] => (
+                        redis::from_redis_value(host)?,
+                        redis::from_redis_value(port)?,
+                    ),
+                    _ => {
+                        return Err(RedisError::from((
+                            ErrorKind::TypeError,
+                            "Invalid cluster slot range",
+                        )))
+                    }
+                };
+                Ok((
+                    redis::from_redis_value(range.first().unwrap_or(&Value::Nil))?,
+                    redis::from_redis_value(range.get(1).unwrap_or(&Value::Nil))?,
+                    format!("{}:{}", host, port),
+                ))
+            })
+            .collect::<RedisResult<Vec<(u16, u16, String)>>>()?;
+        slots.sort();
+        self.slots = slots;
+        Ok(())
+    }
+    fn slot_address(&self, slot: u16) -> Option<String> {
+        self.slots
+            .iter()
+            .find(|(start, end, _)| *start <= slot && slot <= *end)
+            .map(|(_, _, address)| address.clone())
+    }
+    fn any_address(&self) -> RedisResult<String> {
+        self.slots
+            .first()
+            .map(|(_, _, address)| address.clone())
+            .ok_or_else(|| {
+                RedisError::from((
+                    ErrorKind::ResponseError,
+                    "The cluster has no slots assigned",
+                ))
+            })
+    }
+    /// Returns true if the packed command is SCRIPT LOAD or SCRIPT FLUSH, which apply to the script cache of a single
+    /// node. A script loaded on an arbitrary node would not be found by an EVALSHA routed to the node serving its keys,
+    /// so that `redis::Script`, which loads a script on the connection that reported it missing, would retry forever.
+    fn is_script_cache_command(cmd: &[u8]) -> bool {
+        match parse_commands(cmd).as_deref() {
+            Some([args]) => {
+                args.len() >= 2
+                    && args[0].eq_ignore_ascii_case(b"SCRIPT")
+                    && (args[1].eq_ignore_ascii_case(b"LOAD")
+                        || args[1].eq_ignore_ascii_case(b"FLUSH"))
+            }
+            _ => false,
+        }
+    }
+    /// Performs a command on every node serving slots of the cluster, returning the reply of the last node or the first
+    /// error.
+    fn broadcast(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let mut reply = Value::Nil;
+        for connection in self.node_connections()? {
+            reply = connection.req_packed_command(cmd)?;
+        }
+        Ok(reply)
+    }
+    /// Returns the slot of the first key accessed by the packed commands, if any.
+    fn route(cmd: &[u8]) -> Option<u16> {
+        parse_commands(cmd)?
+            .iter()
+            .flat_map(|args| command_keys(args).into_iter().next())
+            .next()
+            .map(key_slot)
+    }
+    fn slot_or_any_address(&self, slot: Option<u16>) -> RedisResult<String> {
+        match slot.and_then(|slot| self.slot_address(slot)) {
+            Some(address) => Ok(address),
+            None => self.any_address(),
+        }
+    }
+    /// Performs a request on the node serving `slot` (or an arbitrary node), following redirections.
+    ///
+    /// A redirection part-way through a pipeline leaves its remaining replies unread, so for pipelines the
+    /// connection is discarded and the pipeline is retried in full, following a MOVED against the node given by the
+    /// refreshed slots and an ASK against the node it names. `request` is told whether to send ASKING first, which for
+    /// a pipeline applies to the whole of the transaction it begins.
+    fn request<R, F>(&mut self, slot: Option<u16>, pipeline: bool, mut request: F) -> RedisResult<R>
+    where
+        F: FnMut(&mut redis::Connection, bool) -> RedisResult<R>,
+    {
+        let mut address = self.slot_or_any_address(slot)?;
+        let mut asking = false;
+        for _ in 0..MAX_REDIRECTIONS {
+            let result = {
+                let connection = self.node_connection(&address)?;
+                request(connection, asking).map_err(disconnected)
+            };
+            let err = match result {
+                Err(err) => err,
+                result => return result,
+            };
+            asking = false;
+            let redirection = redirection(&err);
+            if pipeline && redirection.is_some() {
+                self.connections.remove(&address);
+            }
+            match redirection {
+                Some(Redirection::Moved(_)) if pipeline => {
+                    self.refresh_slots()?;
+                    address = self.slot_or_any_address(slot)?;
+                }
+                Some(Redirection::Moved(moved)) => {
+                    self.refresh_slots()?;
+                    address = moved;
+                }
+                Some(Redirection::Ask(ask)) => {
+                    address = ask;
+                    asking = true;
+                }
+                None => {
+                    // The request may have been performed before the connection failed, so it is not retried.
+                    if err.is_io_error() {
+                        self.connections.remove(&address);
+                        let _ = self.refresh_slots();
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Err(RedisError::from((
+            ErrorKind::ResponseError,
+            "Too many cluster redirections",
+        )))
+    }
+    /// Consumes the cluster connection, returning its connection to the node currently serving the slot of `key`.
+    pub(crate) fn into_key_connection(mut self, key: &str) -> RedisResult<redis::Connection> {
+        let address = self.slot_or_any_address(Some(key_slot(key.as_bytes())))?;
+        self.node_connection(&address)?;
+        Ok(self.connections.remove(&address).unwrap())
+    }
+    /// Sets the timeouts of the connections to the nodes of the cluster.
+    pub(crate) fn set_timeouts(&mut self, timeouts: Timeouts) -> RedisResult<()> {
+        self.timeouts = timeouts;
+        for connection in self.connections.values() {
+            timeouts.apply(connection)?;
+        }
+        Ok(())
+    }
+    /// Returns connections to every node serving slots of the cluster.
+    pub(crate) fn node_connections(&mut self) -> RedisResult<Vec<&mut redis::Connection>> {
+        let mut addresses: Vec<String> = self
+            .slots
+            .iter()
+            .map(|(_, _, address)| address.clone())
+            .collect();
+        addresses.sort();
+        addresses.dedup();
+        for address in &addresses {
+            self.node_connection(address)?;
+        }
+        // The nodes are ordered by address so that they are indexed alike by every connection to the cluster.
+        let mut connections: Vec<_> = self
+            .connections
+            .iter_mut()
+            .filter(|(address, _)| addresses.contains(address))
+            .collect();
+        connections.sort_by_key(|(address, _)| *address);
+        Ok(connections
+            .into_iter()
+            .map(|(_, connection)| connection)
+            .collect())
+    }
+}
+
+impl ConnectionLike for ClusterConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        if ClusterConnection::is_script_cache_command(cmd) {
+            return self.broadcast(cmd);
+        }
+        self.request(
+            ClusterConnection::route(cmd),
+            false,
+            |connection, asking| {
+                if asking {
+                    let _: () = redis::cmd("ASKING").query(connection)?;
+                }
+                connection.req_packed_command(cmd)
+            },
+        )
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.request(ClusterConnection::route(cmd), true, |connection, asking| {
+            if asking {
+                let _: () = redis::cmd("ASKING").query(connection)?;
+            }
+            connection.req_packed_commands(cmd, offset, count)
+        })
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}
@@ -0,0 +1,266 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::Error;
+
+use std::{borrow::Cow, collections::BTreeMap, fmt, io, sync::Arc};
+
+/// The first byte of an element stored in a versioned envelope. This is a reserved initial byte in CBOR, so that
+/// elements stored before versioning was enabled, which are plain CBOR, are never mistaken for envelopes.
+const VERSIONED: u8 = 0x5c;
+
+/// The length of the envelope preceding the serialized value: the header byte and the version as a big-endian u32.
+const ENVELOPE_LEN: usize = 5;
+
+/// Serializes a value in the form in which the elements of collections are stored.
+pub(crate) fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    Ok(serde_cbor::to_vec(value)?)
+}
+
+/// Deserializes an element read from `key`, attributing failures to the element at `index` if it is known.
+pub(crate) fn decode<T: DeserializeOwned>(
+    key: &str,
+    index: Option<i64>,
+    data: &[u8],
+) -> Result<T, Error> {
+    serde_cbor::from_slice(data).map_err(|source| decode_error(key, index, source))
+}
+
+fn decode_error(key: &str, index: Option<i64>, source: serde_cbor::error::Error) -> Error {
+    Error::Decode {
+        key: key.to_owned(),
+        index,
+        source,
+    }
+}
+
+fn codec_error(key: Option<&str>, index: Option<i64>, source: io::Error) -> Error {
+    Error::Codec {
+        key: key.map(str::to_owned),
+        index,
+        source,
+    }
+}
+
+fn version_error(key: &str, index: Option<i64>, message: String) -> Error {
+    decode_error(key, index, serde::de::Error::custom(message))
+}
+
+/// A transformation of the serialized elements of collections as they are written to and read from the database, for
+/// example `Compressed`. See `Database::with_codec` and `List::with_codec`.
+///
+/// Codecs compose by wrapping an inner codec, which encodes elements before and decodes them after the wrapping codec.
+/// Elements are serialized as CBOR, whose reserved initial bytes (`0x1c`-`0x1e`, `0x3c`-`0x3e` and so on) codecs use as
+/// headers to mark the elements they have transformed, so that elements written without the codec, or left untransformed
+/// by it, are passed through unchanged.
+pub trait Codec: Send + Sync + 'static {
+    /// Transforms a serialized element to be written to the database.
+    fn encode(&self, data: &[u8]) -> io::Result<Vec<u8>>;
+    /// Reverses the transformation of an element read from the database.
+    fn decode(&self, data: &[u8]) -> io::Result<Vec<u8>>;
+}
+
+/// The codec that leaves elements unchanged, used as the innermost codec of a composition.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Plain;
+
+impl Codec for Plain {
+    fn encode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+    fn decode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+}
+
+/// A type whose serialized representation is versioned, such that values stored by earlier versions of the type
+/// can still be read once it has changed. See `List::versioned`.
+///
+/// Values stored before versioning was enabled for a collection are treated as version 0, so the first versioned
+/// schema of a type is typically version 1, with a migration registered from version 0 if the schema changed.
+pub trait Migrate: Serialize + DeserializeOwned {
+    /// The version of the current schema of the type.
+    const VERSION: u32;
+    /// Returns the upgrades of values stored by earlier versions of the type.
+    fn migrations() -> Migrations;
+}
+
+type Upgrade = Box<dyn Fn(&[u8]) -> Result<Vec<u8>, serde_cbor::error::Error> + Send + Sync>;
+
+/// The upgrades by which values stored by earlier versions of a `Migrate` type are brought up to date.
+///
+/// Each upgrade converts a value of one version to the following version, so a value stored at version 1 of a type
+/// whose current version is 3 is upgraded by the upgrades registered for versions 1 and 2 in turn. Upgrades are
+/// typically written in terms of copies of the earlier definitions of the type, i.e.
+/// `Migrations::new().register(1, |person: PersonV1| Person { name: person.name, email: None })`.
+#[derive(Default)]
+pub struct Migrations {
+    upgrades: BTreeMap<u32, Upgrade>,
+}
+
+impl Migrations {
+    /// Creates an empty set of migrations.
+    pub fn new() -> Self {
+        Migrations::default()
+    }
+    /// Registers `upgrade` as the conversion of values stored at `version` to version `version + 1`, replacing any
+    /// upgrade previously registered for `version`.
+    pub fn register<A, B, F>(mut self, version: u32, upgrade: F) -> Self
+    where
+        A: DeserializeOwned,
+        B: Serialize,
+        F: Fn(A) -> B + Send + Sync + 'static,
+    {
+        self.upgrades.insert(
+            version,
+            Box::new(move |data| serde_cbor::to_vec(&upgrade(serde_cbor::from_slice(data)?))),
+        );
+        self
+    }
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrations")
+            .field("versions", &self.upgrades.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// The current version of a versioned collection along with the migrations from its earlier versions.
+struct Versioning {
+    version: u32,
+    migrations: Migrations,
+}
+
+/// The manner in which the elements of a collection are encoded.
+#[derive(Clone, Default)]
+pub(crate) struct Encoding {
+    codec: Option<Arc<dyn Codec>>,
+    versioning: Option<Arc<Versioning>>,
+    /// Whether elements read at an earlier version are rewritten at the current version.
+    pub(crate) write_back: bool,
+}
+
+impl Encoding {
+    /// Returns an encoding that transforms serialized elements with `codec`, if any.
+    pub(crate) fn new(codec: Option<Arc<dyn Codec>>) -> Encoding {
+        Encoding {
+            codec,
+            versioning: None,
+            write_back: false,
+        }
+    }
+    /// Stores elements in a versioned envelope at the current version of `T`.
+    pub(crate) fn versioned<T: Migrate>(self) -> Encoding {
+        Encoding {
+            versioning: Some(Arc::new(Versioning {
+                version: T::VERSION,
+                migrations: T::migrations(),
+            })),
+            ..self
+        }
+    }
+    /// Transforms serialized elements with `codec` in place of any previous codec.
+    pub(crate) fn with_codec(self, codec: Arc<dyn Codec>) -> Encoding {
+        Encoding {
+            codec: Some(codec),
+            ..self
+        }
+    }
+    /// Serializes an element of a collection.
+    pub(crate) fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, Error> {
+        let data = encode(value)?;
+        match self.versioning {
+            Some(ref versioning) => self.transform(envelope(versioning.version, &data)),
+            None => self.transform(data),
+        }
+    }
+    /// Applies the codec, if any, to a serialized element.
+    fn transform(&self, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        match self.codec {
+            Some(ref codec) => codec
+                .encode(&data)
+                .map_err(|source| codec_error(None, None, source)),
+            None => Ok(data),
+        }
+    }
+    /// Deserializes an element read from `key`. See `decode`.
+    pub(crate) fn decode<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        index: Option<i64>,
+        data: &[u8],
+    ) -> Result<T, Error> {
+        self.decode_upgraded(key, index, data)
+            .map(|(value, _)| value)
+    }
+    /// Deserializes an element read from `key`, upgrading it if it was stored at an earlier version. The encoding of
+    /// the upgraded element at the current version is returned alongside it in that case.
+    pub(crate) fn decode_upgraded<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        index: Option<i64>,
+        data: &[u8],
+    ) -> Result<(T, Option<Vec<u8>>), Error> {
+        let data = match self.codec {
+            Some(ref codec) => Cow::Owned(
+                codec
+                    .decode(data)
+                    .map_err(|source| codec_error(Some(key), index, source))?,
+            ),
+            None => Cow::Borrowed(data),
+        };
+        let data = data.as_ref();
+        let versioning = match self.versioning {
+            Some(ref versioning) => versioning,
+            None => return Ok((decode(key, index, data)?, None)),
+        };
+        let (mut version, payload) = match data.first() {
+            Some(&VERSIONED) if data.len() >= ENVELOPE_LEN => {
+                let mut version = [0; 4];
+                version.copy_from_slice(&data[1..ENVELOPE_LEN]);
+                (u32::from_be_bytes(version), &data[ENVELOPE_LEN..])
+            }
+            _ => (0, data),
+        };
+        if version == versioning.version {
+            return Ok((decode(key, index, payload)?, None));
+        }
+        if version > versioning.version {
+            return Err(version_error(
+                key,
+                index,
+                format!(
+                    "stored at version {}, which is newer than the current version {}",
+                    version, versioning.version
+                ),
+            ));
+        }
+        let mut payload = payload.to_vec();
+        while version < versioning.version {
+            let upgrade = versioning
+                .migrations
+                .upgrades
+                .get(&version)
+                .ok_or_else(|| {
+                    version_error(
+                        key,
+                        index,
+                        format!("no migration is registered from version {}", version),
+                    )
+                })?;
+            payload = upgrade(&payload).map_err(|source| decode_error(key, index, source))?;
+            version += 1;
+        }
+        let value = decode(key, index, &payload)?;
+        Ok((value, Some(self.transform(envelope(version, &payload))?)))
+    }
+}
+
+fn envelope(version: u32, payload: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(ENVELOPE_LEN + payload.len());
+    data.push(VERSIONED);
+    data.extend_from_slice(&version.to_be_bytes());
+    data.extend_from_slice(payload);
+    data
+}
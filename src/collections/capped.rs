@@ -0,0 +1,123 @@
+use super::{End, List};
+use futures::{lazy, Future};
+
+use crate::Error;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub(crate) const PUSH_EVICTING_SCRIPT: &str = r"
+local excess = redis.call(ARGV[1], KEYS[1], ARGV[3]) - tonumber(ARGV[2])
+local evicted = {}
+for i = 1, excess do
+    evicted[i] = redis.call(ARGV[4], KEYS[1])
+end
+return evicted
+";
+
+/// A redis-backed list that never holds more than a fixed number of elements.
+///
+/// Pushing onto one end of a full capped list evicts elements from the opposite end, so that the list always
+/// holds the most recently pushed elements. Every push executes atomically with the trim that follows it, so other
+/// clients never observe the list above its limit provided they only push through a `CappedList`.
+pub struct CappedList<T: Serialize + DeserializeOwned> {
+    list: List<T>,
+    limit: u32,
+}
+
+impl<T: Serialize + DeserializeOwned> Clone for CappedList<T> {
+    fn clone(&self) -> Self {
+        CappedList {
+            list: self.list.clone(),
+            limit: self.limit,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> CappedList<T> {
+    /// Wraps `list` so that it holds at most `limit` elements. A limit of zero is treated as a limit of one.
+    /// Note that an existing list is not trimmed until the next push.
+    pub fn new(list: List<T>, limit: u32) -> Self {
+        CappedList {
+            list,
+            limit: limit.max(1),
+        }
+    }
+    /// Returns the maximum number of elements held by the list.
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+    /// Returns the underlying list, for example to read from it. Elements pushed directly onto the
+    /// underlying list are not subject to the limit until the next push through the capped list.
+    pub fn list(&mut self) -> &mut List<T> {
+        &mut self.list
+    }
+    /// Unwraps the underlying list.
+    pub fn into_inner(self) -> List<T> {
+        self.list
+    }
+    fn push(&mut self, end: End, item: T) -> impl Future<Item = (), Error = Error> {
+        let key = self.list.key.clone();
+        let connection = self.list.connection.clone();
+        let encoding = self.list.encoding.clone();
+        let limit = i64::from(self.limit);
+        let (start, stop) = match end {
+            End::Front => (-limit, -1),
+            End::Back => (0, limit - 1),
+        };
+        lazy(move || {
+            let data = encoding.encode(&item)?;
+            let _: () = redis::pipe()
+                .atomic()
+                .cmd(end.push_command())
+                .arg(&key)
+                .arg(data)
+                .ignore()
+                .cmd("LTRIM")
+                .arg(&key)
+                .arg(start)
+                .arg(stop)
+                .ignore()
+                .query(&mut *connection.write().unwrap())?;
+            Ok(())
+        })
+    }
+    fn push_evicting(&mut self, end: End, item: T) -> impl Future<Item = Vec<T>, Error = Error> {
+        let key = self.list.key.clone();
+        let connection = self.list.connection.clone();
+        let encoding = self.list.encoding.clone();
+        let limit = self.limit;
+        lazy(move || {
+            let data: Vec<Vec<u8>> = redis::Script::new(PUSH_EVICTING_SCRIPT)
+                .key(&key)
+                .arg(end.push_command())
+                .arg(limit)
+                .arg(encoding.encode(&item)?)
+                .arg(end.opposite().pop_command())
+                .invoke(&mut *connection.write().unwrap())
+                .map_err(Error::script)?;
+            data.iter()
+                .map(|data| encoding.decode(&key, Some(end.opposite().index()), data))
+                .collect::<Result<Vec<T>, Error>>()
+        })
+    }
+    /// Pushes an element to the front/right/tail/end of the list, evicting elements from the rear of the list
+    /// if it would exceed its limit. This operation is O(1) amortized over pushes.
+    pub fn push_front(&mut self, item: T) -> impl Future<Item = (), Error = Error> {
+        self.push(End::Front, item)
+    }
+    /// Pushes an element to the rear/left/head/start of the list, evicting elements from the front of the list
+    /// if it would exceed its limit. This operation is O(1) amortized over pushes.
+    pub fn push_back(&mut self, item: T) -> impl Future<Item = (), Error = Error> {
+        self.push(End::Back, item)
+    }
+    /// Behaves as `push_front` but returns the evicted elements, in the order they were evicted
+    /// (i.e. starting with the first element of the list).
+    pub fn push_front_evicting(&mut self, item: T) -> impl Future<Item = Vec<T>, Error = Error> {
+        self.push_evicting(End::Front, item)
+    }
+    /// Behaves as `push_back` but returns the evicted elements, in the order they were evicted
+    /// (i.e. starting with the last element of the list).
+    pub fn push_back_evicting(&mut self, item: T) -> impl Future<Item = Vec<T>, Error = Error> {
+        self.push_evicting(End::Back, item)
+    }
+}
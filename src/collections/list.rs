@@ -1,14 +1,20 @@
-use super::Collection;
-use futures::{lazy, Future};
-use redis::{Connection, RedisError};
+use super::{lua, CappedList, Collection};
+use futures::{lazy, Async, AsyncSink, Future, Poll, Sink, StartSend, Stream};
+use redis::{ErrorKind, RedisError};
+use tokio::timer::Delay;
 
-use crate::Error;
+use crate::{
+    codec::{encode, Encoding, Migrate},
+    Codec, Connection, Error, KeyType, ReadFrom,
+};
 
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
+    collections::VecDeque,
     marker::PhantomData,
     str::FromStr,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 
 /// A redis-backed list wrapping the built-in redis List structure.
@@ -16,10 +22,194 @@ use std::{
 /// This data-structure behaves similarly to a VecDeque i.e. it is O(1)
 /// to add/remove elements (push/pop) from the head and tail but O(n) over
 /// the length of the list to insert/set at a specific index.
+///
+/// If the database has read replicas the read-only operations of a list (`index`, `range`, `len`, `position`,
+/// `contains`, `find_by_field` and iteration) are performed on a replica by default, see `set_read_from`.
+///
+/// Clones of a list share its connection, so their requests are performed one at a time. Lists obtained separately
+/// from the database have connections of their own, which permits for example blocking in `move_to_blocking` without
+/// holding up other handles.
 pub struct List<T: Serialize + DeserializeOwned> {
-    connection: Arc<RwLock<Connection>>,
-    key: String,
-    data: PhantomData<T>,
+    pub(super) connection: Arc<RwLock<Connection>>,
+    pub(crate) key: String,
+    read_from: ReadFrom,
+    pub(crate) encoding: Encoding,
+    // A list does not hold elements, so it is `Send` and `Sync` regardless of `T`.
+    data: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.with_read_from(self.read_from)
+    }
+}
+
+pub(crate) const FIND_BY_FIELD_SCRIPT: &str = r"
+local expected, count = ARGV[1], tonumber(ARGV[2])
+local path = {unpack(ARGV, 3)}
+local matches = {}
+for index, element in ipairs(redis.call('LRANGE', KEYS[1], 0, -1)) do
+    local i = cbor.locate(element, path)
+    if i ~= nil and string.sub(element, i, cbor.skip(element, i) - 1) == expected then
+        -- Matches are flattened into consecutive index and element, as the client reads a vector of pairs.
+        table.insert(matches, index - 1)
+        table.insert(matches, element)
+        if #matches == 2 * count then
+            break
+        end
+    end
+end
+return matches
+";
+
+/// One of the two ends of a List.
+///
+/// The naming follows that of the `List` push and pop methods, i.e. the front
+/// is the right/tail/end of the list and the back is the left/head/start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum End {
+    /// The front/right/tail/end of the list.
+    Front,
+    /// The rear/left/head/start of the list.
+    Back,
+}
+
+impl End {
+    fn direction(self) -> &'static str {
+        match self {
+            End::Front => "RIGHT",
+            End::Back => "LEFT",
+        }
+    }
+    pub(super) fn push_command(self) -> &'static str {
+        match self {
+            End::Front => "RPUSH",
+            End::Back => "LPUSH",
+        }
+    }
+    pub(super) fn pop_command(self) -> &'static str {
+        match self {
+            End::Front => "RPOP",
+            End::Back => "LPOP",
+        }
+    }
+    /// Returns the index of the element at this end of a list.
+    pub(super) fn index(self) -> i64 {
+        match self {
+            End::Front => -1,
+            End::Back => 0,
+        }
+    }
+    /// Returns the other end of the list.
+    pub fn opposite(self) -> End {
+        match self {
+            End::Front => End::Back,
+            End::Back => End::Front,
+        }
+    }
+}
+
+fn is_unknown_command(error: &RedisError) -> bool {
+    error.kind() == ErrorKind::ResponseError && error.to_string().contains("unknown command")
+}
+
+/// Returns true if `error` is the reply of LSET to an index out of range or a key that does not exist.
+fn is_out_of_range(error: &RedisError) -> bool {
+    let message = error.to_string();
+    error.kind() == ErrorKind::ResponseError
+        && (message.contains("index out of range") || message.contains("no such key"))
+}
+
+pub(crate) const WRITE_BACK_SCRIPT: &str = r"
+local rewritten = 0
+for i = 1, #ARGV, 3 do
+    if redis.call('LINDEX', KEYS[1], ARGV[i]) == ARGV[i + 1] then
+        redis.call('LSET', KEYS[1], ARGV[i], ARGV[i + 2])
+        rewritten = rewritten + 1
+    end
+end
+return rewritten
+";
+
+/// Replaces each element at the given index that still holds its original data with its upgraded data, returning the
+/// number of elements replaced.
+fn rewrite(
+    connection: &mut Connection,
+    key: &str,
+    upgrades: &[(i64, Vec<u8>, Vec<u8>)],
+) -> Result<u32, Error> {
+    let script = redis::Script::new(WRITE_BACK_SCRIPT);
+    let mut invocation = script.prepare_invoke();
+    invocation.key(key);
+    for (index, original, upgraded) in upgrades {
+        invocation
+            .arg(*index)
+            .arg(original.as_slice())
+            .arg(upgraded.as_slice());
+    }
+    invocation.invoke(connection).map_err(Error::script)
+}
+
+/// Decodes elements read from the list at `key` along with their indices where known. If the encoding writes back
+/// upgrades, the elements that were stored at an earlier version are then rewritten, and a failure to rewrite them is
+/// returned in place of the elements.
+fn decode_elements<T: DeserializeOwned>(
+    connection: &RwLock<Connection>,
+    key: &str,
+    encoding: &Encoding,
+    data: Vec<(Vec<u8>, Option<i64>)>,
+) -> Result<Vec<T>, Error> {
+    let mut upgrades = vec![];
+    let mut values = Vec::with_capacity(data.len());
+    for (data, index) in data {
+        let (value, upgraded) = encoding.decode_upgraded(key, index, &data)?;
+        if let (true, Some(upgraded), Some(index)) = (encoding.write_back, upgraded, index) {
+            upgrades.push((index, data, upgraded));
+        }
+        values.push(value);
+    }
+    if !upgrades.is_empty() {
+        rewrite(&mut connection.write().unwrap(), key, &upgrades)?;
+    }
+    Ok(values)
+}
+
+/// Moves a raw element between two lists with LMOVE (or BLMOVE if a timeout is provided),
+/// falling back to (B)RPOPLPUSH on servers that predate LMOVE.
+pub(super) fn move_element(
+    connection: &mut Connection,
+    source: &str,
+    destination: &str,
+    from: End,
+    to: End,
+    timeout: Option<Duration>,
+) -> Result<Option<Vec<u8>>, RedisError> {
+    let (mut command, mut fallback) = match timeout {
+        None => (redis::cmd("LMOVE"), redis::cmd("RPOPLPUSH")),
+        Some(_) => (redis::cmd("BLMOVE"), redis::cmd("BRPOPLPUSH")),
+    };
+    command
+        .arg(source)
+        .arg(destination)
+        .arg(from.direction())
+        .arg(to.direction());
+    fallback.arg(source).arg(destination);
+    if let Some(timeout) = timeout {
+        // A timeout of zero blocks indefinitely, so sub-second timeouts must not be truncated to it. BLMOVE accepts
+        // fractional seconds, while servers that predate it may not, so the fallback rounds up to whole seconds.
+        command.arg(timeout.as_secs_f64());
+        fallback.arg(timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0));
+    }
+    let request = |connection: &mut Connection| match command.query(connection) {
+        Err(ref err) if is_unknown_command(err) && from == End::Front && to == End::Back => {
+            fallback.query(connection)
+        }
+        data => data,
+    };
+    match timeout {
+        Some(timeout) => connection.blocking(timeout, request),
+        None => request(connection),
+    }
 }
 
 /// Events that can occur on a List.
@@ -64,15 +254,23 @@ impl FromStr for ListEvent {
     }
 }
 
-impl<'a, T: Serialize + DeserializeOwned> Collection<'a> for List<T> {
+impl<T: Serialize + DeserializeOwned + 'static> Collection for List<T> {
     type WatchEvent = ListEvent;
-    fn get(key: String, connection: Connection) -> Result<List<T>, RedisError> {
+    fn get(key: String, connection: Connection) -> Result<List<T>, Error> {
         Ok(List {
-            key: format!("_orm_list:{}", key),
+            key,
+            encoding: Encoding::new(connection.codec()),
             connection: Arc::new(RwLock::new(connection)),
+            read_from: ReadFrom::Replica,
             data: PhantomData,
         })
     }
+    fn type_prefix() -> &'static str {
+        "_orm_list"
+    }
+    fn key_type() -> KeyType {
+        KeyType::List
+    }
     fn key(&self) -> String {
         self.key.clone()
     }
@@ -82,18 +280,107 @@ impl<'a, T: Serialize + DeserializeOwned> Collection<'a> for List<T> {
 }
 
 impl<T: Serialize + DeserializeOwned> List<T> {
+    /// Sets the server from which this handle performs reads. Reading from the primary guarantees that reads observe
+    /// the writes made through this or any other handle, at the cost of placing the load on the primary.
+    pub fn set_read_from(&mut self, read_from: ReadFrom) {
+        self.read_from = read_from;
+    }
+    /// Returns the server from which this handle performs reads.
+    pub fn read_from(&self) -> ReadFrom {
+        self.read_from
+    }
+    /// Returns a handle to the same list, sharing this handle's connection, that performs reads from the provided
+    /// server. This permits overriding the read preference for a single call, i.e.
+    /// `list.with_read_from(ReadFrom::Primary).range(0, -1)` to read one's own writes.
+    pub fn with_read_from(&self, read_from: ReadFrom) -> List<T> {
+        List {
+            connection: self.connection.clone(),
+            key: self.key.clone(),
+            read_from,
+            encoding: self.encoding.clone(),
+            data: PhantomData,
+        }
+    }
+    /// Transforms the serialized elements of the list with `codec`, in place of the codec of the database. See
+    /// `Database::with_codec`.
+    pub fn with_codec<C: Codec>(mut self, codec: C) -> List<T> {
+        self.encoding = self.encoding.with_codec(Arc::new(codec));
+        self
+    }
+    /// Stores the elements of the list in a versioned envelope at the current version of `T`, upgrading elements stored
+    /// at earlier versions, or before versioning was enabled, as they are read. See `Migrate`.
+    ///
+    /// Upgraded elements are not rewritten unless `write_back_upgrades` is set or `migrate_all` is called, and elements
+    /// in an envelope cannot be read through handles that are not versioned. Since equality is determined by the
+    /// stored representation, `remove`, `insert_before`, `insert_after`, `position` and `contains` only match elements
+    /// stored at the current version. `find_by_field` and `sort_by_key` locate fields according to the current schema.
+    pub fn versioned(mut self) -> List<T>
+    where
+        T: Migrate,
+    {
+        self.encoding = self.encoding.versioned::<T>();
+        self
+    }
+    /// Rewrites elements of a versioned list that were upgraded as they were read by `index`, `get`, `range` (from a
+    /// non-negative start), `find_by_field` or iteration, provided they were not modified in the meantime. Rewriting
+    /// executes as a script once the elements are read, and a failure to rewrite them fails the read with
+    /// `Error::Script`; since elements modified in the meantime are left as they are, the read may simply be retried.
+    pub fn write_back_upgrades(mut self) -> List<T> {
+        self.encoding.write_back = true;
+        self
+    }
+    /// Rewrites every element of a versioned list that is stored at an earlier version at the current version,
+    /// reading `page_size` elements at a time, and returns the number of elements rewritten.
+    ///
+    /// Each page is rewritten atomically by a script that only replaces elements that are unchanged since the page was
+    /// read, so the list may be modified concurrently, though elements shifted by concurrent insertions and removals
+    /// may be skipped; migrating again rewrites those that remain. This operation is O(N) over the length of the list.
+    pub fn migrate_all(&mut self, page_size: u32) -> impl Future<Item = u32, Error = Error> {
+        let key = self.key.clone();
+        let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        let page_size = i64::from(page_size.max(1));
+        lazy(move || {
+            let mut connection = connection.write().unwrap();
+            let mut rewritten = 0;
+            let mut start = 0;
+            loop {
+                let data: Vec<Vec<u8>> = redis::cmd("LRANGE")
+                    .arg(&key)
+                    .arg(start)
+                    .arg(start + page_size - 1)
+                    .query(&mut *connection)?;
+                let mut upgrades = vec![];
+                for (data, index) in data.iter().zip(start..) {
+                    if let (_, Some(upgraded)) =
+                        encoding.decode_upgraded::<T>(&key, Some(index), data)?
+                    {
+                        upgrades.push((index, data.clone(), upgraded));
+                    }
+                }
+                if !upgrades.is_empty() {
+                    rewritten += rewrite(&mut connection, &key, &upgrades)?;
+                }
+                if (data.len() as i64) < page_size {
+                    return Ok(rewritten);
+                }
+                start += page_size;
+            }
+        })
+    }
     /// Pops an element from the front/right/tail/end of the list. This is also
     /// sometimes referred to as the last element of the list. This operation is O(1).
     pub fn pop_front(&mut self) -> impl Future<Item = Option<T>, Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
         lazy(move || {
             let data: Option<Vec<u8>> = redis::cmd("RPOP")
-                .arg(key)
+                .arg(&key)
                 .query(&mut *connection.write().unwrap())?;
             match data {
                 None => Ok(None),
-                Some(data) => Ok(serde_cbor::from_slice(data.as_slice())?),
+                Some(data) => Ok(Some(encoding.decode(&key, Some(-1), &data)?)),
             }
         })
     }
@@ -102,13 +389,14 @@ impl<T: Serialize + DeserializeOwned> List<T> {
     pub fn pop_back(&mut self) -> impl Future<Item = Option<T>, Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
         lazy(move || {
             let data: Option<Vec<u8>> = redis::cmd("LPOP")
-                .arg(key)
+                .arg(&key)
                 .query(&mut *connection.write().unwrap())?;
             match data {
                 None => Ok(None),
-                Some(data) => Ok(serde_cbor::from_slice(data.as_slice())?),
+                Some(data) => Ok(Some(encoding.decode(&key, Some(0), &data)?)),
             }
         })
     }
@@ -117,30 +405,67 @@ impl<T: Serialize + DeserializeOwned> List<T> {
     /// elements starting at the end/tail/right of the list (i.e. -1 is the last element and so forth).
     /// This function runs in O(n) over the distance of the provided index from the nearest
     /// end of the list i.e. getting the start or end of the list is O(1). If the specified element
-    /// does not exist or the index is out of range an error will be returned.
+    /// does not exist, i.e. the index is out of range, this fails with `Error::MissingElement`; see `get` to handle
+    /// this case as an `Option` instead.
     pub fn index(&mut self, index: i64) -> impl Future<Item = T, Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        let read_from = self.read_from;
         lazy(move || {
-            let data: Vec<u8> = redis::cmd("LINDEX")
-                .arg(key)
-                .arg(index)
-                .query(&mut *connection.write().unwrap())?;
-            Ok(serde_cbor::from_slice(data.as_slice())?)
+            let data: Option<Vec<u8>> =
+                connection.write().unwrap().read(read_from, |connection| {
+                    redis::cmd("LINDEX").arg(&key).arg(index).query(connection)
+                })?;
+            match data {
+                None => Err(Error::MissingElement { key, index }),
+                Some(data) => {
+                    let mut values =
+                        decode_elements(&connection, &key, &encoding, vec![(data, Some(index))])?;
+                    Ok(values.remove(0))
+                }
+            }
+        })
+    }
+    /// Gets the element from the list at the provided index, or `None` if the index is out of range. See `index` for
+    /// information on time complexity and the behaviour of the `index` argument.
+    pub fn get(&mut self, index: i64) -> impl Future<Item = Option<T>, Error = Error> {
+        let key = self.key.clone();
+        let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        let read_from = self.read_from;
+        lazy(move || {
+            let data: Option<Vec<u8>> =
+                connection.write().unwrap().read(read_from, |connection| {
+                    redis::cmd("LINDEX").arg(&key).arg(index).query(connection)
+                })?;
+            match data {
+                None => Ok(None),
+                Some(data) => {
+                    let mut values =
+                        decode_elements(&connection, &key, &encoding, vec![(data, Some(index))])?;
+                    Ok(Some(values.remove(0)))
+                }
+            }
         })
     }
     /// Sets the list element at `index` to `value`. See `index` for information on
-    /// time complexity and the behaviour of the `index` argument.
+    /// time complexity and the behaviour of the `index` argument. If the index is out of range, including if the list
+    /// is empty, this fails with `Error::IndexOutOfRange`.
     pub fn set_index(&mut self, index: i64, value: T) -> impl Future<Item = (), Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
         lazy(move || {
-            let _: String = redis::cmd("LSET")
-                .arg(key)
+            let result: Result<String, RedisError> = redis::cmd("LSET")
+                .arg(&key)
                 .arg(index)
-                .arg(serde_cbor::to_vec(&value)?)
-                .query(&mut *connection.write().unwrap())?;
-            Ok(())
+                .arg(encoding.encode(&value)?)
+                .query(&mut *connection.write().unwrap());
+            match result {
+                Err(ref err) if is_out_of_range(err) => Err(Error::IndexOutOfRange { key, index }),
+                result => result.map(|_| ()).map_err(Error::from),
+            }
         })
     }
     /// Returns elements of the list starting at `start` and stopping at `stop` which are zero-based indices
@@ -150,15 +475,23 @@ impl<T: Serialize + DeserializeOwned> List<T> {
     pub fn range(&mut self, start: i64, stop: i64) -> impl Future<Item = Vec<T>, Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        let read_from = self.read_from;
         lazy(move || {
-            let data: Vec<Vec<u8>> = redis::cmd("LRANGE")
-                .arg(key)
-                .arg(start)
-                .arg(stop)
-                .query(&mut *connection.write().unwrap())?;
-            data.iter()
-                .map(|data| serde_cbor::from_slice(data.as_slice()).map_err(|err| Error::from(err)))
-                .collect::<Result<Vec<T>, Error>>()
+            let data: Vec<Vec<u8>> = connection.write().unwrap().read(read_from, |connection| {
+                redis::cmd("LRANGE")
+                    .arg(&key)
+                    .arg(start)
+                    .arg(stop)
+                    .query(connection)
+            })?;
+            // The indices of the elements are only known if the range is counted from the head of the list.
+            let data = data
+                .into_iter()
+                .zip(0..)
+                .map(|(data, offset)| (data, Some(start + offset).filter(|_| start >= 0)))
+                .collect();
+            decode_elements(&connection, &key, &encoding, data)
         })
     }
     /// Trims the list to the specified range of values. See `range` for the manner in which
@@ -182,9 +515,10 @@ impl<T: Serialize + DeserializeOwned> List<T> {
     pub fn push_front(&mut self, item: T) -> impl Future<Item = (), Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
         lazy(move || {
-            let data: Vec<u8> = serde_cbor::to_vec(&item)?;
-            redis::cmd("RPUSH")
+            let data: Vec<u8> = encoding.encode(&item)?;
+            let _: () = redis::cmd("RPUSH")
                 .arg(key)
                 .arg(data)
                 .query(&mut *connection.write().unwrap())?;
@@ -196,9 +530,10 @@ impl<T: Serialize + DeserializeOwned> List<T> {
     pub fn push_back(&mut self, item: T) -> impl Future<Item = (), Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
         lazy(move || {
-            let data: Vec<u8> = serde_cbor::to_vec(&item)?;
-            redis::cmd("LPUSH")
+            let data: Vec<u8> = encoding.encode(&item)?;
+            let _: () = redis::cmd("LPUSH")
                 .arg(key)
                 .arg(data)
                 .query(&mut *connection.write().unwrap())?;
@@ -209,10 +544,11 @@ impl<T: Serialize + DeserializeOwned> List<T> {
     pub fn len(&mut self) -> impl Future<Item = u32, Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let read_from = self.read_from;
         lazy(move || {
-            let data: u32 = redis::cmd("LLEN")
-                .arg(key)
-                .query(&mut *connection.write().unwrap())?;
+            let data: u32 = connection.write().unwrap().read(read_from, |connection| {
+                redis::cmd("LLEN").arg(&key).query(connection)
+            })?;
             Ok(data)
         })
     }
@@ -221,11 +557,12 @@ impl<T: Serialize + DeserializeOwned> List<T> {
     pub fn remove(&mut self, count: u32, item: T) -> impl Future<Item = u32, Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
         lazy(move || {
             let data: u32 = redis::cmd("LREM")
                 .arg(key)
                 .arg(count)
-                .arg(serde_cbor::to_vec(&item)?)
+                .arg(encoding.encode(&item)?)
                 .query(&mut *connection.write().unwrap())?;
             Ok(data)
         })
@@ -236,14 +573,15 @@ impl<T: Serialize + DeserializeOwned> List<T> {
     pub fn insert_before(&mut self, pivot: T, value: T) -> impl Future<Item = bool, Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
         lazy(move || {
             let data: i64 = redis::cmd("LINSERT")
                 .arg(key)
                 .arg("BEFORE")
-                .arg(serde_cbor::to_vec(&pivot)?)
-                .arg(serde_cbor::to_vec(&value)?)
+                .arg(encoding.encode(&pivot)?)
+                .arg(encoding.encode(&value)?)
                 .query(&mut *connection.write().unwrap())?;
-            Ok(data != -1)
+            Ok(data > 0)
         })
     }
     /// Inserts `value` into the list after the first occurrence of `pivot`. This operation is O(N) over the number of elements
@@ -252,14 +590,408 @@ impl<T: Serialize + DeserializeOwned> List<T> {
     pub fn insert_after(&mut self, pivot: T, value: T) -> impl Future<Item = bool, Error = Error> {
         let key = self.key.clone();
         let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
         lazy(move || {
             let data: i64 = redis::cmd("LINSERT")
                 .arg(key)
                 .arg("AFTER")
-                .arg(serde_cbor::to_vec(&pivot)?)
-                .arg(serde_cbor::to_vec(&value)?)
+                .arg(encoding.encode(&pivot)?)
+                .arg(encoding.encode(&value)?)
                 .query(&mut *connection.write().unwrap())?;
-            Ok(data != -1)
+            Ok(data > 0)
         })
     }
-}
\ No newline at end of file
+    /// Returns the indices of elements equal to `item`. `rank` selects the match to begin from: 1 is the first match
+    /// from the head of the list, 2 the second and so on, while negative ranks count matches from the tail of the list, in which
+    /// case the indices are returned from the tail towards the head. At most `count` indices are returned, or every remaining
+    /// match if `count` is zero. A `rank` of zero is rejected by the server.
+    ///
+    /// This operation is O(N) over the number of elements traversed and requires redis 6.0.6 or later.
+    pub fn position(
+        &mut self,
+        item: T,
+        rank: i64,
+        count: u32,
+    ) -> impl Future<Item = Vec<u32>, Error = Error> {
+        let key = self.key.clone();
+        let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        let read_from = self.read_from;
+        lazy(move || {
+            let item = encoding.encode(&item)?;
+            let data: Vec<u32> = connection.write().unwrap().read(read_from, |connection| {
+                redis::cmd("LPOS")
+                    .arg(&key)
+                    .arg(item.as_slice())
+                    .arg("RANK")
+                    .arg(rank)
+                    .arg("COUNT")
+                    .arg(count)
+                    .query(connection)
+            })?;
+            Ok(data)
+        })
+    }
+    /// Returns true if the list contains an element equal to `item`. This operation is O(N) over the number of
+    /// elements traversed before `item` is encountered and requires redis 6.0.6 or later.
+    pub fn contains(&mut self, item: T) -> impl Future<Item = bool, Error = Error> {
+        let key = self.key.clone();
+        let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        let read_from = self.read_from;
+        lazy(move || {
+            let item = encoding.encode(&item)?;
+            let data: Option<u32> = connection.write().unwrap().read(read_from, |connection| {
+                redis::cmd("LPOS")
+                    .arg(&key)
+                    .arg(item.as_slice())
+                    .query(connection)
+            })?;
+            Ok(data.is_some())
+        })
+    }
+    /// Searches the list on the server for elements containing a field equal to `value`, returning the index and value of
+    /// the first `count` matching elements from the head of the list, or of every matching element if `count` is zero.
+    ///
+    /// `path` locates the field within each element: each segment names a field of a struct or key of a map, or an index
+    /// into a sequence, so `&["address", "lines", "0"]` designates the first line of the address of an element. Fields are
+    /// compared by their serialized representation, so `value` should have the same type as the field. Elements in which
+    /// the path does not exist do not match.
+    ///
+    /// This executes as a script that decodes each element on the server and is O(N) over the total size of the list.
+    pub fn find_by_field<V: Serialize>(
+        &mut self,
+        path: &[&str],
+        value: V,
+        count: u32,
+    ) -> impl Future<Item = Vec<(u32, T)>, Error = Error> {
+        let key = self.key.clone();
+        let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        let read_from = self.read_from;
+        let path: Vec<String> = path.iter().map(|segment| (*segment).to_owned()).collect();
+        lazy(move || {
+            let value = encode(&value)?;
+            let script = redis::Script::new(&lua::with_cbor(FIND_BY_FIELD_SCRIPT));
+            let data: Vec<(u32, Vec<u8>)> = connection
+                .write()
+                .unwrap()
+                .read(read_from, |connection| {
+                    script
+                        .key(&key)
+                        .arg(value.as_slice())
+                        .arg(count)
+                        .arg(path.as_slice())
+                        .invoke(connection)
+                })
+                .map_err(Error::script)?;
+            let indices: Vec<u32> = data.iter().map(|(index, _)| *index).collect();
+            let data = data
+                .into_iter()
+                .map(|(index, data)| (data, Some(i64::from(index))))
+                .collect();
+            let values = decode_elements(&connection, &key, &encoding, data)?;
+            Ok(indices.into_iter().zip(values).collect())
+        })
+    }
+    /// Atomically pops an element from the `from` end of this list and pushes it onto the `to` end of
+    /// `other`, returning the moved element or `None` if this list is empty. `other` may be this same list,
+    /// in which case the list is rotated. This operation is O(1).
+    ///
+    /// This uses LMOVE where available. On servers older than redis 6.2 it falls back to RPOPLPUSH,
+    /// which only supports moving from the front of this list to the back of `other`; other
+    /// combinations of ends will produce the server's error on such servers.
+    pub fn move_to(
+        &mut self,
+        other: &List<T>,
+        from: End,
+        to: End,
+    ) -> impl Future<Item = Option<T>, Error = Error> {
+        let key = self.key.clone();
+        let destination = other.key.clone();
+        let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        lazy(move || {
+            let mut connection = connection.write().unwrap();
+            connection.check_slots(&[&key, &destination])?;
+            let data = move_element(&mut connection, &key, &destination, from, to, None)?;
+            match data {
+                None => Ok(None),
+                Some(data) => Ok(Some(encoding.decode(&key, Some(from.index()), &data)?)),
+            }
+        })
+    }
+    /// Blocking variant of `move_to`. If this list is empty the connection blocks until an element
+    /// is pushed onto it by another client or `timeout` elapses, in which case `None` is returned. A timeout of zero
+    /// blocks indefinitely, and on servers without BLMOVE the timeout is rounded up to whole seconds.
+    ///
+    /// This uses BLMOVE where available, falling back to BRPOPLPUSH with the same restrictions as `move_to`.
+    pub fn move_to_blocking(
+        &mut self,
+        other: &List<T>,
+        from: End,
+        to: End,
+        timeout: Duration,
+    ) -> impl Future<Item = Option<T>, Error = Error> {
+        let key = self.key.clone();
+        let destination = other.key.clone();
+        let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        lazy(move || {
+            let mut connection = connection.write().unwrap();
+            connection.check_slots(&[&key, &destination])?;
+            let data = move_element(&mut connection, &key, &destination, from, to, Some(timeout))?;
+            match data {
+                None => Ok(None),
+                Some(data) => Ok(Some(encoding.decode(&key, Some(from.index()), &data)?)),
+            }
+        })
+    }
+    /// Converts this list into a capped list that holds at most `limit` elements. See `CappedList`.
+    pub fn with_capacity_limit(self, limit: u32) -> CappedList<T> {
+        CappedList::new(self, limit)
+    }
+    /// Returns a stream over the elements of the list from the first (head) to the last (tail), fetching `page_size`
+    /// elements at a time. Each page is O(S+N) as described for `range`.
+    ///
+    /// By default the stream fails with `Error::ConcurrentModification` if the length of the list changes between
+    /// pages, see `Iter::tolerate_modification` to permit this.
+    pub fn iter(&mut self, page_size: u32) -> Iter<T> {
+        Iter::new(
+            self.key.clone(),
+            self.connection.clone(),
+            self.read_from,
+            self.encoding.clone(),
+            page_size,
+            false,
+        )
+    }
+    /// Returns a stream over the elements of the list from the last (tail) to the first (head). See `iter`.
+    pub fn iter_rev(&mut self, page_size: u32) -> Iter<T> {
+        Iter::new(
+            self.key.clone(),
+            self.connection.clone(),
+            self.read_from,
+            self.encoding.clone(),
+            page_size,
+            true,
+        )
+    }
+    /// Returns a sink that pushes elements onto the provided end of the list in batches. Elements sent to the sink
+    /// are pushed in the order they are sent, i.e. sending a then b behaves as pushing a then pushing b.
+    pub fn sink(&mut self, end: End) -> ListSink<T> {
+        ListSink {
+            connection: self.connection.clone(),
+            key: self.key.clone(),
+            encoding: self.encoding.clone(),
+            end,
+            buffer: vec![],
+            batch_size: 128,
+            flush_interval: None,
+            deadline: None,
+            data: PhantomData,
+        }
+    }
+}
+
+/// A sink that buffers elements and pushes them onto one end of a List with a single variadic push per batch.
+///
+/// Buffered elements are pushed once the batch size is reached and when the sink is closed. Without a flush interval
+/// they are also pushed whenever the sink is flushed with `poll_complete`. With one, `poll_complete` instead waits until
+/// the interval has elapsed since the first of them was buffered, on a timer of the tokio runtime, so that a stream
+/// forwarded into the sink is pushed in batches even while it stalls; outside a runtime it pushes them at once.
+///
+/// Elements still buffered when the sink is dropped are discarded, so the sink must be closed once the last element has
+/// been sent, which `Sink::send_all` and `Stream::forward` do as they complete. Elements whose push fails remain
+/// buffered, so that flushing or closing the sink again retries them, and are likewise discarded if it is dropped.
+pub struct ListSink<T: Serialize> {
+    connection: Arc<RwLock<Connection>>,
+    key: String,
+    encoding: Encoding,
+    end: End,
+    buffer: Vec<Vec<u8>>,
+    batch_size: usize,
+    flush_interval: Option<Duration>,
+    deadline: Option<Delay>,
+    data: PhantomData<fn(T)>,
+}
+
+impl<T: Serialize> ListSink<T> {
+    /// Sets the maximum number of elements buffered before they are pushed. The default is 128.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+    /// Sets the maximum time for which elements are buffered. By default elements are only pushed when the batch is
+    /// full or the sink is flushed.
+    pub fn flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = Some(flush_interval);
+        self
+    }
+    /// Pushes every buffered element.
+    pub(crate) fn push_buffered(&mut self) -> Result<(), Error> {
+        self.deadline = None;
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let _: () = redis::cmd(self.end.push_command())
+            .arg(&self.key)
+            .arg(self.buffer.as_slice())
+            .query(&mut *self.connection.write().unwrap())?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<T: Serialize> Sink for ListSink<T> {
+    type SinkItem = T;
+    type SinkError = Error;
+
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        self.buffer.push(self.encoding.encode(&item)?);
+        if let (None, Some(interval)) = (&self.deadline, self.flush_interval) {
+            self.deadline = Some(Delay::new(Instant::now() + interval));
+        }
+        let interval_elapsed = self
+            .deadline
+            .as_ref()
+            .is_some_and(|deadline| deadline.deadline() <= Instant::now());
+        if self.buffer.len() >= self.batch_size || interval_elapsed {
+            self.push_buffered()?;
+        }
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        if let Some(ref mut deadline) = self.deadline {
+            // Without a timer the deadline fails, and the elements are pushed at once.
+            if let Ok(Async::NotReady) = deadline.poll() {
+                return Ok(Async::NotReady);
+            }
+        }
+        self.push_buffered()?;
+        Ok(Async::Ready(()))
+    }
+
+    fn close(&mut self) -> Poll<(), Self::SinkError> {
+        self.push_buffered()?;
+        Ok(Async::Ready(()))
+    }
+}
+
+/// A stream over the elements of a List that fetches them in pages of consecutive LRANGE windows.
+///
+/// Each page is read atomically together with the length of the list. Elements are addressed by
+/// offset from the end at which iteration began, so if elements are pushed or popped at that end or inserted or removed
+/// before the current offset while the stream is in progress, the following pages shift: elements may be skipped
+/// or yielded more than once. Since the detection of modification compares lengths it will not notice
+/// modifications that leave the length of the list unchanged, such as `set_index` or a push followed by a pop.
+pub struct Iter<T: DeserializeOwned> {
+    connection: Arc<RwLock<Connection>>,
+    key: String,
+    read_from: ReadFrom,
+    encoding: Encoding,
+    page_size: u32,
+    offset: u32,
+    reverse: bool,
+    tolerate_modification: bool,
+    len: Option<u32>,
+    buffer: VecDeque<T>,
+    done: bool,
+}
+
+impl<T: DeserializeOwned> Iter<T> {
+    fn new(
+        key: String,
+        connection: Arc<RwLock<Connection>>,
+        read_from: ReadFrom,
+        encoding: Encoding,
+        page_size: u32,
+        reverse: bool,
+    ) -> Self {
+        Iter {
+            connection,
+            key,
+            read_from,
+            encoding,
+            page_size: page_size.max(1),
+            offset: 0,
+            reverse,
+            tolerate_modification: false,
+            len: None,
+            buffer: VecDeque::new(),
+            done: false,
+        }
+    }
+    /// Permits the list to be modified while the stream is in progress. Rather than failing, the stream continues at
+    /// its current offset and ends at the first page shorter than the page size, with the shifting behaviour described
+    /// for `Iter`.
+    pub fn tolerate_modification(mut self) -> Self {
+        self.tolerate_modification = true;
+        self
+    }
+    fn fetch(&mut self) -> Result<(), Error> {
+        let start = i64::from(self.offset);
+        let stop = start + i64::from(self.page_size) - 1;
+        let (start, stop) = if self.reverse {
+            (-stop - 1, -start - 1)
+        } else {
+            (start, stop)
+        };
+        let key = &self.key;
+        let (data, len): (Vec<Vec<u8>>, u32) =
+            self.connection
+                .write()
+                .unwrap()
+                .read(self.read_from, |connection| {
+                    redis::pipe()
+                        .atomic()
+                        .cmd("LRANGE")
+                        .arg(key)
+                        .arg(start)
+                        .arg(stop)
+                        .cmd("LLEN")
+                        .arg(key)
+                        .query(connection)
+                })?;
+        match self.len {
+            Some(previous) if previous != len && !self.tolerate_modification => {
+                return Err(Error::ConcurrentModification {
+                    key: self.key.clone(),
+                })
+            }
+            _ => self.len = Some(len),
+        }
+        self.done = (data.len() as u32) < self.page_size;
+        self.offset += data.len() as u32;
+        // A reverse page ends at `stop` but starts after `start` if the list is shorter than the page.
+        let first = if self.reverse {
+            stop - data.len() as i64 + 1
+        } else {
+            start
+        };
+        let data = data
+            .into_iter()
+            .zip(first..)
+            .map(|(data, index)| (data, Some(index)))
+            .collect();
+        let mut page = decode_elements(&self.connection, &self.key, &self.encoding, data)?;
+        if self.reverse {
+            page.reverse();
+        }
+        self.buffer = page.into();
+        Ok(())
+    }
+}
+
+impl<T: DeserializeOwned> Stream for Iter<T> {
+    type Item = T;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        if self.buffer.is_empty() && !self.done {
+            self.fetch()?;
+        }
+        Ok(Async::Ready(self.buffer.pop_front()))
+    }
+}
@@ -0,0 +1,178 @@
+/// Lua helpers for inspecting CBOR-encoded elements on the server. These define a local `cbor` table with:
+///
+/// * `cbor.skip(s, i)` returning the position following the item at position `i` of `s`,
+/// * `cbor.child(s, i, segment)` returning the position of the named field or index of the map or array at `i`,
+/// * `cbor.locate(s, path)` returning the position of the item designated by a table of path segments within an element,
+///   skipping the envelope of a versioned element, and
+/// * `cbor.decode(s, i)` decoding the scalar at `i` as a Lua number, string or boolean.
+///
+/// Positions are one-based as is conventional in Lua, and `nil` is returned where an item does not exist or is not a scalar.
+const CBOR: &str = r"
+local cbor = {}
+
+local function header(s, i)
+    local b = string.byte(s, i)
+    local major, info = math.floor(b / 32), b % 32
+    if info < 24 then
+        return major, info, i + 1
+    elseif info == 31 then
+        return major, -1, i + 1
+    end
+    local size = 2 ^ (info - 24)
+    local value = 0
+    for j = 1, size do
+        value = value * 256 + string.byte(s, i + j)
+    end
+    return major, value, i + size + 1
+end
+
+local function skip_indefinite(s, i)
+    while string.byte(s, i) ~= 255 do
+        i = cbor.skip(s, i)
+    end
+    return i + 1
+end
+
+function cbor.skip(s, i)
+    local major, value, i = header(s, i)
+    if major == 2 or major == 3 then
+        if value < 0 then
+            return skip_indefinite(s, i)
+        end
+        return i + value
+    elseif major == 4 or major == 5 then
+        if value < 0 then
+            return skip_indefinite(s, i)
+        end
+        if major == 5 then
+            value = value * 2
+        end
+        for _ = 1, value do
+            i = cbor.skip(s, i)
+        end
+        return i
+    elseif major == 6 then
+        return cbor.skip(s, i)
+    end
+    return i
+end
+
+function cbor.child(s, i, segment)
+    local major, value, i = header(s, i)
+    if major == 6 then
+        return cbor.child(s, i, segment)
+    elseif major == 5 then
+        local remaining = value
+        while remaining ~= 0 and string.byte(s, i) ~= 255 do
+            local key_major, key_length, key_start = header(s, i)
+            local key_end = cbor.skip(s, i)
+            if key_major == 3 and key_length >= 0 and string.sub(s, key_start, key_end - 1) == segment then
+                return key_end
+            end
+            i = cbor.skip(s, key_end)
+            remaining = remaining - 1
+        end
+    elseif major == 4 then
+        local index = tonumber(segment)
+        if index == nil or (value >= 0 and index >= value) then
+            return nil
+        end
+        for _ = 1, index do
+            if string.byte(s, i) == 255 then
+                return nil
+            end
+            i = cbor.skip(s, i)
+        end
+        if string.byte(s, i) ~= 255 then
+            return i
+        end
+    end
+    return nil
+end
+
+function cbor.locate(s, path)
+    local i = 1
+    if string.byte(s, 1) == 0x5c then
+        i = 6
+    end
+    for _, segment in ipairs(path) do
+        i = cbor.child(s, i, segment)
+        if i == nil then
+            return nil
+        end
+    end
+    return i
+end
+
+local function float(s, i, size)
+    local high = 0
+    for j = 0, math.min(size, 4) - 1 do
+        high = high * 256 + string.byte(s, i + j)
+    end
+    local low = 0
+    for j = 4, size - 1 do
+        low = low * 256 + string.byte(s, i + j)
+    end
+    local exponent_bits, mantissa_bits = 5, 10
+    if size == 4 then
+        exponent_bits, mantissa_bits = 8, 23
+    elseif size == 8 then
+        exponent_bits, mantissa_bits = 11, 52
+    end
+    local high_bits = math.min(size, 4) * 8
+    local sign = 1
+    if high >= 2 ^ (high_bits - 1) then
+        sign, high = -1, high - 2 ^ (high_bits - 1)
+    end
+    local high_mantissa_bits = mantissa_bits - (size - math.min(size, 4)) * 8
+    local exponent = math.floor(high / 2 ^ high_mantissa_bits)
+    local mantissa = (high % 2 ^ high_mantissa_bits) * 2 ^ (mantissa_bits - high_mantissa_bits) + low
+    local bias = 2 ^ (exponent_bits - 1) - 1
+    if exponent == 0 then
+        return sign * mantissa * 2 ^ (1 - bias - mantissa_bits)
+    elseif exponent == 2 ^ exponent_bits - 1 then
+        if mantissa == 0 then
+            return sign * math.huge
+        end
+        return nil
+    end
+    return sign * (1 + mantissa / 2 ^ mantissa_bits) * 2 ^ (exponent - bias)
+end
+
+function cbor.decode(s, i)
+    local info = string.byte(s, i) % 32
+    local major, value, j = header(s, i)
+    if major == 0 then
+        return value
+    elseif major == 1 then
+        return -1 - value
+    elseif major == 2 or major == 3 then
+        if value >= 0 then
+            return string.sub(s, j, j + value - 1)
+        end
+        local chunks = {}
+        while string.byte(s, j) ~= 255 do
+            local _, length, start = header(s, j)
+            table.insert(chunks, string.sub(s, start, start + length - 1))
+            j = start + length
+        end
+        return table.concat(chunks)
+    elseif major == 6 then
+        return cbor.decode(s, j)
+    elseif major == 7 then
+        if info == 20 then
+            return false
+        elseif info == 21 then
+            return true
+        elseif info >= 25 and info <= 27 then
+            return float(s, i + 1, 2 ^ (info - 24))
+        end
+    end
+    return nil
+end
+";
+
+/// Prepends the CBOR helpers to the provided script body.
+pub(crate) fn with_cbor(body: &str) -> String {
+    format!("{}{}", CBOR, body)
+}
@@ -1,7 +1,14 @@
+/// A capped list that evicts elements to remain within a fixed length.
+pub mod capped;
 /// A redis-backed list collection.
 pub mod list;
+pub(crate) mod lua;
+/// A reliable queue built on redis-backed lists.
+pub mod queue;
+/// Server-side sorting of redis-backed lists.
+pub mod sort;
 
-use redis::{Connection, ConnectionLike, RedisError};
+use redis::ConnectionLike;
 
 use futures::{lazy, task::AtomicTask, Async, Future, Poll, Stream};
 
@@ -9,13 +16,18 @@ use std::{
     fmt::Debug,
     str::FromStr,
     sync::{Arc, RwLock},
+    thread,
+    time::Duration,
 };
 
-use crossbeam_channel::{unbounded, Receiver, TryRecvError};
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, TryRecvError};
 
-use crate::Error;
+use crate::{connection::Client, Connection, Error, KeyType};
 
-pub use list::List;
+pub use capped::CappedList;
+pub use list::{End, List};
+pub use queue::{Receipt, ReliableQueue};
+pub use sort::Sort;
 
 /// Generic notification events that apply to all types of keys.
 #[derive(Debug, Clone, Copy)]
@@ -44,35 +56,88 @@ impl<T: Send + Debug + FromStr<Err = Error>> FromStr for WatchEvent<T> {
     }
 }
 
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
 /// A watcher that provides a stream of update notifications for a redis key.
+///
+/// Notifications are received on a dedicated connection and background thread. If the connection fails, or the master
+/// changes in a Sentinel-monitored deployment, the subscription is re-established on the server now holding the key;
+/// notifications of modifications made in the meantime are lost.
 pub struct Watcher<T: Send + Debug> {
     receiver: Receiver<Result<Option<WatchEvent<T>>, Error>>,
     task: Arc<AtomicTask>,
 }
 
-impl<'a, T: Send + Debug + FromStr<Err = Error> + 'static> Watcher<T> {
+impl<T: Send + Debug + FromStr<Err = Error> + 'static> Watcher<T> {
     fn watch(conn: Arc<RwLock<Connection>>, key: String) -> Watcher<T> {
         let (sender, receiver) = unbounded();
         let task = Arc::new(AtomicTask::new());
         let task_cloned = task.clone();
-        tokio::spawn(lazy(move || {
-            let mut conn = conn.write().unwrap();
-            let db = conn.get_db();
-            let mut pubsub = conn.as_pubsub();
-            pubsub
-                .subscribe(format!("__keyspace@{}__:{}", db, key))
-                .unwrap();
-            loop {
-                let message = pubsub.get_message().unwrap();
-                let payload: String = message.get_payload().unwrap();
-                let event = payload.parse::<WatchEvent<T>>().map(|event| Some(event));
-                sender.send(event).unwrap();
-                task_cloned.notify();
+        let (client, db) = {
+            let conn = conn.write().unwrap();
+            (conn.client().clone(), conn.get_db())
+        };
+        thread::spawn(move || {
+            let channel = format!("__keyspace@{}__:{}", db, key);
+            if let Client::Memory(ref memory) = client {
+                let events = memory.subscribe(channel.as_bytes());
+                while Arc::strong_count(&task_cloned) > 1 {
+                    match events.recv_timeout(POLL_INTERVAL) {
+                        Ok(event) => {
+                            let event = String::from_utf8_lossy(&event).parse::<WatchEvent<T>>();
+                            if sender.send(event.map(Some)).is_err() {
+                                return;
+                            }
+                            task_cloned.notify();
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                return;
             }
-            Ok(())
-        }));
-        let watcher = Watcher { receiver, task };
-        watcher
+            // The watcher is dropped once this thread holds the only reference to its task.
+            while Arc::strong_count(&task_cloned) > 1 {
+                let (mut connection, generation) = match client.key_connection(&key) {
+                    Ok(connection) => connection,
+                    Err(_) => {
+                        thread::sleep(RECONNECT_INTERVAL);
+                        continue;
+                    }
+                };
+                let mut pubsub = connection.as_pubsub();
+                if pubsub.subscribe(&channel).is_err()
+                    || pubsub.set_read_timeout(Some(POLL_INTERVAL)).is_err()
+                {
+                    thread::sleep(RECONNECT_INTERVAL);
+                    continue;
+                }
+                while Arc::strong_count(&task_cloned) > 1 {
+                    match pubsub.get_message() {
+                        Ok(message) => {
+                            let event = message
+                                .get_payload::<String>()
+                                .map_err(Error::from)
+                                .and_then(|payload| payload.parse::<WatchEvent<T>>())
+                                .map(Some);
+                            if sender.send(event).is_err() {
+                                return;
+                            }
+                            task_cloned.notify();
+                        }
+                        Err(ref err) if err.is_timeout() => {
+                            if client.generation() != generation {
+                                break;
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                }
+            }
+        });
+
+        Watcher { receiver, task }
     }
 }
 
@@ -98,9 +163,19 @@ impl<T: Send + Debug> Stream for Watcher<T> {
 }
 
 /// A redis-backed data structure.
-pub trait Collection<'a>: Key<<Self as Collection<'a>>::WatchEvent> {
+pub trait Collection:
+    Key<<Self as Collection>::WatchEvent> + Clone + Send + Sync + 'static
+{
+    #[doc(hidden)]
+    fn get(key: String, connection: Connection) -> Result<Self, Error>
+    where
+        Self: Sized;
+    #[doc(hidden)]
+    fn type_prefix() -> &'static str
+    where
+        Self: Sized;
     #[doc(hidden)]
-    fn get(key: String, connection: Connection) -> Result<Self, RedisError>
+    fn key_type() -> KeyType
     where
         Self: Sized;
     #[doc(hidden)]
@@ -119,16 +194,16 @@ pub trait Key<T: Send + Debug> {
     fn watch(&self) -> Box<dyn Future<Item = Watcher<T>, Error = Error> + Send>;
 }
 
-impl<'a, T> Key<T::WatchEvent> for T
+impl<T> Key<T::WatchEvent> for T
 where
-    T: Collection<'a>,
+    T: Collection,
 {
     /// Removes the collection from the database. This operation is O(1).
     fn remove(self) -> Box<dyn Future<Item = (), Error = Error> + Send> {
         let key = self.key();
         let connection = self.connection();
         Box::new(lazy(move || {
-            let _: String = redis::cmd("DEL")
+            let _: u32 = redis::cmd("DEL")
                 .arg(key)
                 .query(&mut *connection.write().unwrap())?;
             Ok(())
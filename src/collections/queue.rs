@@ -0,0 +1,215 @@
+use super::{
+    list::{move_element, End},
+    List,
+};
+use futures::{lazy, Future};
+
+use crate::Error;
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub(crate) const REQUEUE_SCRIPT: &str = r"
+local now = tonumber(ARGV[1])
+local timeout = tonumber(ARGV[2])
+local requeued = 0
+for _, item in ipairs(redis.call('LRANGE', KEYS[2], 0, -1)) do
+    local deadline = redis.call('ZSCORE', KEYS[3], item)
+    if not deadline then
+        redis.call('ZADD', KEYS[3], now + timeout, item)
+    elseif tonumber(deadline) <= now then
+        redis.call('LREM', KEYS[2], 1, item)
+        redis.call('ZREM', KEYS[3], item)
+        redis.call('RPUSH', KEYS[1], item)
+        requeued = requeued + 1
+    end
+end
+return requeued
+";
+
+fn now_millis() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    now.as_secs() * 1000 + u64::from(now.subsec_millis())
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
+/// An element taken from a `ReliableQueue`, holding both the element and the exact bytes stored for it on the
+/// processing list.
+///
+/// The receipt is passed to `ack` to acknowledge the element. Since the stored bytes are removed as they are, the
+/// acknowledgement does not rely on the element encoding to the same bytes again.
+#[derive(Debug, Clone)]
+pub struct Receipt<T> {
+    item: T,
+    data: Vec<u8>,
+}
+
+impl<T> Receipt<T> {
+    /// Returns the element that was taken.
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+    /// Returns the element that was taken, discarding the receipt.
+    pub fn into_item(self) -> T {
+        self.item
+    }
+}
+
+/// A reliable work queue built on a pair of redis-backed lists providing at-least-once processing.
+///
+/// Elements are pushed onto the back of the queue list and taken from its front. Taking an element atomically
+/// moves it onto a processing list where it remains until it is acknowledged with `ack`. Each taken element
+/// is leased for the visibility timeout of the queue, and `requeue_expired` returns elements whose lease
+/// has expired (i.e. those left behind by a consumer that crashed or stalled) to the front of the queue
+/// so that they are delivered again.
+///
+/// Leases are tracked in a sorted set stored alongside the processing list. Identical elements
+/// share a single lease, so taking several equal elements at once may extend the lease of the earlier ones.
+pub struct ReliableQueue<T: Serialize + DeserializeOwned> {
+    queue: List<T>,
+    processing: List<T>,
+    visibility_timeout: Duration,
+}
+
+impl<T: Serialize + DeserializeOwned> Clone for ReliableQueue<T> {
+    fn clone(&self) -> Self {
+        ReliableQueue {
+            queue: self.queue.clone(),
+            processing: self.processing.clone(),
+            visibility_timeout: self.visibility_timeout,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> ReliableQueue<T> {
+    /// Creates a reliable queue that takes elements from `queue` and holds them in `processing` until they are
+    /// acknowledged. Elements that remain unacknowledged for longer than `visibility_timeout` are
+    /// considered abandoned and are requeued by `requeue_expired`.
+    ///
+    /// On a cluster both lists must hash to the same slot, since taking an element moves it between them atomically.
+    /// Name the processing list with the hash tag of the queue, e.g. `{jobs}:processing` for the queue `jobs`, whose
+    /// name the namespace of a cluster database wraps in the hash tag `{jobs}`. Otherwise taking and requeueing elements
+    /// fail with `Error::CrossSlot`.
+    pub fn new(queue: List<T>, processing: List<T>, visibility_timeout: Duration) -> Self {
+        ReliableQueue {
+            queue,
+            processing,
+            visibility_timeout,
+        }
+    }
+    fn leases(&self) -> String {
+        format!("{}:leases", self.processing.key)
+    }
+    /// Pushes an element onto the back of the queue. This operation is O(1).
+    pub fn push(&mut self, item: T) -> impl Future<Item = (), Error = Error> {
+        self.queue.push_back(item)
+    }
+    /// Takes the element at the front of the queue, moving it onto the processing list and leasing it for the
+    /// visibility timeout of the queue. Returns a receipt for the element to be passed to `ack`, or `None` if the
+    /// queue is empty. This operation is O(log(N)) over the number of elements being processed.
+    pub fn take(&mut self) -> impl Future<Item = Option<Receipt<T>>, Error = Error> {
+        self.take_with_timeout(None)
+    }
+    /// Blocking variant of `take`. If the queue is empty the queue's connection blocks until an element is
+    /// pushed or `timeout` elapses, in which case `None` is returned. See `List::move_to_blocking` for the
+    /// behaviour of `timeout`.
+    ///
+    /// Acknowledgements are performed over the connection of the processing list and are not held up by a
+    /// blocked `take_blocking`.
+    pub fn take_blocking(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Item = Option<Receipt<T>>, Error = Error> {
+        self.take_with_timeout(Some(timeout))
+    }
+    fn take_with_timeout(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> impl Future<Item = Option<Receipt<T>>, Error = Error> {
+        let key = self.queue.key.clone();
+        let processing = self.processing.key.clone();
+        let leases = self.leases();
+        let visibility_timeout = self.visibility_timeout;
+        let connection = self.queue.connection.clone();
+        let encoding = self.queue.encoding.clone();
+        lazy(move || {
+            let mut connection = connection.write().unwrap();
+            connection.check_slots(&[&key, &processing, &leases])?;
+            let data = move_element(
+                &mut connection,
+                &key,
+                &processing,
+                End::Front,
+                End::Back,
+                timeout,
+            )?;
+            match data {
+                None => Ok(None),
+                Some(data) => {
+                    let _: () = redis::cmd("ZADD")
+                        .arg(leases)
+                        .arg(now_millis() + millis(visibility_timeout))
+                        .arg(data.as_slice())
+                        .query(&mut *connection)?;
+                    let item = encoding.decode(&key, Some(End::Front.index()), &data)?;
+                    Ok(Some(Receipt { item, data }))
+                }
+            }
+        })
+    }
+    /// Acknowledges that processing of the element taken with `receipt` has completed, removing it from the processing
+    /// list and releasing its lease. Returns false if the element was not being processed, which may occur if its lease
+    /// expired and it was requeued. This operation is O(N) over the number of elements being processed.
+    pub fn ack(&mut self, receipt: &Receipt<T>) -> impl Future<Item = bool, Error = Error> {
+        let processing = self.processing.key.clone();
+        let leases = self.leases();
+        let connection = self.processing.connection.clone();
+        let data = receipt.data.clone();
+        lazy(move || {
+            let mut connection = connection.write().unwrap();
+            connection.check_slots(&[&processing, &leases])?;
+            let (removed, _): (u32, u32) = redis::pipe()
+                .atomic()
+                .cmd("LREM")
+                .arg(processing)
+                .arg(1)
+                .arg(data.as_slice())
+                .cmd("ZREM")
+                .arg(leases)
+                .arg(data.as_slice())
+                .query(&mut *connection)?;
+            Ok(removed != 0)
+        })
+    }
+    /// Returns every element whose lease has expired from the processing list to the front of the queue, returning
+    /// the number of elements requeued. Elements found on the processing list without a lease, i.e. those
+    /// taken by a consumer that crashed between taking and leasing them, are leased from the time of the call.
+    ///
+    /// This should be invoked periodically, for example from a `tokio::timer::Interval`. It executes
+    /// atomically as a script and is O(N log(N)) over the number of elements being processed.
+    pub fn requeue_expired(&mut self) -> impl Future<Item = u32, Error = Error> {
+        let key = self.queue.key.clone();
+        let processing = self.processing.key.clone();
+        let leases = self.leases();
+        let visibility_timeout = self.visibility_timeout;
+        let connection = self.processing.connection.clone();
+        lazy(move || {
+            let mut connection = connection.write().unwrap();
+            connection.check_slots(&[&key, &processing, &leases])?;
+            let requeued: u32 = redis::Script::new(REQUEUE_SCRIPT)
+                .key(key)
+                .key(processing)
+                .key(leases)
+                .arg(now_millis())
+                .arg(millis(visibility_timeout))
+                .invoke(&mut *connection)
+                .map_err(Error::script)?;
+            Ok(requeued)
+        })
+    }
+}
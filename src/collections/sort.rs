@@ -0,0 +1,252 @@
+use super::{lua, List};
+use futures::{lazy, Future};
+
+use crate::{Connection, Error};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub(crate) const SORT_BY_KEY_SCRIPT: &str = r"
+local descending = ARGV[1] == '1'
+local path = {unpack(ARGV, 2)}
+local keyed = {}
+for index, element in ipairs(redis.call('LRANGE', KEYS[1], 0, -1)) do
+    local i = cbor.locate(element, path)
+    local key = nil
+    if i ~= nil then
+        key = cbor.decode(element, i)
+    end
+    if type(key) == 'boolean' then
+        key = key and 1 or 0
+    end
+    keyed[index] = {key, element, index}
+end
+local rank = {['nil'] = 0, number = 1, string = 2}
+table.sort(keyed, function(a, b)
+    local a_rank, b_rank = rank[type(a[1])], rank[type(b[1])]
+    if a_rank ~= b_rank then
+        if descending then
+            return a_rank > b_rank
+        end
+        return a_rank < b_rank
+    elseif a[1] ~= nil and a[1] ~= b[1] then
+        if descending then
+            return a[1] > b[1]
+        end
+        return a[1] < b[1]
+    end
+    return a[3] < b[3]
+end)
+local sorted = {}
+for i, entry in ipairs(keyed) do
+    sorted[i] = entry[2]
+end
+if KEYS[2] then
+    redis.call('DEL', KEYS[2])
+    for i = 1, #sorted, 1000 do
+        redis.call('RPUSH', KEYS[2], unpack(sorted, i, math.min(i + 999, #sorted)))
+    end
+    return #sorted
+end
+return sorted
+";
+
+/// Options for a server-side sort of a List with the redis SORT command.
+///
+/// By default elements are compared as numbers, which fails for elements of a List since they are stored
+/// serialized. Either compare elements by the raw bytes of their serialized form with `alpha`, or, more
+/// usefully, by weights stored in external keys with `by`. To order elements by a field of the elements
+/// themselves, see `List::sort_by_key`.
+#[derive(Debug, Clone, Default)]
+pub struct Sort {
+    by: Option<String>,
+    limit: Option<(u32, u32)>,
+    descending: bool,
+    alpha: bool,
+}
+
+impl Sort {
+    /// Creates options for an ascending numeric sort of every element.
+    pub fn new() -> Self {
+        Sort::default()
+    }
+    /// Sorts elements by the values of external keys given by `pattern`, in which the first `*` is substituted
+    /// with each serialized element. `->` may be used to designate a field of a hash, i.e. `weight_*->value`.
+    /// The pattern `nosort` skips sorting entirely, which is useful in combination with `limit`.
+    ///
+    /// On a cluster the pattern must contain a hash tag that does not include the `*` and hashes to the same slot as
+    /// the list, e.g. `{people}:weight_*` for the list `{people}`, so that every key it names resides on the same node.
+    /// Sorting with any other pattern fails with `Error::CrossSlot`. The same applies to the patterns of `sorted_get`.
+    pub fn by(mut self, pattern: &str) -> Self {
+        self.by = Some(pattern.to_owned());
+        self
+    }
+    /// Returns only `count` elements starting at `offset` of the sorted elements.
+    pub fn limit(mut self, offset: u32, count: u32) -> Self {
+        self.limit = Some((offset, count));
+        self
+    }
+    /// Sorts elements in descending rather than ascending order.
+    pub fn descending(mut self) -> Self {
+        self.descending = true;
+        self
+    }
+    /// Compares elements (or external weights) lexicographically rather than numerically.
+    pub fn alpha(mut self) -> Self {
+        self.alpha = true;
+        self
+    }
+    /// Ensures that the keys named by the `BY` pattern and `get` patterns may be accessed along with `key`, see
+    /// `Connection::check_pattern`.
+    fn check_patterns(
+        &self,
+        connection: &Connection,
+        key: &str,
+        get: &[String],
+    ) -> Result<(), Error> {
+        self.by
+            .iter()
+            .chain(get)
+            .try_for_each(|pattern| connection.check_pattern(key, pattern))
+    }
+    fn command(&self, key: &str, get: &[&str], store: Option<&str>) -> redis::Cmd {
+        let mut command = redis::cmd("SORT");
+        command.arg(key);
+        if let Some(ref by) = self.by {
+            command.arg("BY").arg(by.as_str());
+        }
+        if let Some((offset, count)) = self.limit {
+            command.arg("LIMIT").arg(offset).arg(count);
+        }
+        for pattern in get {
+            command.arg("GET").arg(*pattern);
+        }
+        command.arg(if self.descending { "DESC" } else { "ASC" });
+        if self.alpha {
+            command.arg("ALPHA");
+        }
+        if let Some(store) = store {
+            command.arg("STORE").arg(store);
+        }
+        command
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> List<T> {
+    /// Returns the elements of the list sorted on the server as specified by `sort`. The list itself is unchanged.
+    /// This operation is O(N+M*log(M)) where N is the length of the list and M the number of elements returned.
+    pub fn sorted(&mut self, sort: &Sort) -> impl Future<Item = Vec<T>, Error = Error> {
+        let key = self.key.clone();
+        let sort = sort.clone();
+        let command = sort.command(&key, &[], None);
+        let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        let read_from = self.read_from();
+        lazy(move || {
+            let mut connection = connection.write().unwrap();
+            sort.check_patterns(&connection, &key, &[])?;
+            let data: Vec<Vec<u8>> =
+                connection.read(read_from, |connection| command.query(connection))?;
+            data.iter()
+                .map(|data| encoding.decode(&key, None, data))
+                .collect::<Result<Vec<T>, Error>>()
+        })
+    }
+    /// Sorts the elements of the list as specified by `sort` and returns, for each sorted element, the raw values of the
+    /// external keys given by each of `patterns`, which are substituted in the same manner as `Sort::by`. The pattern `#`
+    /// retrieves the serialized element itself. Values are `None` where the external key does not exist.
+    pub fn sorted_get(
+        &mut self,
+        sort: &Sort,
+        patterns: &[&str],
+    ) -> impl Future<Item = Vec<Vec<Option<Vec<u8>>>>, Error = Error> {
+        let key = self.key.clone();
+        let sort = sort.clone();
+        let command = sort.command(&key, patterns, None);
+        let patterns: Vec<String> = patterns
+            .iter()
+            .map(|pattern| (*pattern).to_owned())
+            .collect();
+        let connection = self.connection.clone();
+        let read_from = self.read_from();
+        let width = patterns.len().max(1);
+        lazy(move || {
+            let mut connection = connection.write().unwrap();
+            sort.check_patterns(&connection, &key, &patterns)?;
+            let data: Vec<Option<Vec<u8>>> =
+                connection.read(read_from, |connection| command.query(connection))?;
+            Ok(data.chunks(width).map(<[_]>::to_vec).collect())
+        })
+    }
+    /// Sorts the elements of the list as specified by `sort` and stores them in `destination`, replacing its contents,
+    /// which may be this list itself. Returns the number of elements stored.
+    pub fn sort_into(
+        &mut self,
+        sort: &Sort,
+        destination: &List<T>,
+    ) -> impl Future<Item = u32, Error = Error> {
+        let key = self.key.clone();
+        let destination = destination.key.clone();
+        let sort = sort.clone();
+        let command = sort.command(&key, &[], Some(&destination));
+        let connection = self.connection.clone();
+        lazy(move || {
+            let mut connection = connection.write().unwrap();
+            connection.check_slots(&[&key, &destination])?;
+            sort.check_patterns(&connection, &key, &[])?;
+            let data: u32 = command.query(&mut *connection)?;
+            Ok(data)
+        })
+    }
+    /// Returns the elements of the list sorted on the server by the field designated by `path` (see `find_by_field`),
+    /// which must hold a number, string or boolean. In ascending order elements without the field come first, followed
+    /// by numbers (and booleans, as 0 and 1) and then strings; `descending` reverses this, so that strings come first and
+    /// elements without the field last. Elements with equal keys retain their relative order in either direction.
+    ///
+    /// This executes as a script that decodes each element on the server and is O(N*log(N)) over the length of the list.
+    pub fn sort_by_key(
+        &mut self,
+        path: &[&str],
+        descending: bool,
+    ) -> impl Future<Item = Vec<T>, Error = Error> {
+        let key = self.key.clone();
+        let connection = self.connection.clone();
+        let encoding = self.encoding.clone();
+        let path: Vec<String> = path.iter().map(|segment| (*segment).to_owned()).collect();
+        lazy(move || {
+            let data: Vec<Vec<u8>> = redis::Script::new(&lua::with_cbor(SORT_BY_KEY_SCRIPT))
+                .key(&key)
+                .arg(if descending { 1 } else { 0 })
+                .arg(path)
+                .invoke(&mut *connection.write().unwrap())
+                .map_err(Error::script)?;
+            data.iter()
+                .map(|data| encoding.decode(&key, None, data))
+                .collect::<Result<Vec<T>, Error>>()
+        })
+    }
+    /// Sorts the elements of the list as `sort_by_key` does and atomically stores them in `destination`, replacing its
+    /// contents, which may be this list itself. Returns the number of elements stored.
+    pub fn sort_by_key_into(
+        &mut self,
+        path: &[&str],
+        descending: bool,
+        destination: &List<T>,
+    ) -> impl Future<Item = u32, Error = Error> {
+        let key = self.key.clone();
+        let destination = destination.key.clone();
+        let connection = self.connection.clone();
+        let path: Vec<String> = path.iter().map(|segment| (*segment).to_owned()).collect();
+        lazy(move || {
+            let mut connection = connection.write().unwrap();
+            connection.check_slots(&[&key, &destination])?;
+            let data: u32 = redis::Script::new(&lua::with_cbor(SORT_BY_KEY_SCRIPT))
+                .key(key)
+                .key(destination)
+                .arg(if descending { 1 } else { 0 })
+                .arg(path)
+                .invoke(&mut *connection)
+                .map_err(Error::script)?;
+            Ok(data)
+        })
+    }
+}
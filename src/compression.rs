@@ -0,0 +1,145 @@
+use crate::{Codec, Plain};
+
+use std::io;
+
+/// The headers marking elements compressed with each algorithm, which are reserved initial bytes in CBOR.
+const ZSTD: u8 = 0x1c;
+const LZ4: u8 = 0x1d;
+const SNAPPY: u8 = 0x1e;
+
+/// An algorithm by which `Compressed` compresses elements. Each is available with the feature of the same name, i.e.
+/// `zstd`, `lz4` or `snappy`, and compression is available once at least one of them is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// Zstandard at the provided compression level, where 0 selects the default level.
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+    /// LZ4 in its block format.
+    #[cfg(feature = "lz4")]
+    Lz4,
+    /// Snappy in its raw format.
+    #[cfg(feature = "snappy")]
+    Snappy,
+}
+
+impl Compression {
+    fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut compressed = Vec::with_capacity(data.len() / 2 + 2);
+        match self {
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(level) => {
+                compressed.push(ZSTD);
+                compressed.extend(zstd::encode_all(data, level)?);
+            }
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => {
+                compressed.push(LZ4);
+                compressed.extend(lz4_flex::compress_prepend_size(data));
+            }
+            #[cfg(feature = "snappy")]
+            Compression::Snappy => {
+                compressed.push(SNAPPY);
+                compressed.extend(
+                    snap::raw::Encoder::new()
+                        .compress_vec(data)
+                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?,
+                );
+            }
+        }
+        Ok(compressed)
+    }
+}
+
+/// Decompresses an element compressed with the algorithm designated by `header`.
+fn decompress(header: u8, data: &[u8]) -> io::Result<Vec<u8>> {
+    match header {
+        #[cfg(feature = "zstd")]
+        ZSTD => zstd::decode_all(data),
+        #[cfg(feature = "lz4")]
+        LZ4 => lz4_flex::decompress_size_prepended(data)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        #[cfg(feature = "snappy")]
+        SNAPPY => snap::raw::Decoder::new()
+            .decompress_vec(data)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        _ => {
+            let (name, feature) = match header {
+                ZSTD => ("Zstandard", "zstd"),
+                LZ4 => ("LZ4", "lz4"),
+                _ => ("Snappy", "snappy"),
+            };
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "the element is compressed with {}, which requires the {} feature",
+                    name, feature
+                ),
+            ))
+        }
+    }
+}
+
+/// A codec that compresses the elements of collections that are at least a threshold size.
+///
+/// Compressed elements are marked by a header byte designating the algorithm, while smaller elements, and those that
+/// compression would not shrink, are stored as they are. Elements are decompressed according to their header
+/// regardless of the algorithm the codec compresses with, provided its feature is enabled, and elements without a
+/// header, including those written before compression was enabled, are read as they are.
+///
+/// Equal elements compress identically, so equality-based operations such as `List::remove` continue to work.
+/// Operations that inspect elements on the server, such as `List::find_by_field` and `List::sort_by_key`, cannot
+/// read compressed elements.
+#[derive(Debug, Clone)]
+pub struct Compressed<C = Plain> {
+    inner: C,
+    compression: Compression,
+    threshold: usize,
+}
+
+impl Compressed {
+    /// Creates a codec compressing elements with `compression`.
+    pub fn new(compression: Compression) -> Self {
+        Compressed::wrap(Plain, compression)
+    }
+}
+
+impl<C: Codec> Compressed<C> {
+    /// Creates a codec compressing elements with `compression` once they have been encoded by `inner`.
+    pub fn wrap(inner: C, compression: Compression) -> Self {
+        Compressed {
+            inner,
+            compression,
+            threshold: 256,
+        }
+    }
+    /// Sets the size in bytes, once encoded by the inner codec, below which elements are not compressed. The default
+    /// is 256.
+    pub fn threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+impl<C: Codec> Codec for Compressed<C> {
+    fn encode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let data = self.inner.encode(data)?;
+        if data.len() < self.threshold {
+            return Ok(data);
+        }
+        let compressed = self.compression.compress(&data)?;
+        Ok(if compressed.len() < data.len() {
+            compressed
+        } else {
+            data
+        })
+    }
+    fn decode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match data.split_first() {
+            Some((&header, data)) if header == ZSTD || header == LZ4 || header == SNAPPY => {
+                self.inner.decode(&decompress(header, data)?)
+            }
+            _ => self.inner.decode(data),
+        }
+    }
+}
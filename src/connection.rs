@@ -0,0 +1,343 @@
+use redis::{ConnectionInfo, ConnectionLike, ErrorKind, RedisError, RedisResult, Value};
+
+use crate::{
+    cluster::{key_slot, ClusterConnection},
+    memory::{Memory, MemoryConnection},
+    replicas::{ReadFrom, Replicas, ReplicatedConnection},
+    retry::{disconnected, is_idempotent},
+    sentinel::{Sentinel, SentinelConnection},
+    timeout::Timeouts,
+    Codec, Error, RetryPolicy,
+};
+
+use std::{sync::Arc, thread, time::Duration};
+
+/// The means by which a Database establishes connections.
+#[derive(Clone)]
+pub(crate) enum Client {
+    Single(redis::Client),
+    Cluster(Vec<ConnectionInfo>),
+    Sentinel(Arc<Sentinel>),
+    Replicated(Arc<Replicas>),
+    Memory(Arc<Memory>),
+}
+
+impl Client {
+    /// Opens a connection whose requests are retried according to `retry` and bounded by `timeouts`.
+    pub(crate) fn get_connection_with(
+        &self,
+        retry: RetryPolicy,
+        timeouts: Timeouts,
+    ) -> RedisResult<Connection> {
+        let applied = timeouts.current(None);
+        Ok(Connection {
+            inner: self.connect(applied)?,
+            client: self.clone(),
+            retry,
+            broken: false,
+            timeouts,
+            applied,
+            blocking: None,
+            codec: None,
+        })
+    }
+    fn connect(&self, timeouts: Timeouts) -> RedisResult<Inner> {
+        Ok(match *self {
+            Client::Single(ref client) => Inner::Single(timeouts.connect(client)?),
+            Client::Cluster(ref seeds) => Inner::Cluster(Box::new(ClusterConnection::connect(
+                seeds.clone(),
+                timeouts,
+            )?)),
+            Client::Sentinel(ref sentinel) => {
+                Inner::Sentinel(SentinelConnection::connect(sentinel.clone(), timeouts)?)
+            }
+            Client::Replicated(ref replicas) => {
+                Inner::Replicated(ReplicatedConnection::connect(replicas.clone(), timeouts)?)
+            }
+            Client::Memory(ref memory) => Inner::Memory(MemoryConnection::new(memory.clone())),
+        })
+    }
+    /// Opens a dedicated connection to the server holding `key`, for example to subscribe to its keyspace notifications.
+    /// This is returned along with the generation of the server, see `generation`.
+    pub(crate) fn key_connection(&self, key: &str) -> RedisResult<(redis::Connection, u64)> {
+        match *self {
+            Client::Single(ref client) => Ok((client.get_connection()?, 0)),
+            Client::Cluster(ref seeds) => Ok((
+                ClusterConnection::connect(seeds.clone(), Timeouts::default())?
+                    .into_key_connection(key)?,
+                0,
+            )),
+            Client::Sentinel(ref sentinel) => sentinel.connect(&Timeouts::default()),
+            Client::Replicated(ref replicas) => Ok((replicas.primary.get_connection()?, 0)),
+            Client::Memory(_) => Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "An in-memory database has no server to connect to",
+            ))),
+        }
+    }
+    /// Returns a number that changes whenever the server holding keys changes, i.e. on a failover in a
+    /// Sentinel-monitored deployment.
+    pub(crate) fn generation(&self) -> u64 {
+        match *self {
+            Client::Sentinel(ref sentinel) => sentinel.generation().unwrap_or(0),
+            _ => 0,
+        }
+    }
+}
+
+enum Inner {
+    Single(redis::Connection),
+    Cluster(Box<ClusterConnection>),
+    Sentinel(SentinelConnection),
+    Replicated(ReplicatedConnection),
+    Memory(MemoryConnection),
+}
+
+/// A connection to a database, whether a single server, a cluster, a Sentinel-monitored master, a primary with read
+/// replicas or an in-memory database, over which a collection performs its operations.
+///
+/// Requests are retried and broken connections reestablished according to the retry policy of the database, and
+/// bounded by its timeouts. A connection on which a request times out is discarded, since the reply to the request
+/// may yet arrive, and reestablished before the following request.
+pub struct Connection {
+    inner: Inner,
+    client: Client,
+    retry: RetryPolicy,
+    broken: bool,
+    timeouts: Timeouts,
+    applied: Timeouts,
+    blocking: Option<Duration>,
+    codec: Option<Arc<dyn Codec>>,
+}
+
+impl Connection {
+    /// Sets the codec by which the collections using this connection transform their elements by default.
+    pub(crate) fn with_codec(mut self, codec: Option<Arc<dyn Codec>>) -> Connection {
+        self.codec = codec;
+        self
+    }
+    /// Returns the codec by which the collections using this connection transform their elements by default.
+    pub(crate) fn codec(&self) -> Option<Arc<dyn Codec>> {
+        self.codec.clone()
+    }
+    /// Performs `request` with the read timeout extended by `duration`, for commands that block on the server for
+    /// that long. A zero duration blocks indefinitely and so disables the read timeout.
+    pub(crate) fn blocking<R, F>(&mut self, duration: Duration, request: F) -> R
+    where
+        F: FnOnce(&mut Connection) -> R,
+    {
+        self.blocking = Some(duration);
+        let result = request(self);
+        self.blocking = None;
+        result
+    }
+    /// Performs `request`, retrying it according to the retry policy and reconnecting first if the connection
+    /// has broken. `idempotent` is only called once the request has failed in a manner that may be retried.
+    fn retry<R, F>(&mut self, idempotent: impl Fn() -> bool, mut request: F) -> RedisResult<R>
+    where
+        F: FnMut(&mut Inner) -> RedisResult<R>,
+    {
+        let timeouts = self.timeouts.current(self.blocking);
+        let mut attempt = 1;
+        loop {
+            let (error, sent) = match self.reconnect(timeouts) {
+                Err(error) => (error, false),
+                Ok(()) => match request(&mut self.inner) {
+                    Ok(value) => return Ok(value),
+                    Err(error) => {
+                        self.broken = error.is_io_error();
+                        (error, true)
+                    }
+                },
+            };
+            if !self.retry.should_retry(attempt, &error, sent, &idempotent) {
+                return Err(error);
+            }
+            thread::sleep(self.retry.backoff_after(attempt));
+            attempt += 1;
+        }
+    }
+    /// Reestablishes the connection if a request on it failed with an I/O error, and applies `timeouts` to it.
+    fn reconnect(&mut self, timeouts: Timeouts) -> RedisResult<()> {
+        if self.broken {
+            self.inner = self.client.connect(timeouts)?;
+            self.applied = timeouts;
+            self.broken = false;
+        }
+        if timeouts != self.applied {
+            self.inner.set_timeouts(timeouts)?;
+            self.applied = timeouts;
+        }
+        Ok(())
+    }
+    /// Returns the client from which this connection was established.
+    pub(crate) fn client(&self) -> &Client {
+        &self.client
+    }
+    /// Performs a read-only request on the server at `index` among those holding keys of the database, returning `None`
+    /// if there is no such server. Requests are retried as others are, and the connection reestablished if it breaks.
+    pub(crate) fn on_node<R, F>(&mut self, index: usize, mut request: F) -> RedisResult<Option<R>>
+    where
+        F: FnMut(&mut dyn ConnectionLike) -> RedisResult<R>,
+    {
+        self.retry(
+            || true,
+            |inner| match inner.node_connections()?.into_iter().nth(index) {
+                Some(connection) => request(connection).map(Some).map_err(disconnected),
+                None => Ok(None),
+            },
+        )
+    }
+    /// Performs a read-only request, on a replica if `read_from` permits and the database has replicas. Requests are
+    /// retried as others are, and fall back to the primary if the replica cannot be reached.
+    pub(crate) fn read<R, F>(&mut self, read_from: ReadFrom, mut request: F) -> RedisResult<R>
+    where
+        F: FnMut(&mut dyn ConnectionLike) -> RedisResult<R>,
+    {
+        if let (&Inner::Replicated(_), ReadFrom::Replica) = (&self.inner, read_from) {
+            let result = self.retry(
+                || true,
+                |inner| match *inner {
+                    Inner::Replicated(ref mut connection) => connection.read(&mut request),
+                    _ => Ok(None),
+                },
+            )?;
+            if let Some(value) = result {
+                return Ok(value);
+            }
+        }
+        request(self)
+    }
+    /// Ensures that `keys` may be accessed by a single command, failing with `Error::CrossSlot` if this
+    /// is a cluster connection and the keys hash to different slots.
+    pub(crate) fn check_slots(&self, keys: &[&str]) -> Result<(), Error> {
+        if let Inner::Cluster(_) = self.inner {
+            let mut slots = keys.iter().map(|key| key_slot(key.as_bytes()));
+            if let Some(first) = slots.next() {
+                if slots.any(|slot| slot != first) {
+                    return Err(Error::CrossSlot {
+                        keys: keys.iter().map(|key| (*key).to_owned()).collect(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Ensures that the keys named by a SORT `BY` or `GET` pattern, in which `*` is substituted with each element, may
+    /// be accessed along with `key`. On a cluster connection this requires the pattern to have a hash tag without a `*`
+    /// that hashes to the slot of `key`, and fails with `Error::CrossSlot` otherwise. The patterns `#` and `nosort` name
+    /// no keys.
+    pub(crate) fn check_pattern(&self, key: &str, pattern: &str) -> Result<(), Error> {
+        if pattern == "#" || pattern.eq_ignore_ascii_case("nosort") {
+            return Ok(());
+        }
+        if let Inner::Cluster(_) = self.inner {
+            let tagged = pattern.find('{').is_some_and(|open| {
+                pattern[open + 1..].find('}').is_some_and(|length| {
+                    length > 0 && !pattern[open + 1..][..length].contains('*')
+                })
+            });
+            if !tagged {
+                return Err(Error::CrossSlot {
+                    keys: vec![key.to_owned(), pattern.to_owned()],
+                });
+            }
+        }
+        self.check_slots(&[key, pattern])
+    }
+}
+
+impl ConnectionLike for Connection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.retry(|| is_idempotent(cmd), |inner| inner.req_packed_command(cmd))
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.retry(
+            || is_idempotent(cmd),
+            |inner| inner.req_packed_commands(cmd, offset, count),
+        )
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+}
+
+impl Inner {
+    /// Returns connections to every server holding keys of the database, in the same order on every connection.
+    fn node_connections(&mut self) -> RedisResult<Vec<&mut dyn ConnectionLike>> {
+        match *self {
+            Inner::Single(ref mut connection) => Ok(vec![connection]),
+            Inner::Cluster(ref mut connection) => Ok(connection
+                .node_connections()?
+                .into_iter()
+                .map(|connection| connection as &mut dyn ConnectionLike)
+                .collect()),
+            Inner::Sentinel(ref mut connection) => Ok(vec![connection.connection()?]),
+            Inner::Replicated(ref mut connection) => Ok(vec![&mut connection.primary]),
+            Inner::Memory(ref mut connection) => Ok(vec![connection]),
+        }
+    }
+    fn set_timeouts(&mut self, timeouts: Timeouts) -> RedisResult<()> {
+        match *self {
+            Inner::Single(ref connection) => timeouts.apply(connection),
+            Inner::Cluster(ref mut connection) => connection.set_timeouts(timeouts),
+            Inner::Sentinel(ref mut connection) => connection.set_timeouts(timeouts),
+            Inner::Replicated(ref mut connection) => connection.set_timeouts(timeouts),
+            Inner::Memory(_) => Ok(()),
+        }
+    }
+}
+
+impl ConnectionLike for Inner {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        match *self {
+            Inner::Single(ref mut connection) => {
+                connection.req_packed_command(cmd).map_err(disconnected)
+            }
+            Inner::Cluster(ref mut connection) => connection.req_packed_command(cmd),
+            Inner::Sentinel(ref mut connection) => connection.req_packed_command(cmd),
+            Inner::Replicated(ref mut connection) => connection.req_packed_command(cmd),
+            Inner::Memory(ref mut connection) => connection.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        match *self {
+            Inner::Single(ref mut connection) => connection
+                .req_packed_commands(cmd, offset, count)
+                .map_err(disconnected),
+            Inner::Cluster(ref mut connection) => {
+                connection.req_packed_commands(cmd, offset, count)
+            }
+            Inner::Sentinel(ref mut connection) => {
+                connection.req_packed_commands(cmd, offset, count)
+            }
+            Inner::Replicated(ref mut connection) => {
+                connection.req_packed_commands(cmd, offset, count)
+            }
+            Inner::Memory(ref mut connection) => connection.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match *self {
+            Inner::Single(ref connection) => connection.get_db(),
+            Inner::Cluster(ref connection) => connection.get_db(),
+            Inner::Sentinel(ref connection) => connection.get_db(),
+            Inner::Replicated(ref connection) => connection.get_db(),
+            Inner::Memory(ref connection) => connection.get_db(),
+        }
+    }
+}
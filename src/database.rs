@@ -1,14 +1,45 @@
 use futures::{lazy, Future};
 
-use redis::{Client, IntoConnectionInfo, RedisError};
+use redis::IntoConnectionInfo;
 
-use crate::collections::Collection;
+use crate::{
+    collections::Collection,
+    connection::Client,
+    keys::{escape_pattern, CollectionNames, Keys},
+    replicas::Replicas,
+    sentinel::Sentinel,
+    timeout::Timeouts,
+    Codec, Error, KeyType, Namespace, ReplicaSelection, RetryPolicy, Script,
+};
 
-use std::sync::{Arc, RwLock};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// The manner in which `Database::open` treats the existence of the key being opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenMode {
+    /// The key must exist and hold a value of the type of the collection.
+    Existing,
+    /// The key may hold a value of the type of the collection or not exist, in which case it is created by the
+    /// first write to the collection.
+    OrCreate,
+    /// The key must not exist.
+    New,
+}
 
 /// A redis database connection.
+///
+/// Databases are cheap to clone, and clones share their configuration and the underlying client. Each collection and
+/// script obtained from a database uses a connection of its own.
+#[derive(Clone)]
 pub struct Database {
     client: Arc<RwLock<Client>>,
+    namespace: Namespace,
+    retry: RetryPolicy,
+    timeouts: Timeouts,
+    codec: Option<Arc<dyn Codec>>,
 }
 
 impl Database {
@@ -19,22 +50,295 @@ impl Database {
     /// but no more, actual connection will not occur until an operation is performed.
     pub fn new<'a, T: IntoConnectionInfo + 'a>(
         addr: T,
-    ) -> impl Future<Item = Database, Error = RedisError> + 'a {
+    ) -> impl Future<Item = Database, Error = Error> + 'a {
+        lazy(move || {
+            let client = Arc::new(RwLock::new(Client::Single(redis::Client::open(addr)?)));
+            Ok(Database {
+                client,
+                namespace: Namespace::default(),
+                retry: RetryPolicy::default(),
+                timeouts: Timeouts::default(),
+                codec: None,
+            })
+        })
+    }
+    /// Connects to a redis cluster through the provided addresses of some of its nodes.
+    ///
+    /// Like `new` this will not fail if the nodes are unreachable. Each connection discovers the slots of the cluster from the first
+    /// reachable node and routes every command to the node serving the slot of its keys, following MOVED and ASK redirections.
+    /// Scripts are loaded on every node, so that they are found by the node serving the keys of each invocation.
+    /// The namespace of the database wraps collection names in hash tags (see `Namespace::hash_tags`) so that keys derived from
+    /// a collection reside in the same slot, and operations that would access keys in different slots fail with
+    /// `Error::CrossSlot`.
+    pub fn cluster<'a, T: IntoConnectionInfo + 'a>(
+        addrs: Vec<T>,
+    ) -> impl Future<Item = Database, Error = Error> + 'a {
+        lazy(move || {
+            let seeds = addrs
+                .into_iter()
+                .map(IntoConnectionInfo::into_connection_info)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Database {
+                client: Arc::new(RwLock::new(Client::Cluster(seeds))),
+                namespace: Namespace::default().hash_tags(true),
+                retry: RetryPolicy::default(),
+                timeouts: Timeouts::default(),
+                codec: None,
+            })
+        })
+    }
+    /// Connects to the master named `master_name` of a set of redis servers monitored by the provided sentinels. The
+    /// database number and password used for the master are taken from the first sentinel address.
+    ///
+    /// Like `new` this will not fail if the sentinels are unreachable. The current master is discovered from the sentinels
+    /// and connections, including those of watchers, move to the new master when the sentinels announce a failover.
+    pub fn sentinel<'a, T: IntoConnectionInfo + 'a>(
+        sentinels: Vec<T>,
+        master_name: &'a str,
+    ) -> impl Future<Item = Database, Error = Error> + 'a {
         lazy(move || {
-            let client = Arc::new(RwLock::new(Client::open(addr)?));
-            Ok(Database { client })
+            let sentinels = sentinels
+                .into_iter()
+                .map(IntoConnectionInfo::into_connection_info)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Database {
+                client: Arc::new(RwLock::new(Client::Sentinel(Sentinel::new(
+                    sentinels,
+                    master_name.to_owned(),
+                )))),
+                namespace: Namespace::default(),
+                retry: RetryPolicy::default(),
+                timeouts: Timeouts::default(),
+                codec: None,
+            })
         })
     }
-    /// Gets a data structure of the provided type at the specified key.
-    pub fn get<'a, T: Collection<'a> + 'a>(
-        &'a mut self,
-        name: &'a str,
-    ) -> impl Future<Item = T, Error = RedisError> {
+    /// Connects to a primary server and its read replicas. Writes are always performed on the primary, while the read-only
+    /// operations of collections are performed on a replica chosen by `selection` unless the collection handle is configured
+    /// to read from the primary (see `List::set_read_from`). Reads fall back to the primary if a replica cannot be reached,
+    /// but fail with `Error::Timeout` rather than falling back if the replica does not reply in time.
+    ///
+    /// Like `new` this will not fail if no server is listening on the provided addresses.
+    pub fn replicated<'a, T: IntoConnectionInfo + 'a>(
+        primary: T,
+        replicas: Vec<T>,
+        selection: ReplicaSelection,
+    ) -> impl Future<Item = Database, Error = Error> + 'a {
+        lazy(move || {
+            let replicas = replicas
+                .into_iter()
+                .map(redis::Client::open)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Database {
+                client: Arc::new(RwLock::new(Client::Replicated(Arc::new(Replicas::new(
+                    redis::Client::open(primary)?,
+                    replicas,
+                    selection,
+                ))))),
+                namespace: Namespace::default(),
+                retry: RetryPolicy::default(),
+                timeouts: Timeouts::default(),
+                codec: None,
+            })
+        })
+    }
+    /// Sets the namespace by which collection names are mapped to redis keys. See `Namespace`.
+    ///
+    /// The namespace of a database connected to a cluster always wraps names in hash tags, whatever `Namespace::hash_tags`
+    /// is set to, since the keys derived from a collection must reside in its slot.
+    pub fn with_namespace(mut self, namespace: Namespace) -> Self {
+        let cluster = matches!(*self.client.read().unwrap(), Client::Cluster(_));
+        self.namespace = if cluster {
+            namespace.hash_tags(true)
+        } else {
+            namespace
+        };
+        self
+    }
+    /// Creates an empty database held in the memory of this process, which emulates the commands and keyspace
+    /// notifications of lists, hashes, sets, sorted sets and strings. This permits testing code that uses collections
+    /// without a redis server. Each call creates a distinct database, which is shared by the collections obtained from it.
+    ///
+    /// Lua is not interpreted: the scripts executed by the collections, such as those of `List::find_by_field`,
+    /// `List::sort_by_key` and `ReliableQueue::requeue_expired`, are emulated, while any other script, including those
+    /// of `Database::script`, fails with an error.
+    pub fn in_memory() -> Database {
+        Database {
+            client: Arc::new(RwLock::new(Client::Memory(Arc::default()))),
+            namespace: Namespace::default(),
+            retry: RetryPolicy::default(),
+            timeouts: Timeouts::default(),
+            codec: None,
+        }
+    }
+    /// Sets the policy by which the requests of collections and scripts subsequently obtained from the database are
+    /// retried. See `RetryPolicy`.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+    /// Returns the policy by which requests are retried.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+    /// Sets the time within which the connections of collections and scripts subsequently obtained from the database
+    /// must be established, failing with `Error::Timeout` otherwise. By default this is unbounded.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.connect = Some(timeout);
+        self
+    }
+    /// Sets the time within which each reply must be received, failing with `Error::Timeout` otherwise. By default
+    /// this is unbounded. Blocking operations such as `List::move_to_blocking` extend this by the time for which they
+    /// block. Individual operations may override the timeouts of the database with `TimeoutExt::with_timeout`.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.read = Some(timeout);
+        self
+    }
+    /// Sets the time within which each request must be sent, failing with `Error::Timeout` otherwise. By default this
+    /// is unbounded.
+    pub fn with_write_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.write = Some(timeout);
+        self
+    }
+    /// Sets the codec by which the collections and scripts subsequently obtained from the database transform serialized
+    /// elements, for example to compress them (see `Compressed`) or encrypt them (see `Encrypted`). Individual lists
+    /// may override this with `List::with_codec`. By default elements are stored as they are serialized.
+    ///
+    /// A codec must be able to decode the elements already stored in the collections it is used with; `Compressed` for
+    /// example reads elements stored without compression as they are. Elements in script replies are decoded by
+    /// the codec with `Invocation::invoke_decoded`.
+    pub fn with_codec<C: Codec>(mut self, codec: C) -> Self {
+        self.codec = Some(Arc::new(codec));
+        self
+    }
+    /// Returns the namespace by which collection names are mapped to redis keys.
+    pub fn namespace(&self) -> &Namespace {
+        &self.namespace
+    }
+    /// Gets a data structure of the provided type with the specified name. The key at which it is stored is
+    /// determined by the namespace of the database.
+    pub fn get<T: Collection>(&self, name: &str) -> impl Future<Item = T, Error = Error> {
+        let key = self.namespace.key(
+            self.namespace
+                .collection_prefix(&T::key_type(), T::type_prefix()),
+            name,
+        );
+        self.get_raw(key)
+    }
+    /// Gets a data structure of the provided type stored at exactly the specified key, disregarding the namespace
+    /// of the database. This permits adopting keys created by other applications.
+    pub fn get_raw<T: Collection, K: Into<String>>(
+        &self,
+        key: K,
+    ) -> impl Future<Item = T, Error = Error> {
         let client = self.client.clone();
-        let name = name.to_owned();
+        let retry = self.retry.clone();
+        let timeouts = self.timeouts;
+        let codec = self.codec.clone();
+        let key = key.into();
         lazy(move || {
-            let conn = client.read().unwrap().get_connection()?;
-            T::get(name, conn)
+            let conn = client
+                .read()
+                .unwrap()
+                .get_connection_with(retry, timeouts)?
+                .with_codec(codec);
+            T::get(key, conn)
         })
     }
+    /// Gets a data structure of the provided type with the specified name as `get` does, but first verifies the type
+    /// of the value stored at its key with TYPE, failing with `Error::WrongType` if the key holds a value of another type.
+    /// Depending on `mode` this also fails with `Error::KeyNotFound` or `Error::KeyExists`.
+    ///
+    /// Note that this check is not atomic with respect to later operations on the collection.
+    pub fn open<T: Collection>(
+        &self,
+        name: &str,
+        mode: OpenMode,
+    ) -> impl Future<Item = T, Error = Error> {
+        let key = self.namespace.key(
+            self.namespace
+                .collection_prefix(&T::key_type(), T::type_prefix()),
+            name,
+        );
+        self.open_raw(key, mode)
+    }
+    /// Type-checked variant of `get_raw`. See `open`.
+    pub fn open_raw<T: Collection, K: Into<String>>(
+        &self,
+        key: K,
+        mode: OpenMode,
+    ) -> impl Future<Item = T, Error = Error> {
+        let client = self.client.clone();
+        let retry = self.retry.clone();
+        let timeouts = self.timeouts;
+        let codec = self.codec.clone();
+        let key = key.into();
+        lazy(move || {
+            let mut conn = client
+                .read()
+                .unwrap()
+                .get_connection_with(retry, timeouts)?
+                .with_codec(codec);
+            let actual: String = redis::cmd("TYPE").arg(&key).query(&mut conn)?;
+            let actual = KeyType::from(actual.as_str());
+            match (actual, mode) {
+                (KeyType::None, OpenMode::Existing) => Err(Error::KeyNotFound { key }),
+                (KeyType::None, _) => Ok(T::get(key, conn)?),
+                (_, OpenMode::New) => Err(Error::KeyExists { key }),
+                (ref actual, _) if *actual == T::key_type() => Ok(T::get(key, conn)?),
+                (actual, _) => Err(Error::WrongType {
+                    key,
+                    expected: T::key_type(),
+                    actual,
+                }),
+            }
+        })
+    }
+    /// Prepares a Lua script for invocation on a dedicated connection. See `Script`.
+    pub fn script(&self, code: &str) -> impl Future<Item = Script, Error = Error> {
+        let client = self.client.clone();
+        let retry = self.retry.clone();
+        let timeouts = self.timeouts;
+        let codec = self.codec.clone();
+        let code = code.to_owned();
+        lazy(move || {
+            let conn = client
+                .read()
+                .unwrap()
+                .get_connection_with(retry, timeouts)?
+                .with_codec(codec);
+            Ok(Script::new(&code, conn))
+        })
+    }
+    /// Returns a stream of the logical names of the collections of the provided type whose names match the glob-style
+    /// `pattern`, e.g. `*` for every collection or `user:*`. This scans the keyspace incrementally with SCAN and
+    /// requires redis 6.0 or later.
+    pub fn keys<T: Collection>(&self, pattern: &str) -> CollectionNames {
+        CollectionNames::new(
+            self.client.clone(),
+            self.retry.clone(),
+            self.timeouts,
+            self.namespace.clone(),
+            self.namespace
+                .collection_prefix(&T::key_type(), T::type_prefix())
+                .map(str::to_owned),
+            T::key_type(),
+            pattern,
+        )
+    }
+    /// Returns a stream of the keys matching the glob-style `pattern` within the application prefix of the
+    /// namespace of the database, if any, along with their types. Keys are produced in full as they are
+    /// stored in redis. Note that without an application prefix this includes every key in the database.
+    pub fn all_keys(&self, pattern: &str) -> Keys {
+        Keys::new(
+            self.client.clone(),
+            self.retry.clone(),
+            self.timeouts,
+            format!(
+                "{}{}",
+                escape_pattern(&self.namespace.application_prefix()),
+                pattern
+            ),
+        )
+    }
 }
@@ -0,0 +1,304 @@
+#[cfg(feature = "aes-gcm")]
+use aes_gcm::aead;
+#[cfg(not(feature = "aes-gcm"))]
+use chacha20poly1305::aead;
+
+use aead::{
+    generic_array::GenericArray,
+    rand_core::{OsRng, RngCore},
+    Aead, KeyInit, Payload,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{Codec, Plain};
+
+use std::{collections::BTreeMap, fmt, io};
+
+/// The header marking encrypted elements, which is a reserved initial byte in CBOR.
+const ENCRYPTED: u8 = 0x3c;
+
+/// The length of the nonce of each supported cipher.
+const NONCE_LEN: usize = 12;
+
+/// The length of the envelope preceding the nonce: the header, the cipher and the key ID as a big-endian u32.
+const PREFIX_LEN: usize = 6;
+
+/// The context from which the key of the nonces of deterministic encryption is derived from each key.
+const NONCE_KEY_CONTEXT: &[u8] = b"redis_backed deterministic nonce";
+
+/// An AEAD cipher with which `Encrypted` encrypts elements. Each is available with the feature of the same name, i.e.
+/// `aes-gcm` or `chacha20poly1305`, and encryption is available once at least one of them is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Cipher {
+    /// AES-256 in Galois/Counter Mode.
+    #[cfg(feature = "aes-gcm")]
+    Aes256Gcm,
+    /// ChaCha20-Poly1305.
+    #[cfg(feature = "chacha20poly1305")]
+    ChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// Returns the identifier of the cipher stored in the envelope of each element.
+    fn id(self) -> u8 {
+        match self {
+            #[cfg(feature = "aes-gcm")]
+            Cipher::Aes256Gcm => 1,
+            #[cfg(feature = "chacha20poly1305")]
+            Cipher::ChaCha20Poly1305 => 2,
+        }
+    }
+    fn from_id(id: u8) -> io::Result<Cipher> {
+        match id {
+            #[cfg(feature = "aes-gcm")]
+            1 => Ok(Cipher::Aes256Gcm),
+            #[cfg(feature = "chacha20poly1305")]
+            2 => Ok(Cipher::ChaCha20Poly1305),
+            _ => Err(invalid(match id {
+                1 => "the element is encrypted with AES-256-GCM, which requires the aes-gcm feature",
+                2 => "the element is encrypted with ChaCha20-Poly1305, which requires the chacha20poly1305 feature",
+                _ => "the element is encrypted with an unknown cipher",
+            })),
+        }
+    }
+    fn seal(self, key: &Key, nonce: &[u8], payload: Payload<'_, '_>) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "aes-gcm")]
+            Cipher::Aes256Gcm => seal::<aes_gcm::Aes256Gcm>(key, nonce, payload),
+            #[cfg(feature = "chacha20poly1305")]
+            Cipher::ChaCha20Poly1305 => {
+                seal::<chacha20poly1305::ChaCha20Poly1305>(key, nonce, payload)
+            }
+        }
+    }
+    fn open(self, key: &Key, nonce: &[u8], payload: Payload<'_, '_>) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "aes-gcm")]
+            Cipher::Aes256Gcm => open::<aes_gcm::Aes256Gcm>(key, nonce, payload),
+            #[cfg(feature = "chacha20poly1305")]
+            Cipher::ChaCha20Poly1305 => {
+                open::<chacha20poly1305::ChaCha20Poly1305>(key, nonce, payload)
+            }
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn seal<A: Aead + KeyInit>(
+    key: &Key,
+    nonce: &[u8],
+    payload: Payload<'_, '_>,
+) -> io::Result<Vec<u8>> {
+    A::new(GenericArray::from_slice(&key.key))
+        .encrypt(GenericArray::from_slice(nonce), payload)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "encryption failed"))
+}
+
+fn open<A: Aead + KeyInit>(
+    key: &Key,
+    nonce: &[u8],
+    payload: Payload<'_, '_>,
+) -> io::Result<Vec<u8>> {
+    A::new(GenericArray::from_slice(&key.key))
+        .decrypt(GenericArray::from_slice(nonce), payload)
+        .map_err(|_| invalid("the element could not be decrypted and authenticated"))
+}
+
+struct Key {
+    key: [u8; 32],
+    /// The key from which the nonces of deterministic encryption are derived.
+    nonce_key: [u8; 32],
+}
+
+impl Key {
+    fn new(key: [u8; 32]) -> Key {
+        let mut nonce_key = [0; 32];
+        nonce_key.copy_from_slice(&hmac(&key, NONCE_KEY_CONTEXT));
+        Key { key, nonce_key }
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The 256-bit keys with which `Encrypted` encrypts and decrypts elements, each identified by an ID stored alongside
+/// the elements it encrypted.
+///
+/// Elements are encrypted with the current key, while every key in the keyring may decrypt elements. Keys are rotated
+/// by making a new key current while retaining the previous keys until no element encrypted with them remains.
+pub struct Keyring {
+    keys: BTreeMap<u32, Key>,
+    current: u32,
+}
+
+impl Keyring {
+    /// Creates a keyring whose current key is `key`, identified by `id`.
+    pub fn new(id: u32, key: [u8; 32]) -> Self {
+        let mut keys = BTreeMap::new();
+        keys.insert(id, Key::new(key));
+        Keyring { keys, current: id }
+    }
+    /// Adds `key`, identified by `id`, with which elements are only decrypted, replacing any key with the same ID
+    /// other than the current key.
+    pub fn with_key(mut self, id: u32, key: [u8; 32]) -> Self {
+        if id != self.current {
+            self.keys.insert(id, Key::new(key));
+        }
+        self
+    }
+    /// Returns the ID of the current key.
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+}
+
+impl fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keyring")
+            .field("ids", &self.keys.keys().collect::<Vec<_>>())
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+/// A codec that encrypts the elements of collections with an AEAD cipher, so that the database holds neither the
+/// elements nor the keys with which they are encrypted.
+///
+/// Each element is stored with a header designating the cipher and the ID of the key in the `Keyring` with which it was
+/// encrypted, followed by the nonce and the ciphertext, and the header is authenticated along with the element. Elements
+/// without this header fail to decode, since anyone with write access to the database could otherwise substitute
+/// elements of their choosing; see `accept_plaintext` to read elements written before encryption was enabled.
+///
+/// By default each element is encrypted with a random nonce, so that equal elements have different ciphertexts and
+/// equality-based operations such as `List::remove`, `List::insert_before`, `List::position` and `List::contains` never
+/// match. See `deterministic` to permit these. A `ReliableQueue` may use either mode, since `ReliableQueue::ack` removes
+/// the ciphertext held by the `Receipt` of the element rather than encrypting the element again. Operations that
+/// inspect elements on the server, such as `List::find_by_field` and `List::sort_by_key`, cannot read encrypted
+/// elements.
+pub struct Encrypted<C = Plain> {
+    inner: C,
+    cipher: Cipher,
+    keyring: Keyring,
+    deterministic: bool,
+    accept_plaintext: bool,
+}
+
+impl Encrypted {
+    /// Creates a codec encrypting elements with `cipher` and the current key of `keyring`.
+    pub fn new(cipher: Cipher, keyring: Keyring) -> Self {
+        Encrypted::wrap(Plain, cipher, keyring)
+    }
+}
+
+impl<C: Codec> Encrypted<C> {
+    /// Creates a codec encrypting elements with `cipher` and the current key of `keyring` once they have been encoded
+    /// by `inner`, for example `Compressed`, since encrypted elements do not compress.
+    pub fn wrap(inner: C, cipher: Cipher, keyring: Keyring) -> Self {
+        Encrypted {
+            inner,
+            cipher,
+            keyring,
+            deterministic: false,
+            accept_plaintext: false,
+        }
+    }
+    /// Encrypts elements deterministically, deriving the nonce of each element from the element and the key, so that
+    /// equal elements encrypted with the same key have equal ciphertexts and equality-based operations continue to work.
+    ///
+    /// This reveals to those with access to the database which elements are equal, and an element only matches
+    /// elements encrypted with the current key, so following a rotation elements encrypted with previous keys must be
+    /// rewritten for equality-based operations to match them.
+    pub fn deterministic(mut self) -> Self {
+        self.deterministic = true;
+        self
+    }
+    /// Reads elements without the header of encrypted elements, i.e. those written before encryption was enabled, as
+    /// they are, rather than failing to decode them.
+    ///
+    /// Such elements are neither confidential nor authenticated, so this should only be enabled while a collection is
+    /// migrated to encryption, after which it should be rewritten if it must not hold plaintext.
+    pub fn accept_plaintext(mut self) -> Self {
+        self.accept_plaintext = true;
+        self
+    }
+}
+
+impl<C> fmt::Debug for Encrypted<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Encrypted")
+            .field("cipher", &self.cipher)
+            .field("keyring", &self.keyring)
+            .field("deterministic", &self.deterministic)
+            .field("accept_plaintext", &self.accept_plaintext)
+            .finish()
+    }
+}
+
+impl<C: Codec> Codec for Encrypted<C> {
+    fn encode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let data = self.inner.encode(data)?;
+        let key = &self.keyring.keys[&self.keyring.current];
+        let mut nonce = [0; NONCE_LEN];
+        if self.deterministic {
+            nonce.copy_from_slice(&hmac(&key.nonce_key, &data)[..NONCE_LEN]);
+        } else {
+            OsRng.fill_bytes(&mut nonce);
+        }
+        let mut encrypted = Vec::with_capacity(PREFIX_LEN + NONCE_LEN + data.len() + 16);
+        encrypted.push(ENCRYPTED);
+        encrypted.push(self.cipher.id());
+        encrypted.extend_from_slice(&self.keyring.current.to_be_bytes());
+        let ciphertext = self.cipher.seal(
+            key,
+            &nonce,
+            Payload {
+                msg: &data,
+                aad: &encrypted,
+            },
+        )?;
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend(ciphertext);
+        Ok(encrypted)
+    }
+    fn decode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        if data.first() != Some(&ENCRYPTED) {
+            if self.accept_plaintext {
+                return self.inner.decode(data);
+            }
+            return Err(invalid("the element is not encrypted"));
+        }
+        if data.len() < PREFIX_LEN + NONCE_LEN {
+            return Err(invalid("the encrypted element is truncated"));
+        }
+        let (prefix, data) = data.split_at(PREFIX_LEN);
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let cipher = Cipher::from_id(prefix[1])?;
+        let mut id = [0; 4];
+        id.copy_from_slice(&prefix[2..]);
+        let id = u32::from_be_bytes(id);
+        let key = self.keyring.keys.get(&id).ok_or_else(|| {
+            invalid(&format!(
+                "the element is encrypted with the key {}, which is not in the keyring",
+                id
+            ))
+        })?;
+        let data = cipher.open(
+            key,
+            nonce,
+            Payload {
+                msg: ciphertext,
+                aad: prefix,
+            },
+        )?;
+        self.inner.decode(&data)
+    }
+}
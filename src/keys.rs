@@ -0,0 +1,291 @@
+use futures::{Async, Poll, Stream};
+use redis::{ConnectionLike, RedisResult};
+
+use crate::{
+    connection::{Client, Connection},
+    timeout::Timeouts,
+    Error, Namespace, RetryPolicy,
+};
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display, Formatter},
+    sync::{Arc, RwLock},
+};
+
+/// The type of the value stored at a redis key, as reported by the TYPE command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyType {
+    /// No value is stored at the key.
+    None,
+    /// A string.
+    String,
+    /// A list.
+    List,
+    /// A set.
+    Set,
+    /// A sorted set.
+    SortedSet,
+    /// A hash.
+    Hash,
+    /// A stream.
+    Stream,
+    /// A type not known to redis-backed, such as one provided by a module.
+    Other(String),
+}
+
+impl KeyType {
+    /// Returns the name of the type as used by redis.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            KeyType::None => "none",
+            KeyType::String => "string",
+            KeyType::List => "list",
+            KeyType::Set => "set",
+            KeyType::SortedSet => "zset",
+            KeyType::Hash => "hash",
+            KeyType::Stream => "stream",
+            KeyType::Other(ref name) => name,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for KeyType {
+    fn from(name: &'a str) -> Self {
+        match name {
+            "none" => KeyType::None,
+            "string" => KeyType::String,
+            "list" => KeyType::List,
+            "set" => KeyType::Set,
+            "zset" => KeyType::SortedSet,
+            "hash" => KeyType::Hash,
+            "stream" => KeyType::Stream,
+            name => KeyType::Other(name.to_owned()),
+        }
+    }
+}
+
+impl Display for KeyType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A key found by `Database::all_keys` along with the type of its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyInfo {
+    /// The redis key.
+    pub key: String,
+    /// The type of the value stored at the key.
+    pub key_type: KeyType,
+}
+
+/// Escapes the characters that are special in SCAN MATCH patterns.
+pub(crate) fn escape_pattern(literal: &str) -> String {
+    let mut escaped = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        if let '*' | '?' | '[' | ']' | '\\' = c {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// An incremental SCAN over the keys of a database on a dedicated connection, established when it is first polled.
+/// In a cluster each node is scanned in turn.
+struct Scan {
+    client: Arc<RwLock<Client>>,
+    retry: RetryPolicy,
+    timeouts: Timeouts,
+    connection: Option<Connection>,
+    pattern: String,
+    count: u32,
+    key_type: Option<KeyType>,
+    node: usize,
+    cursor: u64,
+}
+
+impl Scan {
+    fn new(
+        client: Arc<RwLock<Client>>,
+        retry: RetryPolicy,
+        timeouts: Timeouts,
+        pattern: String,
+        key_type: Option<KeyType>,
+    ) -> Self {
+        Scan {
+            client,
+            retry,
+            timeouts,
+            connection: None,
+            pattern,
+            count: 100,
+            key_type,
+            node: 0,
+            cursor: 0,
+        }
+    }
+    /// Performs `request` on the node at `index`, returning `None` if the database has no such node.
+    fn on_node<R, F>(&mut self, index: usize, request: F) -> Result<Option<R>, Error>
+    where
+        F: FnMut(&mut dyn ConnectionLike) -> RedisResult<R>,
+    {
+        if self.connection.is_none() {
+            let connection = self
+                .client
+                .read()
+                .unwrap()
+                .get_connection_with(self.retry.clone(), self.timeouts)?;
+            self.connection = Some(connection);
+        }
+        Ok(self.connection.as_mut().unwrap().on_node(index, request)?)
+    }
+    /// Fetches the next batch of keys, which may be empty, along with the index of the node holding them, or returns
+    /// `None` once the scan is complete.
+    fn next_page(&mut self) -> Result<Option<(Vec<String>, usize)>, Error> {
+        let mut command = redis::cmd("SCAN");
+        command
+            .arg(self.cursor)
+            .arg("MATCH")
+            .arg(&self.pattern)
+            .arg("COUNT")
+            .arg(self.count);
+        if let Some(ref key_type) = self.key_type {
+            command.arg("TYPE").arg(key_type.as_str());
+        }
+        let node = self.node;
+        let (cursor, keys): (u64, Vec<String>) =
+            match self.on_node(node, |connection| command.query(connection))? {
+                Some(page) => page,
+                None => return Ok(None),
+            };
+        self.cursor = cursor;
+        if cursor == 0 {
+            self.node += 1;
+        }
+        Ok(Some((keys, node)))
+    }
+}
+
+/// A stream of the logical names of the collections of a given type in a database, produced by `Database::keys`.
+///
+/// As SCAN provides only weak guarantees, a name may be produced more than once and collections created or removed while
+/// the stream is in progress may or may not be produced.
+pub struct CollectionNames {
+    scan: Scan,
+    namespace: Namespace,
+    type_prefix: Option<String>,
+    buffer: VecDeque<String>,
+}
+
+impl CollectionNames {
+    pub(crate) fn new(
+        client: Arc<RwLock<Client>>,
+        retry: RetryPolicy,
+        timeouts: Timeouts,
+        namespace: Namespace,
+        type_prefix: Option<String>,
+        key_type: KeyType,
+        pattern: &str,
+    ) -> Self {
+        let pattern = format!(
+            "{}{}",
+            escape_pattern(&namespace.key(type_prefix.as_deref(), "")),
+            pattern
+        );
+        CollectionNames {
+            scan: Scan::new(client, retry, timeouts, pattern, Some(key_type)),
+            namespace,
+            type_prefix,
+            buffer: VecDeque::new(),
+        }
+    }
+    /// Sets the COUNT hint, i.e. the amount of work performed by the server for each batch of keys. The default is 100.
+    pub fn count(mut self, count: u32) -> Self {
+        self.scan.count = count.max(1);
+        self
+    }
+}
+
+impl Stream for CollectionNames {
+    type Item = String;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        while self.buffer.is_empty() {
+            match self.scan.next_page()? {
+                None => return Ok(Async::Ready(None)),
+                Some((keys, _)) => {
+                    let namespace = &self.namespace;
+                    let type_prefix = self.type_prefix.as_deref();
+                    self.buffer.extend(
+                        keys.into_iter()
+                            .filter_map(|key| namespace.name(type_prefix, &key)),
+                    );
+                }
+            }
+        }
+        Ok(Async::Ready(self.buffer.pop_front()))
+    }
+}
+
+/// A stream of the keys in a database along with their types, produced by `Database::all_keys`. This
+/// is subject to the same guarantees as `CollectionNames`.
+pub struct Keys {
+    scan: Scan,
+    buffer: VecDeque<KeyInfo>,
+}
+
+impl Keys {
+    pub(crate) fn new(
+        client: Arc<RwLock<Client>>,
+        retry: RetryPolicy,
+        timeouts: Timeouts,
+        pattern: String,
+    ) -> Self {
+        Keys {
+            scan: Scan::new(client, retry, timeouts, pattern, None),
+            buffer: VecDeque::new(),
+        }
+    }
+    /// Sets the COUNT hint, i.e. the amount of work performed by the server for each batch of keys. The default is 100.
+    pub fn count(mut self, count: u32) -> Self {
+        self.scan.count = count.max(1);
+        self
+    }
+}
+
+impl Stream for Keys {
+    type Item = KeyInfo;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        while self.buffer.is_empty() {
+            let (keys, node) = match self.scan.next_page()? {
+                None => return Ok(Async::Ready(None)),
+                Some(page) => page,
+            };
+            if keys.is_empty() {
+                continue;
+            }
+            let mut pipeline = redis::pipe();
+            for key in &keys {
+                pipeline.cmd("TYPE").arg(key.as_str());
+            }
+            let types: Vec<String> = match self
+                .scan
+                .on_node(node, |connection| pipeline.query(connection))?
+            {
+                Some(types) => types,
+                None => return Ok(Async::Ready(None)),
+            };
+            self.buffer
+                .extend(keys.into_iter().zip(types).map(|(key, key_type)| KeyInfo {
+                    key,
+                    key_type: KeyType::from(key_type.as_str()),
+                }));
+        }
+        Ok(Async::Ready(self.buffer.pop_front()))
+    }
+}
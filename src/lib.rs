@@ -13,21 +13,84 @@
     unused_qualifications
 )]
 
-use failure::Fail;
+use redis::ErrorKind;
+use thiserror::Error;
+
+pub use keys::KeyType;
 
 /// A database communication error.
-#[derive(Fail, Debug)]
+///
+/// Errors that wrap a lower-level error expose it through `std::error::Error::source`.
+#[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
+    /// A connection to the database could not be established, or failed while a request was being performed.
+    #[error("The connection to the database failed")]
+    Connection(#[source] redis::RedisError),
+    /// A connection could not be established, or a request sent or its reply received, within the applicable timeout.
+    #[error("The operation timed out")]
+    Timeout,
     /// An error reported by the database engine.
-    #[fail(display = "A redis error occurred")]
-    RedisError(#[cause] redis::RedisError),
-    /// An error reported by the serialization process.
-    #[fail(display = "A serialization error occurred")]
-    SerializationError(#[cause] serde_cbor::error::Error),
+    #[error("A redis error occurred")]
+    RedisError(#[source] redis::RedisError),
+    /// A Lua script, either one prepared with `Database::script` or one executed internally by an operation such as
+    /// `List::find_by_field`, failed on the server.
+    #[error("A script failed")]
+    Script(#[source] redis::RedisError),
+    /// A transaction was discarded by the server rather than executed, because one of its commands was rejected.
+    #[error("The transaction was aborted")]
+    TransactionAborted(#[source] redis::RedisError),
+    /// A value could not be serialized to be sent to the database.
+    #[error("A serialization error occurred")]
+    SerializationError(#[source] serde_cbor::error::Error),
+    /// An element read from the database could not be deserialized as the type of its collection.
+    #[error(
+        "An element of the key {key}{} could not be decoded",
+        .index.map(|index| format!(" at index {}", index)).unwrap_or_default()
+    )]
+    Decode {
+        /// The key from which the element was read.
+        key: String,
+        /// The index of the element in the collection, negative if counted from the end of a list, if known.
+        index: Option<i64>,
+        /// The deserialization error.
+        #[source]
+        source: serde_cbor::error::Error,
+    },
+    /// The codec of a collection failed to transform an element, either one being written or one read from the key.
+    #[error(
+        "The codec failed to transform an element{}{}",
+        .key.as_ref().map(|key| format!(" of the key {}", key)).unwrap_or_default(),
+        .index.map(|index| format!(" at index {}", index)).unwrap_or_default()
+    )]
+    Codec {
+        /// The key from which the element was read, if it was being decoded.
+        key: Option<String>,
+        /// The index of the element in the collection, negative if counted from the end of a list, if known.
+        index: Option<i64>,
+        /// The error produced by the codec.
+        #[source]
+        source: std::io::Error,
+    },
+    /// An element that was required to exist did not.
+    #[error("The key {key} has no element at index {index}")]
+    MissingElement {
+        /// The key of the collection.
+        key: String,
+        /// The index of the element.
+        index: i64,
+    },
+    /// An element was accessed at an index beyond the bounds of a collection, or of a collection that does not exist.
+    #[error("The index {index} is out of range for the key {key}")]
+    IndexOutOfRange {
+        /// The key of the collection.
+        key: String,
+        /// The index that was accessed.
+        index: i64,
+    },
     /// An error produced in the handling of an invalid key notification.
-    #[fail(
-        display = "An unknown or invalid notification was received: no notification {} for redis-backed type {}",
-        notification, type_name
+    #[error(
+        "An unknown or invalid notification was received: no notification {notification} for redis-backed type {type_name}"
     )]
     InvalidNotification {
         /// The relevant invalid notification.
@@ -35,11 +98,82 @@ pub enum Error {
         /// The human-readable name of the type on which the invalid event occurred.
         type_name: String,
     },
+    /// A key held a value of a type other than that of the collection it was opened as.
+    #[error("The key {key} holds a {actual} rather than a {expected}")]
+    WrongType {
+        /// The key that was opened.
+        key: String,
+        /// The type of the collection the key was opened as.
+        expected: KeyType,
+        /// The type of the value held by the key.
+        actual: KeyType,
+    },
+    /// A collection that was required to exist did not.
+    #[error("The key {key} does not exist")]
+    KeyNotFound {
+        /// The key that was opened.
+        key: String,
+    },
+    /// A collection that was required not to exist already did.
+    #[error("The key {key} already exists")]
+    KeyExists {
+        /// The key that was opened.
+        key: String,
+    },
+    /// An operation on a cluster accessed keys that hash to different slots and so may reside on different nodes.
+    /// Keys that share a hash tag, i.e. the same `{...}` section, are always assigned the same slot.
+    #[error("The keys {keys:?} do not hash to the same cluster slot")]
+    CrossSlot {
+        /// The keys accessed by the operation.
+        keys: Vec<String>,
+    },
+    /// A collection was modified while being traversed in a manner that does not tolerate modification.
+    #[error("The collection at key {key} was modified during iteration")]
+    ConcurrentModification {
+        /// The key of the modified collection.
+        key: String,
+    },
+}
+
+impl Error {
+    /// Returns true if the error is transient, such that performing the operation again may succeed: connection
+    /// failures, timeouts, concurrent modification and the errors that the default `RetryPolicy` retries, such as
+    /// those of a server that is loading its dataset or a cluster that is failing over.
+    ///
+    /// Note that an operation that failed with `Error::Connection` or `Error::Timeout` may nonetheless have been
+    /// performed by the server, so operations that are not idempotent, such as pushes, should only be repeated where
+    /// performing them twice is acceptable.
+    pub fn is_retryable(&self) -> bool {
+        match *self {
+            Error::Connection(_) | Error::Timeout | Error::ConcurrentModification { .. } => true,
+            Error::RedisError(ref error) => retry::is_transient(error),
+            _ => false,
+        }
+    }
+    /// Converts the error of a request that ran a script, attributing errors reported by the server to the script.
+    pub(crate) fn script(error: redis::RedisError) -> Error {
+        match error.kind() {
+            ErrorKind::ResponseError | ErrorKind::ExtensionError
+                if !retry::is_transient(&error) =>
+            {
+                Error::Script(error)
+            }
+            _ => Error::from(error),
+        }
+    }
 }
 
 impl From<redis::RedisError> for Error {
     fn from(error: redis::RedisError) -> Error {
-        Error::RedisError(error)
+        if error.is_timeout() {
+            Error::Timeout
+        } else if error.is_io_error() || error.is_connection_refusal() {
+            Error::Connection(error)
+        } else if error.kind() == ErrorKind::ExecAbortError {
+            Error::TransactionAborted(error)
+        } else {
+            Error::RedisError(error)
+        }
     }
 }
 
@@ -49,8 +183,41 @@ impl From<serde_cbor::error::Error> for Error {
     }
 }
 
+mod cluster;
+mod codec;
+pub use codec::{Codec, Migrate, Migrations, Plain};
+#[cfg(any(feature = "zstd", feature = "lz4", feature = "snappy"))]
+mod compression;
+#[cfg(any(feature = "zstd", feature = "lz4", feature = "snappy"))]
+pub use compression::{Compressed, Compression};
+mod connection;
+pub use connection::Connection;
 mod database;
-pub use database::Database;
+pub use database::{Database, OpenMode};
+#[cfg(any(feature = "aes-gcm", feature = "chacha20poly1305"))]
+mod encryption;
+#[cfg(any(feature = "aes-gcm", feature = "chacha20poly1305"))]
+pub use encryption::{Cipher, Encrypted, Keyring};
+mod keys;
+pub use keys::{CollectionNames, KeyInfo, Keys};
+mod memory;
+mod namespace;
+pub use namespace::Namespace;
+mod replicas;
+pub use replicas::{ReadFrom, ReplicaSelection};
+mod resp;
+mod retry;
+pub use retry::RetryPolicy;
+mod sentinel;
+mod timeout;
+pub use timeout::{TimeoutExt, WithTimeout};
+mod script;
+pub use script::{Decoded, Invocation, Script};
 
+/// Synchronous counterparts of `Database` and the collections, whose methods block until they complete.
+pub mod blocking;
 /// Provides types wrapping a variety of redis data structures.
 pub mod collections;
+/// Support for testing code that uses redis-backed against a local fake server.
+#[cfg(feature = "test-util")]
+pub mod test_util;
@@ -15,26 +15,22 @@ fn main() {
         Database::new("redis://127.0.0.1/")
             .map_err(|e| {
                 eprintln!("{:?}", e);
-                ()
             })
-            .and_then(|mut database| {
+            .and_then(|database| {
                 database
                     .get::<List<Person>>("people")
                     .map_err(|e| {
                         eprintln!("{:?}", e);
-                        ()
                     })
                     .and_then(|list| {
                         list.watch()
                             .map_err(|e| {
                                 eprintln!("{:?}", e);
-                                ()
                             })
                             .and_then(|watcher| {
                                 watcher
                                     .map_err(|e| {
                                         eprintln!("{:?}", e);
-                                        ()
                                     })
                                     .for_each(|event| {
                                         println!("{:?}", event);
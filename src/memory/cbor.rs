@@ -0,0 +1,166 @@
+//! A port of the Lua CBOR helpers of the collections (see `collections::lua`), with which the emulated scripts inspect
+//! elements. Positions are zero-based, and `None` is returned where the Lua helpers return `nil` or fail on malformed
+//! input.
+
+use std::convert::TryFrom;
+
+/// A scalar decoded from an element, as the Lua helpers represent it.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Scalar {
+    Number(f64),
+    String(Vec<u8>),
+    Bool(bool),
+}
+
+/// The head of the item at `i`: its major type, its argument (`None` for indefinite lengths) and the position of its
+/// content.
+fn header(s: &[u8], i: usize) -> Option<(u8, Option<u64>, usize)> {
+    let b = *s.get(i)?;
+    let (major, info) = (b >> 5, b & 0x1f);
+    match info {
+        0..=23 => Some((major, Some(u64::from(info)), i + 1)),
+        31 => Some((major, None, i + 1)),
+        24..=27 => {
+            let size = 1 << (info - 24);
+            let bytes = s.get(i + 1..i + 1 + size)?;
+            let value = bytes
+                .iter()
+                .fold(0, |value, byte| (value << 8) | u64::from(*byte));
+            Some((major, Some(value), i + 1 + size))
+        }
+        _ => None,
+    }
+}
+
+fn skip_indefinite(s: &[u8], mut i: usize) -> Option<usize> {
+    while *s.get(i)? != 0xff {
+        i = skip(s, i)?;
+    }
+    Some(i + 1)
+}
+
+/// Returns the position following the item at `i`.
+pub(super) fn skip(s: &[u8], i: usize) -> Option<usize> {
+    let (major, value, mut i) = header(s, i)?;
+    match (major, value) {
+        (2..=5, None) => skip_indefinite(s, i),
+        (2 | 3, Some(length)) => {
+            let end = i.checked_add(usize::try_from(length).ok()?)?;
+            if end > s.len() {
+                return None;
+            }
+            Some(end)
+        }
+        (4 | 5, Some(count)) => {
+            let count = if major == 5 { count * 2 } else { count };
+            for _ in 0..count {
+                i = skip(s, i)?;
+            }
+            Some(i)
+        }
+        (6, _) => skip(s, i),
+        _ => Some(i),
+    }
+}
+
+/// Returns the position of the field `segment` of the map at `i`, or of the element at index `segment` of the array at
+/// `i`.
+fn child(s: &[u8], i: usize, segment: &[u8]) -> Option<usize> {
+    let (major, value, mut i) = header(s, i)?;
+    match major {
+        6 => child(s, i, segment),
+        5 => {
+            let mut remaining = value;
+            while remaining != Some(0) && *s.get(i)? != 0xff {
+                let (key_major, key_length, key_start) = header(s, i)?;
+                let key_end = skip(s, i)?;
+                if key_major == 3 && key_length.is_some() && s[key_start..key_end] == *segment {
+                    return Some(key_end);
+                }
+                i = skip(s, key_end)?;
+                remaining = remaining.map(|remaining| remaining - 1);
+            }
+            None
+        }
+        4 => {
+            let index: u64 = std::str::from_utf8(segment).ok()?.parse().ok()?;
+            if value.is_some_and(|length| index >= length) {
+                return None;
+            }
+            for _ in 0..index {
+                if *s.get(i)? == 0xff {
+                    return None;
+                }
+                i = skip(s, i)?;
+            }
+            (*s.get(i)? != 0xff).then_some(i)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the position of the item designated by `path` within an element, skipping the envelope of a versioned
+/// element.
+pub(super) fn locate(s: &[u8], path: &[Vec<u8>]) -> Option<usize> {
+    let mut i = if s.first() == Some(&0x5c) { 5 } else { 0 };
+    for segment in path {
+        i = child(s, i, segment)?;
+    }
+    Some(i)
+}
+
+/// Decodes the half, single or double precision float of `size` bytes at `i`, where NaN is not a number.
+fn float(s: &[u8], i: usize, size: usize) -> Option<f64> {
+    let bytes = s.get(i..i + size)?;
+    let bits = bytes
+        .iter()
+        .fold(0, |bits, byte| (bits << 8) | u64::from(*byte));
+    let value = match size {
+        2 => {
+            let (sign, exponent, mantissa) = (bits >> 15, (bits >> 10) & 0x1f, bits & 0x3ff);
+            let magnitude = match exponent {
+                0 => mantissa as f64 * 2f64.powi(-24),
+                0x1f if mantissa == 0 => f64::INFINITY,
+                0x1f => f64::NAN,
+                _ => (1.0 + mantissa as f64 / 1024.0) * 2f64.powi(exponent as i32 - 15),
+            };
+            if sign == 1 {
+                -magnitude
+            } else {
+                magnitude
+            }
+        }
+        4 => f64::from(f32::from_bits(bits as u32)),
+        _ => f64::from_bits(bits),
+    };
+    (!value.is_nan()).then_some(value)
+}
+
+/// Decodes the scalar at `i` as a number, string or boolean.
+pub(super) fn decode(s: &[u8], i: usize) -> Option<Scalar> {
+    let info = *s.get(i)? & 0x1f;
+    let (major, value, mut j) = header(s, i)?;
+    match (major, value) {
+        (0, Some(value)) => Some(Scalar::Number(value as f64)),
+        (1, Some(value)) => Some(Scalar::Number(-1.0 - value as f64)),
+        (2 | 3, Some(_)) => Some(Scalar::String(s[j..skip(s, i)?].to_vec())),
+        (2 | 3, None) => {
+            let mut chunks = vec![];
+            while *s.get(j)? != 0xff {
+                let end = skip(s, j)?;
+                let (_, _, start) = header(s, j)?;
+                chunks.extend_from_slice(&s[start..end]);
+                j = end;
+            }
+            Some(Scalar::String(chunks))
+        }
+        (6, _) => decode(s, j),
+        (7, _) => match info {
+            20 => Some(Scalar::Bool(false)),
+            21 => Some(Scalar::Bool(true)),
+            25..=27 => float(s, i + 1, 1 << (info - 24)).map(Scalar::Number),
+            _ => None,
+        },
+        _ => None,
+    }
+}
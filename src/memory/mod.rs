@@ -0,0 +1,1159 @@
+//! An in-process emulation of the subset of redis used by the collections, for testing without a server.
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use redis::{ConnectionLike, RedisError, RedisResult, Value};
+
+use crate::resp::parse_commands;
+
+mod cbor;
+mod scripts;
+mod sort;
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The members of a sorted set along with their scores, ordered by score and then member.
+type SortedSet = Vec<(f64, Vec<u8>)>;
+
+/// A value stored at a key.
+enum Entry {
+    String(Vec<u8>),
+    List(VecDeque<Vec<u8>>),
+    Hash(HashMap<Vec<u8>, Vec<u8>>),
+    Set(HashSet<Vec<u8>>),
+    SortedSet(SortedSet),
+}
+
+impl Entry {
+    fn type_name(&self) -> &'static str {
+        match *self {
+            Entry::String(_) => "string",
+            Entry::List(_) => "list",
+            Entry::Hash(_) => "hash",
+            Entry::Set(_) => "set",
+            Entry::SortedSet(_) => "zset",
+        }
+    }
+    fn is_empty(&self) -> bool {
+        match *self {
+            Entry::String(_) => false,
+            Entry::List(ref list) => list.is_empty(),
+            Entry::Hash(ref hash) => hash.is_empty(),
+            Entry::Set(ref set) => set.is_empty(),
+            Entry::SortedSet(ref set) => set.is_empty(),
+        }
+    }
+}
+
+/// The result of a command, where an error is the line the server replies with, e.g. `ERR syntax error`.
+pub(crate) type Reply<T> = Result<T, String>;
+
+/// The reply to a command executed by a `Session`. EXEC replies with the reply of each queued command, any of which may
+/// be an error, which `Value` cannot represent.
+pub(crate) enum Response {
+    Value(Value),
+    Transaction(Vec<Reply<Value>>),
+}
+
+impl Response {
+    /// Converts the response to the reply that the client reports, which is the first error of a transaction if any
+    /// of its commands failed.
+    fn into_reply(self) -> Reply<Value> {
+        match self {
+            Response::Value(value) => Ok(value),
+            Response::Transaction(replies) => replies
+                .into_iter()
+                .collect::<Reply<Vec<_>>>()
+                .map(Value::Bulk),
+        }
+    }
+}
+
+fn error(line: &str) -> String {
+    line.to_owned()
+}
+
+fn unknown_command(name: &str) -> String {
+    format!(
+        "ERR unknown command '{}', which is not supported by the in-memory backend",
+        name.to_ascii_lowercase()
+    )
+}
+
+/// Produces the error that the client reports for an error reply.
+fn redis_error(line: &str) -> RedisError {
+    match redis::parse_redis_value(format!("-{}\r\n", line).as_bytes()) {
+        Err(err) => err,
+        Ok(_) => unreachable!(),
+    }
+}
+
+fn wrong_type() -> String {
+    error("WRONGTYPE Operation against a key holding the wrong kind of value")
+}
+
+fn syntax_error() -> String {
+    error("ERR syntax error")
+}
+
+fn int(arg: &[u8]) -> Reply<i64> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|arg| arg.parse().ok())
+        .ok_or_else(|| error("ERR value is not an integer or out of range"))
+}
+
+fn float(arg: &[u8]) -> Reply<f64> {
+    match arg.to_ascii_lowercase().as_slice() {
+        b"+inf" | b"inf" => Ok(f64::INFINITY),
+        b"-inf" => Ok(f64::NEG_INFINITY),
+        _ => std::str::from_utf8(arg)
+            .ok()
+            .and_then(|arg| arg.parse().ok())
+            .filter(|value: &f64| !value.is_nan())
+            .ok_or_else(|| error("ERR value is not a valid float")),
+    }
+}
+
+fn data(items: impl IntoIterator<Item = Vec<u8>>) -> Value {
+    Value::Bulk(items.into_iter().map(Value::Data).collect())
+}
+
+/// Resolves a possibly negative index against a collection of length `len`.
+fn resolve(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { index + len as i64 } else { index };
+    if index >= 0 && (index as usize) < len {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+/// Resolves an inclusive range of possibly negative indices as LRANGE and LTRIM do.
+fn resolve_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (start + len).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        stop + len
+    } else {
+        stop.min(len - 1)
+    };
+    if start > stop || start >= len {
+        None
+    } else {
+        Some((start as usize, stop as usize))
+    }
+}
+
+/// Returns true if `text` matches the glob-style `pattern` as interpreted by SCAN MATCH.
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| glob(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && glob(rest, &text[1..]),
+        Some((b'[', rest)) => {
+            let (byte, text) = match text.split_first() {
+                Some(split) => split,
+                None => return false,
+            };
+            let (negate, mut rest) = match rest.split_first() {
+                Some((b'^', rest)) => (true, rest),
+                _ => (false, rest),
+            };
+            let mut matched = false;
+            loop {
+                match rest {
+                    [] => return false,
+                    [b']', tail @ ..] => {
+                        rest = tail;
+                        break;
+                    }
+                    [b'\\', escaped, tail @ ..] => {
+                        matched |= escaped == byte;
+                        rest = tail;
+                    }
+                    [low, b'-', high, tail @ ..] if *high != b']' => {
+                        let (low, high) = if low <= high {
+                            (low, high)
+                        } else {
+                            (high, low)
+                        };
+                        matched |= low <= byte && byte <= high;
+                        rest = tail;
+                    }
+                    [single, tail @ ..] => {
+                        matched |= single == byte;
+                        rest = tail;
+                    }
+                }
+            }
+            matched != negate && glob(rest, text)
+        }
+        Some((b'\\', [escaped, rest @ ..])) => {
+            text.first() == Some(escaped) && glob(rest, &text[1..])
+        }
+        Some((literal, rest)) => text.first() == Some(literal) && glob(rest, &text[1..]),
+    }
+}
+
+/// Converts the blocking commands BLMOVE and BRPOPLPUSH into their non-blocking equivalents, returning the
+/// converted command along with the timeout for which it blocks. Other commands are returned unchanged.
+fn unblock(name: &str, args: Vec<Vec<u8>>) -> Reply<(Vec<Vec<u8>>, Option<f64>)> {
+    let equivalent: &[u8] = match name {
+        "BLMOVE" => b"LMOVE",
+        "BRPOPLPUSH" => b"RPOPLPUSH",
+        _ => return Ok((args, None)),
+    };
+    let mut args = args;
+    if args.len() < 3 {
+        return Err(error(&format!(
+            "ERR wrong number of arguments for '{}' command",
+            name.to_ascii_lowercase()
+        )));
+    }
+    let timeout = float(&args.pop().unwrap())?;
+    if timeout < 0.0 {
+        return Err(error("ERR timeout is negative"));
+    }
+    args[0] = equivalent.to_vec();
+    Ok((args, Some(timeout)))
+}
+
+/// The prefix of the channels on which keyspace notifications are published.
+const KEYSPACE_PREFIX: &[u8] = b"__keyspace@0__:";
+
+#[derive(Default)]
+struct State {
+    keys: HashMap<Vec<u8>, Entry>,
+    subscribers: Vec<(Vec<u8>, Sender<Vec<u8>>)>,
+    /// The digests of the scripts loaded with EVAL or SCRIPT LOAD.
+    scripts: HashSet<String>,
+}
+
+impl State {
+    /// Sends `message` to the subscribers of `channel`, returning the number of subscribers that received it.
+    fn publish(&mut self, channel: &[u8], message: &[u8]) -> usize {
+        let mut received = 0;
+        self.subscribers.retain(|(subscribed, sender)| {
+            if subscribed != channel {
+                return true;
+            }
+            let sent = sender.send(message.to_vec()).is_ok();
+            received += sent as usize;
+            sent
+        });
+        received
+    }
+    /// Publishes the keyspace notification `event` for `key`.
+    fn notify(&mut self, key: &[u8], event: &str) {
+        self.publish(&[KEYSPACE_PREFIX, key].concat(), event.as_bytes());
+    }
+    fn get(&self, key: &[u8]) -> Option<&Entry> {
+        self.keys.get(key)
+    }
+    /// Returns the entry at `key`, creating it with `create` if the key does not exist.
+    fn get_or_create(&mut self, key: &[u8], create: fn() -> Entry) -> &mut Entry {
+        self.keys.entry(key.to_vec()).or_insert_with(create)
+    }
+    /// Removes `key` if it holds an empty collection, as the server does.
+    fn remove_if_empty(&mut self, key: &[u8]) {
+        if self.keys.get(key).is_some_and(Entry::is_empty) {
+            self.keys.remove(key);
+        }
+    }
+    fn list(&self, key: &[u8]) -> Reply<Option<&VecDeque<Vec<u8>>>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Entry::List(list)) => Ok(Some(list)),
+            Some(_) => Err(wrong_type()),
+        }
+    }
+    fn list_mut(&mut self, key: &[u8]) -> Reply<&mut VecDeque<Vec<u8>>> {
+        match self.get_or_create(key, || Entry::List(VecDeque::new())) {
+            Entry::List(list) => Ok(list),
+            _ => Err(wrong_type()),
+        }
+    }
+    fn hash(&self, key: &[u8]) -> Reply<Option<&HashMap<Vec<u8>, Vec<u8>>>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Entry::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(wrong_type()),
+        }
+    }
+    fn hash_mut(&mut self, key: &[u8]) -> Reply<&mut HashMap<Vec<u8>, Vec<u8>>> {
+        match self.get_or_create(key, || Entry::Hash(HashMap::new())) {
+            Entry::Hash(hash) => Ok(hash),
+            _ => Err(wrong_type()),
+        }
+    }
+    fn set(&self, key: &[u8]) -> Reply<Option<&HashSet<Vec<u8>>>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Entry::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(wrong_type()),
+        }
+    }
+    fn set_mut(&mut self, key: &[u8]) -> Reply<&mut HashSet<Vec<u8>>> {
+        match self.get_or_create(key, || Entry::Set(HashSet::new())) {
+            Entry::Set(set) => Ok(set),
+            _ => Err(wrong_type()),
+        }
+    }
+    fn sorted_set(&self, key: &[u8]) -> Reply<Option<&SortedSet>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Entry::SortedSet(set)) => Ok(Some(set)),
+            Some(_) => Err(wrong_type()),
+        }
+    }
+    fn sorted_set_mut(&mut self, key: &[u8]) -> Reply<&mut SortedSet> {
+        match self.get_or_create(key, || Entry::SortedSet(vec![])) {
+            Entry::SortedSet(set) => Ok(set),
+            _ => Err(wrong_type()),
+        }
+    }
+    fn string(&self, key: &[u8]) -> Reply<Option<&Vec<u8>>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Entry::String(string)) => Ok(Some(string)),
+            Some(_) => Err(wrong_type()),
+        }
+    }
+    /// Pops an element from the head (`left`) or tail of the list at `source` and pushes it onto the head (`to_left`)
+    /// or tail of the list at `destination`, as LMOVE does.
+    fn move_element(
+        &mut self,
+        source: &[u8],
+        destination: &[u8],
+        left: bool,
+        to_left: bool,
+    ) -> Reply<Value> {
+        self.list(destination)?;
+        let element = match self.list(source)? {
+            Some(_) => {
+                let list = self.list_mut(source)?;
+                if left {
+                    list.pop_front()
+                } else {
+                    list.pop_back()
+                }
+            }
+            None => None,
+        };
+        let element = match element {
+            Some(element) => element,
+            None => return Ok(Value::Nil),
+        };
+        self.remove_if_empty(source);
+        self.notify(source, if left { "lpop" } else { "rpop" });
+        let list = self.list_mut(destination)?;
+        if to_left {
+            list.push_front(element.clone());
+        } else {
+            list.push_back(element.clone());
+        }
+        self.notify(destination, if to_left { "lpush" } else { "rpush" });
+        Ok(Value::Data(element))
+    }
+    /// Executes a single command, other than MULTI and EXEC, returning its reply.
+    fn execute(&mut self, args: &[Vec<u8>]) -> Reply<Value> {
+        let name = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+        let args = &args[1..];
+        let arity = |valid: bool| {
+            if valid {
+                Ok(())
+            } else {
+                Err(error(&format!(
+                    "ERR wrong number of arguments for '{}' command",
+                    name.to_ascii_lowercase()
+                )))
+            }
+        };
+        let side = |arg: &[u8]| match arg.to_ascii_uppercase().as_slice() {
+            b"LEFT" => Ok(true),
+            b"RIGHT" => Ok(false),
+            _ => Err(syntax_error()),
+        };
+        match name.as_str() {
+            "PING" => Ok(Value::Status("PONG".to_owned())),
+            "SELECT" | "WATCH" | "UNWATCH" => Ok(Value::Okay),
+            "CONFIG" => match args.first().map(|arg| arg.to_ascii_uppercase()) {
+                Some(ref subcommand) if subcommand == b"SET" => Ok(Value::Okay),
+                Some(ref subcommand) if subcommand == b"GET" => Ok(Value::Bulk(vec![])),
+                _ => Err(syntax_error()),
+            },
+            "PUBLISH" => {
+                arity(args.len() == 2)?;
+                Ok(Value::Int(self.publish(&args[0], &args[1]) as i64))
+            }
+            "TYPE" => {
+                arity(args.len() == 1)?;
+                Ok(Value::Status(
+                    self.get(&args[0])
+                        .map_or("none", Entry::type_name)
+                        .to_owned(),
+                ))
+            }
+            "DEL" | "UNLINK" => {
+                arity(!args.is_empty())?;
+                let mut removed = 0;
+                for key in args {
+                    if self.keys.remove(key).is_some() {
+                        removed += 1;
+                        self.notify(key, "del");
+                    }
+                }
+                Ok(Value::Int(removed))
+            }
+            "EXISTS" => {
+                arity(!args.is_empty())?;
+                Ok(Value::Int(
+                    args.iter()
+                        .filter(|key| self.keys.contains_key(*key))
+                        .count() as i64,
+                ))
+            }
+            "FLUSHDB" | "FLUSHALL" => {
+                self.keys.clear();
+                Ok(Value::Okay)
+            }
+            "SCAN" => {
+                arity(!args.is_empty())?;
+                let cursor = int(&args[0])?.max(0) as usize;
+                let (mut pattern, mut count, mut key_type) = (None, 10, None);
+                for option in args[1..].chunks(2) {
+                    let value = match option {
+                        [_, value] => value,
+                        _ => return Err(syntax_error()),
+                    };
+                    match option[0].to_ascii_uppercase().as_slice() {
+                        b"MATCH" => pattern = Some(value),
+                        b"COUNT" => count = int(value)?.max(1) as usize,
+                        b"TYPE" => key_type = Some(String::from_utf8_lossy(value).to_lowercase()),
+                        _ => return Err(syntax_error()),
+                    }
+                }
+                let mut keys: Vec<&Vec<u8>> = self.keys.keys().collect();
+                keys.sort();
+                let end = (cursor + count).min(keys.len());
+                let next = if end >= keys.len() { 0 } else { end };
+                let page = keys
+                    .get(cursor.min(keys.len())..end)
+                    .unwrap_or(&[])
+                    .iter()
+                    .filter(|key| pattern.is_none_or(|pattern| glob(pattern, key)))
+                    .filter(|key| {
+                        key_type
+                            .as_ref()
+                            .is_none_or(|key_type| self.keys[**key].type_name() == key_type)
+                    })
+                    .map(|key| (*key).clone());
+                Ok(Value::Bulk(vec![
+                    Value::Data(next.to_string().into_bytes()),
+                    data(page),
+                ]))
+            }
+            "GET" => {
+                arity(args.len() == 1)?;
+                Ok(self
+                    .string(&args[0])?
+                    .cloned()
+                    .map_or(Value::Nil, Value::Data))
+            }
+            "MGET" => {
+                arity(!args.is_empty())?;
+                Ok(Value::Bulk(
+                    args.iter()
+                        .map(|key| match self.get(key) {
+                            Some(Entry::String(string)) => Value::Data(string.clone()),
+                            _ => Value::Nil,
+                        })
+                        .collect(),
+                ))
+            }
+            "SET" => {
+                arity(args.len() == 2)?;
+                self.keys
+                    .insert(args[0].clone(), Entry::String(args[1].clone()));
+                self.notify(&args[0], "set");
+                Ok(Value::Okay)
+            }
+            "INCR" | "INCRBY" | "DECR" | "DECRBY" => {
+                let by = match name.as_str() {
+                    "INCR" | "DECR" => {
+                        arity(args.len() == 1)?;
+                        1
+                    }
+                    _ => {
+                        arity(args.len() == 2)?;
+                        int(&args[1])?
+                    }
+                };
+                let by = if name.starts_with("DECR") { -by } else { by };
+                let current = match self.string(&args[0])? {
+                    Some(value) => int(value)?,
+                    None => 0,
+                };
+                let value = current
+                    .checked_add(by)
+                    .ok_or_else(|| error("ERR increment or decrement would overflow"))?;
+                self.keys.insert(
+                    args[0].clone(),
+                    Entry::String(value.to_string().into_bytes()),
+                );
+                self.notify(&args[0], if by < 0 { "decrby" } else { "incrby" });
+                Ok(Value::Int(value))
+            }
+            "LPUSH" | "RPUSH" => {
+                arity(args.len() >= 2)?;
+                let list = self.list_mut(&args[0])?;
+                for item in &args[1..] {
+                    if name == "LPUSH" {
+                        list.push_front(item.clone());
+                    } else {
+                        list.push_back(item.clone());
+                    }
+                }
+                let len = list.len() as i64;
+                self.notify(&args[0], &name.to_ascii_lowercase());
+                Ok(Value::Int(len))
+            }
+            "LPOP" | "RPOP" => {
+                arity(args.len() == 1)?;
+                if self.list(&args[0])?.is_none() {
+                    return Ok(Value::Nil);
+                }
+                let list = self.list_mut(&args[0])?;
+                let item = if name == "LPOP" {
+                    list.pop_front()
+                } else {
+                    list.pop_back()
+                };
+                self.remove_if_empty(&args[0]);
+                self.notify(&args[0], &name.to_ascii_lowercase());
+                Ok(item.map_or(Value::Nil, Value::Data))
+            }
+            "LLEN" => {
+                arity(args.len() == 1)?;
+                Ok(Value::Int(
+                    self.list(&args[0])?.map_or(0, VecDeque::len) as i64
+                ))
+            }
+            "LINDEX" => {
+                arity(args.len() == 2)?;
+                let index = int(&args[1])?;
+                Ok(self
+                    .list(&args[0])?
+                    .and_then(|list| resolve(index, list.len()).map(|index| list[index].clone()))
+                    .map_or(Value::Nil, Value::Data))
+            }
+            "LSET" => {
+                arity(args.len() == 3)?;
+                let index = int(&args[1])?;
+                if self.list(&args[0])?.is_none() {
+                    return Err(error("ERR no such key"));
+                }
+                let list = self.list_mut(&args[0])?;
+                match resolve(index, list.len()) {
+                    Some(index) => list[index] = args[2].clone(),
+                    None => return Err(error("ERR index out of range")),
+                }
+                self.notify(&args[0], "lset");
+                Ok(Value::Okay)
+            }
+            "LRANGE" => {
+                arity(args.len() == 3)?;
+                let (start, stop) = (int(&args[1])?, int(&args[2])?);
+                Ok(data(self.list(&args[0])?.map_or(
+                    vec![],
+                    |list| match resolve_range(start, stop, list.len()) {
+                        Some((start, stop)) => list.range(start..=stop).cloned().collect(),
+                        None => vec![],
+                    },
+                )))
+            }
+            "LTRIM" => {
+                arity(args.len() == 3)?;
+                let (start, stop) = (int(&args[1])?, int(&args[2])?);
+                if self.list(&args[0])?.is_none() {
+                    return Ok(Value::Okay);
+                }
+                let list = self.list_mut(&args[0])?;
+                match resolve_range(start, stop, list.len()) {
+                    Some((start, stop)) => {
+                        list.truncate(stop + 1);
+                        list.drain(..start);
+                    }
+                    None => list.clear(),
+                }
+                self.remove_if_empty(&args[0]);
+                self.notify(&args[0], "ltrim");
+                Ok(Value::Okay)
+            }
+            "LREM" => {
+                arity(args.len() == 3)?;
+                let count = int(&args[1])?;
+                if self.list(&args[0])?.is_none() {
+                    return Ok(Value::Int(0));
+                }
+                let list = self.list_mut(&args[0])?;
+                let limit = if count == 0 {
+                    usize::MAX
+                } else {
+                    count.unsigned_abs() as usize
+                };
+                let mut indices: Vec<usize> = (0..list.len()).collect();
+                if count < 0 {
+                    indices.reverse();
+                }
+                let mut matches: Vec<usize> = indices
+                    .into_iter()
+                    .filter(|index| list[*index] == args[2])
+                    .take(limit)
+                    .collect();
+                matches.sort_unstable();
+                for index in matches.iter().rev() {
+                    list.remove(*index);
+                }
+                self.remove_if_empty(&args[0]);
+                if !matches.is_empty() {
+                    self.notify(&args[0], "lrem");
+                }
+                Ok(Value::Int(matches.len() as i64))
+            }
+            "LINSERT" => {
+                arity(args.len() == 4)?;
+                let after = match args[1].to_ascii_uppercase().as_slice() {
+                    b"BEFORE" => false,
+                    b"AFTER" => true,
+                    _ => return Err(syntax_error()),
+                };
+                if self.list(&args[0])?.is_none() {
+                    return Ok(Value::Int(0));
+                }
+                let list = self.list_mut(&args[0])?;
+                match list.iter().position(|item| *item == args[2]) {
+                    Some(index) => {
+                        list.insert(if after { index + 1 } else { index }, args[3].clone());
+                        let len = list.len() as i64;
+                        self.notify(&args[0], "linsert");
+                        Ok(Value::Int(len))
+                    }
+                    None => Ok(Value::Int(-1)),
+                }
+            }
+            "LPOS" => {
+                arity(args.len() >= 2)?;
+                let (mut rank, mut count, mut max_len) = (1, None, 0);
+                for option in args[2..].chunks(2) {
+                    let value = match option {
+                        [_, value] => int(value)?,
+                        _ => return Err(syntax_error()),
+                    };
+                    match option[0].to_ascii_uppercase().as_slice() {
+                        b"RANK" if value == 0 => {
+                            return Err(error(
+                                "ERR RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list",
+                            ))
+                        }
+                        b"RANK" => rank = value,
+                        b"COUNT" if value < 0 => return Err(error("ERR COUNT can't be negative")),
+                        b"COUNT" => count = Some(value as usize),
+                        b"MAXLEN" if value < 0 => {
+                            return Err(error("ERR MAXLEN can't be negative"))
+                        }
+                        b"MAXLEN" => max_len = value as usize,
+                        _ => return Err(syntax_error()),
+                    }
+                }
+                let list = self.list(&args[0])?;
+                let len = list.map_or(0, VecDeque::len);
+                let mut indices: Vec<usize> = (0..len).collect();
+                if rank < 0 {
+                    indices.reverse();
+                }
+                if max_len > 0 {
+                    indices.truncate(max_len);
+                }
+                let matches = indices
+                    .into_iter()
+                    .filter(|index| list.is_some_and(|list| list[*index] == args[1]))
+                    .skip(rank.unsigned_abs() as usize - 1)
+                    .map(|index| Value::Int(index as i64));
+                match count {
+                    None => Ok(matches.take(1).next().unwrap_or(Value::Nil)),
+                    Some(0) => Ok(Value::Bulk(matches.collect())),
+                    Some(count) => Ok(Value::Bulk(matches.take(count).collect())),
+                }
+            }
+            "LMOVE" => {
+                arity(args.len() == 4)?;
+                let (left, to_left) = (side(&args[2])?, side(&args[3])?);
+                self.move_element(&args[0], &args[1], left, to_left)
+            }
+            "RPOPLPUSH" => {
+                arity(args.len() == 2)?;
+                self.move_element(&args[0], &args[1], false, true)
+            }
+            "HSET" | "HMSET" => {
+                arity(args.len() >= 3 && args.len() % 2 == 1)?;
+                let hash = self.hash_mut(&args[0])?;
+                let added = args[1..]
+                    .chunks(2)
+                    .filter(|pair| hash.insert(pair[0].clone(), pair[1].clone()).is_none())
+                    .count();
+                self.notify(&args[0], "hset");
+                Ok(if name == "HSET" {
+                    Value::Int(added as i64)
+                } else {
+                    Value::Okay
+                })
+            }
+            "HGET" => {
+                arity(args.len() == 2)?;
+                Ok(self
+                    .hash(&args[0])?
+                    .and_then(|hash| hash.get(&args[1]).cloned())
+                    .map_or(Value::Nil, Value::Data))
+            }
+            "HMGET" => {
+                arity(args.len() >= 2)?;
+                let hash = self.hash(&args[0])?;
+                Ok(Value::Bulk(
+                    args[1..]
+                        .iter()
+                        .map(|field| {
+                            hash.and_then(|hash| hash.get(field).cloned())
+                                .map_or(Value::Nil, Value::Data)
+                        })
+                        .collect(),
+                ))
+            }
+            "HDEL" => {
+                arity(args.len() >= 2)?;
+                if self.hash(&args[0])?.is_none() {
+                    return Ok(Value::Int(0));
+                }
+                let hash = self.hash_mut(&args[0])?;
+                let removed = args[1..]
+                    .iter()
+                    .filter(|field| hash.remove(*field).is_some())
+                    .count();
+                self.remove_if_empty(&args[0]);
+                if removed > 0 {
+                    self.notify(&args[0], "hdel");
+                }
+                Ok(Value::Int(removed as i64))
+            }
+            "HEXISTS" => {
+                arity(args.len() == 2)?;
+                Ok(Value::Int(
+                    self.hash(&args[0])?
+                        .is_some_and(|hash| hash.contains_key(&args[1])) as i64,
+                ))
+            }
+            "HLEN" => {
+                arity(args.len() == 1)?;
+                Ok(Value::Int(
+                    self.hash(&args[0])?.map_or(0, HashMap::len) as i64
+                ))
+            }
+            "HKEYS" | "HVALS" | "HGETALL" => {
+                arity(args.len() == 1)?;
+                let hash = match self.hash(&args[0])? {
+                    Some(hash) => hash,
+                    None => return Ok(Value::Bulk(vec![])),
+                };
+                Ok(data(hash.iter().flat_map(
+                    |(field, value)| match name.as_str() {
+                        "HKEYS" => vec![field.clone()],
+                        "HVALS" => vec![value.clone()],
+                        _ => vec![field.clone(), value.clone()],
+                    },
+                )))
+            }
+            "SADD" => {
+                arity(args.len() >= 2)?;
+                let set = self.set_mut(&args[0])?;
+                let added = args[1..]
+                    .iter()
+                    .filter(|member| set.insert((*member).clone()))
+                    .count();
+                self.notify(&args[0], "sadd");
+                Ok(Value::Int(added as i64))
+            }
+            "SREM" => {
+                arity(args.len() >= 2)?;
+                if self.set(&args[0])?.is_none() {
+                    return Ok(Value::Int(0));
+                }
+                let set = self.set_mut(&args[0])?;
+                let removed = args[1..]
+                    .iter()
+                    .filter(|member| set.remove(*member))
+                    .count();
+                self.remove_if_empty(&args[0]);
+                if removed > 0 {
+                    self.notify(&args[0], "srem");
+                }
+                Ok(Value::Int(removed as i64))
+            }
+            "SMEMBERS" => {
+                arity(args.len() == 1)?;
+                Ok(data(self.set(&args[0])?.into_iter().flatten().cloned()))
+            }
+            "SISMEMBER" => {
+                arity(args.len() == 2)?;
+                Ok(Value::Int(
+                    self.set(&args[0])?
+                        .is_some_and(|set| set.contains(&args[1])) as i64,
+                ))
+            }
+            "SCARD" => {
+                arity(args.len() == 1)?;
+                Ok(Value::Int(
+                    self.set(&args[0])?.map_or(0, HashSet::len) as i64
+                ))
+            }
+            "ZADD" => {
+                arity(args.len() >= 3 && args.len() % 2 == 1)?;
+                let scores = args[1..]
+                    .chunks(2)
+                    .map(|pair| Ok((float(&pair[0])?, pair[1].clone())))
+                    .collect::<Reply<Vec<_>>>()?;
+                let set = self.sorted_set_mut(&args[0])?;
+                let mut added = 0;
+                for (score, member) in scores {
+                    match set.iter().position(|(_, existing)| *existing == member) {
+                        Some(index) => set[index].0 = score,
+                        None => {
+                            set.push((score, member));
+                            added += 1;
+                        }
+                    }
+                }
+                set.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then_with(|| a.1.cmp(&b.1)));
+                self.notify(&args[0], "zadd");
+                Ok(Value::Int(added))
+            }
+            "ZREM" => {
+                arity(args.len() >= 2)?;
+                if self.sorted_set(&args[0])?.is_none() {
+                    return Ok(Value::Int(0));
+                }
+                let set = self.sorted_set_mut(&args[0])?;
+                let len = set.len();
+                set.retain(|(_, member)| !args[1..].contains(member));
+                let removed = len - set.len();
+                self.remove_if_empty(&args[0]);
+                if removed > 0 {
+                    self.notify(&args[0], "zrem");
+                }
+                Ok(Value::Int(removed as i64))
+            }
+            "ZSCORE" => {
+                arity(args.len() == 2)?;
+                Ok(self
+                    .sorted_set(&args[0])?
+                    .and_then(|set| set.iter().find(|(_, member)| *member == args[1]))
+                    .map_or(Value::Nil, |(score, _)| {
+                        Value::Data(score.to_string().into_bytes())
+                    }))
+            }
+            "ZCARD" => {
+                arity(args.len() == 1)?;
+                Ok(Value::Int(
+                    self.sorted_set(&args[0])?.map_or(0, Vec::len) as i64
+                ))
+            }
+            "ZRANGE" => {
+                arity(args.len() == 3)?;
+                let (start, stop) = (int(&args[1])?, int(&args[2])?);
+                Ok(data(self.sorted_set(&args[0])?.map_or(vec![], |set| {
+                    match resolve_range(start, stop, set.len()) {
+                        Some((start, stop)) => set[start..=stop]
+                            .iter()
+                            .map(|(_, member)| member.clone())
+                            .collect(),
+                        None => vec![],
+                    }
+                })))
+            }
+            "EVAL" | "EVALSHA" | "SCRIPT" => scripts::execute(self, &name, args),
+            "SORT" => sort::sort(self, args),
+            _ => Err(unknown_command(&name)),
+        }
+    }
+}
+
+/// An in-process database emulating the commands and keyspace notifications of lists, hashes, sets, sorted sets and
+/// strings, along with SORT. Rather than interpreting Lua, the scripts executed by the collections are emulated, so any
+/// other script is rejected.
+#[derive(Default)]
+pub(crate) struct Memory {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Memory {
+    /// Returns a receiver of the messages published on `channel`.
+    pub(crate) fn subscribe(&self, channel: &[u8]) -> Receiver<Vec<u8>> {
+        let (sender, receiver) = unbounded();
+        self.state
+            .lock()
+            .unwrap()
+            .subscribers
+            .push((channel.to_vec(), sender));
+        receiver
+    }
+}
+
+/// The commands supported by the in-memory backend along with their arities as given by redis, i.e. the number of
+/// arguments including the name, or the negated minimum number for commands that take a variable number.
+const COMMANDS: &[(&str, i32)] = &[
+    ("PING", -1),
+    ("SELECT", 2),
+    ("WATCH", -2),
+    ("UNWATCH", 1),
+    ("CONFIG", -2),
+    ("PUBLISH", 3),
+    ("TYPE", 2),
+    ("DEL", -2),
+    ("UNLINK", -2),
+    ("EXISTS", -2),
+    ("FLUSHDB", -1),
+    ("FLUSHALL", -1),
+    ("SCAN", -2),
+    ("GET", 2),
+    ("MGET", -2),
+    ("SET", -3),
+    ("INCR", 2),
+    ("INCRBY", 3),
+    ("DECR", 2),
+    ("DECRBY", 3),
+    ("LPUSH", -3),
+    ("RPUSH", -3),
+    ("LPOP", -2),
+    ("RPOP", -2),
+    ("LLEN", 2),
+    ("LINDEX", 3),
+    ("LSET", 4),
+    ("LRANGE", 4),
+    ("LTRIM", 4),
+    ("LREM", 4),
+    ("LINSERT", 5),
+    ("LPOS", -3),
+    ("LMOVE", 5),
+    ("BLMOVE", 6),
+    ("RPOPLPUSH", 3),
+    ("BRPOPLPUSH", 4),
+    ("HSET", -4),
+    ("HMSET", -4),
+    ("HGET", 3),
+    ("HMGET", -3),
+    ("HDEL", -3),
+    ("HEXISTS", 3),
+    ("HLEN", 2),
+    ("HKEYS", 2),
+    ("HVALS", 2),
+    ("HGETALL", 2),
+    ("SADD", -3),
+    ("SREM", -3),
+    ("SMEMBERS", 2),
+    ("SISMEMBER", 3),
+    ("SCARD", 2),
+    ("ZADD", -4),
+    ("ZREM", -3),
+    ("ZSCORE", 3),
+    ("ZCARD", 2),
+    ("ZRANGE", -4),
+    ("EVAL", -3),
+    ("EVALSHA", -3),
+    ("SCRIPT", -2),
+    ("SORT", -2),
+];
+
+/// Checks that a command is known and has a valid number of arguments, as redis does before queuing a command in a
+/// transaction.
+fn check_command(name: &str, args: &[Vec<u8>]) -> Reply<()> {
+    let arity = match COMMANDS.iter().find(|(command, _)| *command == name) {
+        Some(&(_, arity)) => arity,
+        None => return Err(unknown_command(name)),
+    };
+    let count = args.len() as i32;
+    if count == arity || (arity < 0 && count >= -arity) {
+        Ok(())
+    } else {
+        Err(error(&format!(
+            "ERR wrong number of arguments for '{}' command",
+            name.to_ascii_lowercase()
+        )))
+    }
+}
+
+/// A transaction opened by MULTI: the commands it has queued and whether one was rejected while being queued, in which
+/// case EXEC discards it.
+#[derive(Default)]
+struct Transaction {
+    queued: Vec<Vec<Vec<u8>>>,
+    aborted: bool,
+}
+
+/// A client of an in-memory database, along with its open transaction, if any.
+pub(crate) struct Session {
+    memory: Arc<Memory>,
+    transaction: Option<Transaction>,
+}
+
+impl Session {
+    pub(crate) fn new(memory: Arc<Memory>) -> Self {
+        Session {
+            memory,
+            transaction: None,
+        }
+    }
+    /// Executes a command, returning its reply. Commands issued after MULTI are queued and performed atomically by
+    /// EXEC, which performs every queued command even if some of them fail. As in redis, a command that is unknown or
+    /// has the wrong number of arguments is instead rejected when queued, and EXEC then discards the transaction with
+    /// an EXECABORT error. Blocking commands outside of a transaction release the database while they wait.
+    pub(crate) fn execute(&mut self, args: Vec<Vec<u8>>) -> Reply<Response> {
+        let name = match args.first() {
+            Some(name) => String::from_utf8_lossy(name).to_ascii_uppercase(),
+            None => return Err(error("ERR Protocol error: empty command")),
+        };
+        let reply = match (name.as_str(), self.transaction.as_mut()) {
+            ("MULTI", Some(_)) => return Err(error("ERR MULTI calls can not be nested")),
+            ("MULTI", None) => {
+                self.transaction = Some(Transaction::default());
+                return Ok(Response::Value(Value::Okay));
+            }
+            ("EXEC", None) => return Err(error("ERR EXEC without MULTI")),
+            ("DISCARD", None) => return Err(error("ERR DISCARD without MULTI")),
+            ("DISCARD", Some(_)) => {
+                self.transaction = None;
+                return Ok(Response::Value(Value::Okay));
+            }
+            ("EXEC", Some(_)) => {
+                let transaction = self.transaction.take().unwrap();
+                if transaction.aborted {
+                    return Err(error(
+                        "EXECABORT Transaction discarded because of previous errors.",
+                    ));
+                }
+                let mut state = self.memory.state.lock().unwrap();
+                Ok(Response::Transaction(
+                    transaction
+                        .queued
+                        .into_iter()
+                        .map(|args| {
+                            let name = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+                            state.execute(&unblock(&name, args)?.0)
+                        })
+                        .collect(),
+                ))
+            }
+            (_, Some(transaction)) => {
+                if let Err(line) = check_command(&name, &args) {
+                    transaction.aborted = true;
+                    return Err(line);
+                }
+                transaction.queued.push(args);
+                return Ok(Response::Value(Value::Status("QUEUED".to_owned())));
+            }
+            (_, None) => {
+                let (command, timeout) = unblock(&name, args)?;
+                let mut state = self.memory.state.lock().unwrap();
+                match timeout {
+                    None => state.execute(&command).map(Response::Value),
+                    Some(timeout) => {
+                        let deadline = if timeout > 0.0 {
+                            Some(Instant::now() + Duration::from_secs_f64(timeout))
+                        } else {
+                            None
+                        };
+                        loop {
+                            let now = Instant::now();
+                            match (state.execute(&command)?, deadline) {
+                                (Value::Nil, Some(deadline)) if now < deadline => {
+                                    state = self
+                                        .memory
+                                        .changed
+                                        .wait_timeout(state, deadline - now)
+                                        .unwrap()
+                                        .0
+                                }
+                                (Value::Nil, None) => {
+                                    state = self.memory.changed.wait(state).unwrap()
+                                }
+                                (reply, _) => break Ok(Response::Value(reply)),
+                            }
+                        }
+                    }
+                }
+            }
+        };
+        self.memory.changed.notify_all();
+        reply
+    }
+}
+
+/// A connection to an in-memory database.
+pub(crate) struct MemoryConnection {
+    session: Session,
+}
+
+impl MemoryConnection {
+    pub(crate) fn new(memory: Arc<Memory>) -> Self {
+        MemoryConnection {
+            session: Session::new(memory),
+        }
+    }
+    /// Executes a sequence of commands, returning the reply to each. As with a pipeline sent to redis, every command is
+    /// executed even if an earlier one fails.
+    fn run(&mut self, cmd: &[u8]) -> RedisResult<Vec<Reply<Value>>> {
+        let commands = parse_commands(cmd).ok_or_else(|| redis_error("ERR Protocol error"))?;
+        Ok(commands
+            .into_iter()
+            .map(|args| self.session.execute(args).and_then(Response::into_reply))
+            .collect())
+    }
+}
+
+impl ConnectionLike for MemoryConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.req_packed_commands(cmd, 0, usize::MAX)
+            .map(|mut values| values.pop().unwrap_or(Value::Nil))
+    }
+
+    /// Reports the first error among the replies up to those requested, as redis does when reading the replies to a
+    /// pipeline.
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let replies = self
+            .run(cmd)?
+            .into_iter()
+            .take(offset.saturating_add(count))
+            .collect::<Reply<Vec<_>>>()
+            .map_err(|line| redis_error(&line))?;
+        Ok(replies.into_iter().skip(offset).collect())
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests;
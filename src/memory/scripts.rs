@@ -0,0 +1,321 @@
+//! Emulations of the Lua scripts executed by the collections, which the in-memory backend performs in place of a Lua
+//! interpreter. Scripts are recognised by their SHA1 digest, so any other script is rejected.
+
+use redis::Value;
+
+use super::{
+    cbor::{self, Scalar},
+    error, float, int, Reply, State,
+};
+use crate::collections::{
+    capped::PUSH_EVICTING_SCRIPT,
+    list::{FIND_BY_FIELD_SCRIPT, WRITE_BACK_SCRIPT},
+    lua::with_cbor,
+    queue::REQUEUE_SCRIPT,
+    sort::SORT_BY_KEY_SCRIPT,
+};
+
+use std::{cmp::Ordering, collections::HashMap, sync::OnceLock};
+
+/// An emulation of a script, performed with the keys and arguments of its invocation.
+type Emulation = fn(&mut State, &[Vec<u8>], &[Vec<u8>]) -> Reply<Value>;
+
+/// Returns the emulations of the scripts of the collections by the digest of their source.
+fn emulations() -> &'static HashMap<String, Emulation> {
+    static EMULATIONS: OnceLock<HashMap<String, Emulation>> = OnceLock::new();
+    EMULATIONS.get_or_init(|| {
+        let scripts: [(String, Emulation); 5] = [
+            (PUSH_EVICTING_SCRIPT.to_owned(), push_evicting),
+            (REQUEUE_SCRIPT.to_owned(), requeue),
+            (with_cbor(FIND_BY_FIELD_SCRIPT), find_by_field),
+            (with_cbor(SORT_BY_KEY_SCRIPT), sort_by_key),
+            (WRITE_BACK_SCRIPT.to_owned(), write_back),
+        ];
+        scripts
+            .iter()
+            .map(|(code, emulation)| (digest(code.as_bytes()), *emulation))
+            .collect()
+    })
+}
+
+/// Returns the SHA1 digest of a script in hexadecimal, by which the server caches it.
+fn digest(code: &[u8]) -> String {
+    redis::Script::new(&String::from_utf8_lossy(code))
+        .get_hash()
+        .to_owned()
+}
+
+fn unsupported() -> String {
+    error("ERR the in-memory backend only emulates the scripts of the collections, not arbitrary scripts")
+}
+
+/// Executes EVAL, EVALSHA or SCRIPT, where `args` excludes the name of the command.
+pub(super) fn execute(state: &mut State, name: &str, args: &[Vec<u8>]) -> Reply<Value> {
+    let arity = |valid: bool| {
+        if valid {
+            Ok(())
+        } else {
+            Err(error(&format!(
+                "ERR wrong number of arguments for '{}' command",
+                name.to_ascii_lowercase()
+            )))
+        }
+    };
+    match name {
+        "EVAL" | "EVALSHA" => {
+            arity(args.len() >= 2)?;
+            let sha = if name == "EVAL" {
+                let sha = digest(&args[0]);
+                if !emulations().contains_key(&sha) {
+                    return Err(unsupported());
+                }
+                state.scripts.insert(sha.clone());
+                sha
+            } else {
+                String::from_utf8_lossy(&args[0]).to_ascii_lowercase()
+            };
+            if !state.scripts.contains(&sha) {
+                return Err(error("NOSCRIPT No matching script. Please use EVAL."));
+            }
+            let count = int(&args[1])?;
+            if count < 0 {
+                return Err(error("ERR Number of keys can't be negative"));
+            }
+            let count = count as usize;
+            if count > args.len() - 2 {
+                return Err(error(
+                    "ERR Number of keys can't be greater than number of args",
+                ));
+            }
+            let (keys, argv) = args[2..].split_at(count);
+            emulations()[&sha](state, keys, argv)
+                .map_err(|line| format!("ERR Error running script (call to f_{}): {}", sha, line))
+        }
+        "SCRIPT" => {
+            arity(!args.is_empty())?;
+            match args[0].to_ascii_uppercase().as_slice() {
+                b"LOAD" => {
+                    arity(args.len() == 2)?;
+                    let sha = digest(&args[1]);
+                    if !emulations().contains_key(&sha) {
+                        return Err(unsupported());
+                    }
+                    state.scripts.insert(sha.clone());
+                    Ok(Value::Data(sha.into_bytes()))
+                }
+                b"EXISTS" => Ok(Value::Bulk(
+                    args[1..]
+                        .iter()
+                        .map(|sha| {
+                            let sha = String::from_utf8_lossy(sha).to_ascii_lowercase();
+                            Value::Int(state.scripts.contains(&sha) as i64)
+                        })
+                        .collect(),
+                )),
+                b"FLUSH" => {
+                    state.scripts.clear();
+                    Ok(Value::Okay)
+                }
+                _ => Err(error("ERR unknown subcommand for 'script' command")),
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Performs a command from within a script, as `redis.call` does.
+fn call(state: &mut State, args: &[&[u8]]) -> Reply<Value> {
+    let args: Vec<Vec<u8>> = args.iter().map(|arg| arg.to_vec()).collect();
+    state.execute(&args)
+}
+
+/// Returns the argument at `index`, which is `nil` in Lua and so rejected by `redis.call` if it is missing.
+fn arg(args: &[Vec<u8>], index: usize) -> Reply<&[u8]> {
+    args.get(index)
+        .map(Vec::as_slice)
+        .ok_or_else(|| error("ERR Lua redis() command arguments must be strings or integers"))
+}
+
+/// Converts an argument to a number as `tonumber` does, failing where it would be `nil`.
+fn number(arg: &[u8]) -> Reply<f64> {
+    float(arg).map_err(|_| error("ERR attempt to perform arithmetic on a nil value"))
+}
+
+/// Formats a number as Lua converts it to a string for `redis.call`.
+fn format_number(value: f64) -> Vec<u8> {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64).into_bytes()
+    } else {
+        format!("{}", value).into_bytes()
+    }
+}
+
+/// Returns the elements of a list reply.
+fn elements(reply: Value) -> Vec<Vec<u8>> {
+    match reply {
+        Value::Bulk(items) => items
+            .into_iter()
+            .filter_map(|item| match item {
+                Value::Data(data) => Some(data),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// `CappedList::push_front`/`push_back`: pushes `ARGV[3]` with the command `ARGV[1]` and pops the elements in excess of
+/// the limit `ARGV[2]` with the command `ARGV[4]`, returning them.
+fn push_evicting(state: &mut State, keys: &[Vec<u8>], args: &[Vec<u8>]) -> Reply<Value> {
+    let key = arg(keys, 0)?;
+    let len = match call(state, &[arg(args, 0)?, key, arg(args, 2)?])? {
+        Value::Int(len) => len as f64,
+        _ => {
+            return Err(error(
+                "ERR attempt to perform arithmetic on a non-number value",
+            ))
+        }
+    };
+    let excess = len - number(arg(args, 1)?)?;
+    let mut evicted = vec![];
+    while (evicted.len() as f64) < excess.floor() {
+        evicted.push(call(state, &[arg(args, 3)?, key])?);
+    }
+    Ok(Value::Bulk(evicted))
+}
+
+/// `ReliableQueue::requeue_expired`: grants a lease ending at `ARGV[1] + ARGV[2]` to each element being processed in
+/// `KEYS[2]` without one in `KEYS[3]`, and returns those whose lease ended by `ARGV[1]` to the queue `KEYS[1]`.
+fn requeue(state: &mut State, keys: &[Vec<u8>], args: &[Vec<u8>]) -> Reply<Value> {
+    let (queue, processing, leases) = (arg(keys, 0)?, arg(keys, 1)?, arg(keys, 2)?);
+    let (now, timeout) = (number(arg(args, 0)?)?, number(arg(args, 1)?)?);
+    let mut requeued = 0;
+    for item in elements(call(state, &[b"LRANGE", processing, b"0", b"-1"])?) {
+        match call(state, &[b"ZSCORE", leases, &item])? {
+            Value::Data(deadline) => {
+                if number(&deadline)? <= now {
+                    call(state, &[b"LREM", processing, b"1", &item])?;
+                    call(state, &[b"ZREM", leases, &item])?;
+                    call(state, &[b"RPUSH", queue, &item])?;
+                    requeued += 1;
+                }
+            }
+            _ => {
+                call(
+                    state,
+                    &[b"ZADD", leases, &format_number(now + timeout), &item],
+                )?;
+            }
+        }
+    }
+    Ok(Value::Int(requeued))
+}
+
+/// `List::find_by_field`: returns the index and data of up to `ARGV[2]` elements whose field at the path `ARGV[3..]`
+/// is encoded as `ARGV[1]`, flattened into a single array.
+fn find_by_field(state: &mut State, keys: &[Vec<u8>], args: &[Vec<u8>]) -> Reply<Value> {
+    let key = arg(keys, 0)?;
+    let expected = arg(args, 0)?;
+    let count = args.get(1).and_then(|count| float(count).ok());
+    let path = args.get(2..).unwrap_or_default();
+    let mut matches = vec![];
+    for (index, element) in elements(call(state, &[b"LRANGE", key, b"0", b"-1"])?)
+        .into_iter()
+        .enumerate()
+    {
+        let field =
+            cbor::locate(&element, path).and_then(|i| Some(&element[i..cbor::skip(&element, i)?]));
+        if field == Some(expected) {
+            matches.push(Value::Int(index as i64));
+            matches.push(Value::Data(element));
+            if count == Some((matches.len() / 2) as f64) {
+                break;
+            }
+        }
+    }
+    Ok(Value::Bulk(matches))
+}
+
+/// The key by which `sort_by_key` orders an element.
+enum SortKey {
+    Missing,
+    Number(f64),
+    String(Vec<u8>),
+}
+
+impl SortKey {
+    fn rank(&self) -> u8 {
+        match *self {
+            SortKey::Missing => 0,
+            SortKey::Number(_) => 1,
+            SortKey::String(_) => 2,
+        }
+    }
+    fn compare(&self, other: &SortKey) -> Ordering {
+        match (self, other) {
+            (SortKey::Number(a), SortKey::Number(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (SortKey::String(a), SortKey::String(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+/// `List::sort_by_key`/`sort_by_key_into`: sorts the elements of `KEYS[1]` by their field at the path `ARGV[2..]`, in
+/// descending order if `ARGV[1]` is `1`, and returns them or stores them in `KEYS[2]`.
+fn sort_by_key(state: &mut State, keys: &[Vec<u8>], args: &[Vec<u8>]) -> Reply<Value> {
+    let key = arg(keys, 0)?;
+    let descending = args.first().is_some_and(|arg| arg == b"1");
+    let path = args.get(1..).unwrap_or_default();
+    let mut keyed: Vec<(SortKey, Vec<u8>, usize)> =
+        elements(call(state, &[b"LRANGE", key, b"0", b"-1"])?)
+            .into_iter()
+            .enumerate()
+            .map(|(index, element)| {
+                let key = match cbor::locate(&element, path).and_then(|i| cbor::decode(&element, i))
+                {
+                    None => SortKey::Missing,
+                    Some(Scalar::Number(number)) => SortKey::Number(number),
+                    Some(Scalar::Bool(boolean)) => SortKey::Number(f64::from(u8::from(boolean))),
+                    Some(Scalar::String(string)) => SortKey::String(string),
+                };
+                (key, element, index)
+            })
+            .collect();
+    keyed.sort_by(|(a, _, a_index), (b, _, b_index)| {
+        let ordering = a.compare(b);
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+        .then(a_index.cmp(b_index))
+    });
+    let sorted: Vec<Vec<u8>> = keyed.into_iter().map(|(_, element, _)| element).collect();
+    match keys.get(1) {
+        Some(destination) => {
+            call(state, &[b"DEL", destination])?;
+            for chunk in sorted.chunks(1000) {
+                let mut command: Vec<&[u8]> = vec![b"RPUSH", destination];
+                command.extend(chunk.iter().map(Vec::as_slice));
+                call(state, &command)?;
+            }
+            Ok(Value::Int(sorted.len() as i64))
+        }
+        None => Ok(super::data(sorted)),
+    }
+}
+
+/// `List` reads that write back upgraded elements: for each triple of index, original and upgraded data in `ARGV`,
+/// replaces the element at the index of `KEYS[1]` with the upgraded data if it still holds the original.
+fn write_back(state: &mut State, keys: &[Vec<u8>], args: &[Vec<u8>]) -> Reply<Value> {
+    let key = arg(keys, 0)?;
+    let mut rewritten = 0;
+    for upgrade in args.chunks(3) {
+        let (index, original, upgraded) = (arg(upgrade, 0)?, arg(upgrade, 1)?, arg(upgrade, 2)?);
+        if call(state, &[b"LINDEX", key, index])? == Value::Data(original.to_vec()) {
+            call(state, &[b"LSET", key, index, upgraded])?;
+            rewritten += 1;
+        }
+    }
+    Ok(Value::Int(rewritten))
+}
@@ -0,0 +1,155 @@
+//! Emulation of the SORT command.
+
+use redis::Value;
+
+use super::{error, float, int, syntax_error, wrong_type, Entry, Reply, State};
+
+use std::cmp::Ordering;
+
+/// Returns the value of the external key given by `pattern` for `element`, in which the first `*` is substituted with
+/// the element and `->` designates a field of a hash. The pattern `#` designates the element itself.
+fn lookup(state: &State, pattern: &[u8], element: &[u8]) -> Option<Vec<u8>> {
+    if pattern == b"#" {
+        return Some(element.to_vec());
+    }
+    let star = pattern.iter().position(|byte| *byte == b'*')?;
+    let (prefix, rest) = (&pattern[..star], &pattern[star + 1..]);
+    let (postfix, field) = match rest.windows(2).position(|window| window == b"->") {
+        Some(arrow) if arrow + 2 < rest.len() => (&rest[..arrow], Some(&rest[arrow + 2..])),
+        _ => (rest, None),
+    };
+    match (state.get(&[prefix, element, postfix].concat())?, field) {
+        (Entry::String(value), None) => Some(value.clone()),
+        (Entry::Hash(hash), Some(field)) => hash.get(field).cloned(),
+        _ => None,
+    }
+}
+
+/// Executes SORT, where `args` excludes the name of the command.
+pub(super) fn sort(state: &mut State, args: &[Vec<u8>]) -> Reply<Value> {
+    if args.is_empty() {
+        return Err(error("ERR wrong number of arguments for 'sort' command"));
+    }
+    let mut by = None;
+    let mut limit = None;
+    let mut get = vec![];
+    let mut descending = false;
+    let mut alpha = false;
+    let mut store = None;
+    let mut i = 1;
+    while i < args.len() {
+        let remaining = args.len() - i - 1;
+        match args[i].to_ascii_uppercase().as_slice() {
+            b"ASC" => descending = false,
+            b"DESC" => descending = true,
+            b"ALPHA" => alpha = true,
+            b"LIMIT" if remaining >= 2 => {
+                limit = Some((int(&args[i + 1])?, int(&args[i + 2])?));
+                i += 2;
+            }
+            b"BY" if remaining >= 1 => {
+                by = Some(args[i + 1].as_slice());
+                i += 1;
+            }
+            b"GET" if remaining >= 1 => {
+                get.push(args[i + 1].as_slice());
+                i += 1;
+            }
+            b"STORE" if remaining >= 1 => {
+                store = Some(args[i + 1].as_slice());
+                i += 1;
+            }
+            _ => return Err(syntax_error()),
+        }
+        i += 1;
+    }
+    let mut elements: Vec<Vec<u8>> = match state.get(&args[0]) {
+        None => vec![],
+        Some(Entry::List(list)) => list.iter().cloned().collect(),
+        // The members of a set are ordered lexicographically where they are not sorted, for determinism.
+        Some(Entry::Set(set)) => {
+            let mut members: Vec<Vec<u8>> = set.iter().cloned().collect();
+            members.sort();
+            members
+        }
+        Some(Entry::SortedSet(set)) => set.iter().map(|(_, member)| member.clone()).collect(),
+        Some(_) => return Err(wrong_type()),
+    };
+    // A pattern without `*`, such as `nosort`, skips sorting.
+    if by.is_none_or(|by| by.contains(&b'*')) {
+        let weights: Vec<Option<Vec<u8>>> = elements
+            .iter()
+            .map(|element| match by {
+                Some(by) => lookup(state, by, element),
+                None => Some(element.clone()),
+            })
+            .collect();
+        let mut keyed = weights
+            .into_iter()
+            .zip(elements)
+            .map(|(weight, element)| {
+                let score = match (alpha, &weight) {
+                    (true, _) => None,
+                    (false, None) => Some(0.0),
+                    (false, Some(weight)) => Some(float(weight).map_err(|_| {
+                        error("ERR One or more scores can't be converted into double")
+                    })?),
+                };
+                Ok((weight, score, element))
+            })
+            .collect::<Reply<Vec<_>>>()?;
+        keyed.sort_by(|a, b| {
+            let ordering = match (a.1, b.1) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                _ => a.0.cmp(&b.0),
+            }
+            .then_with(|| a.2.cmp(&b.2));
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        elements = keyed.into_iter().map(|(_, _, element)| element).collect();
+    }
+    let (offset, count) = limit.unwrap_or((0, -1));
+    let start = (offset.max(0) as usize).min(elements.len());
+    let end = if count < 0 {
+        elements.len()
+    } else {
+        start.saturating_add(count as usize).min(elements.len())
+    };
+    let values: Vec<Option<Vec<u8>>> = elements[start..end]
+        .iter()
+        .flat_map(|element| {
+            if get.is_empty() {
+                vec![Some(element.clone())]
+            } else {
+                get.iter()
+                    .map(|pattern| lookup(state, pattern, element))
+                    .collect()
+            }
+        })
+        .collect();
+    match store {
+        Some(destination) => {
+            let len = values.len();
+            if values.is_empty() {
+                if state.keys.remove(destination).is_some() {
+                    state.notify(destination, "del");
+                }
+            } else {
+                let list = values.into_iter().map(Option::unwrap_or_default).collect();
+                state.keys.insert(destination.to_vec(), Entry::List(list));
+                state.notify(destination, "sortstore");
+            }
+            Ok(Value::Int(len as i64))
+        }
+        None => Ok(Value::Bulk(
+            values
+                .into_iter()
+                .map(|value| value.map_or(Value::Nil, Value::Data))
+                .collect(),
+        )),
+    }
+}
@@ -0,0 +1,662 @@
+use super::*;
+use crate::collections::{
+    capped::PUSH_EVICTING_SCRIPT,
+    list::{FIND_BY_FIELD_SCRIPT, WRITE_BACK_SCRIPT},
+    lua::with_cbor,
+    queue::REQUEUE_SCRIPT,
+    sort::SORT_BY_KEY_SCRIPT,
+};
+
+use std::thread;
+
+fn session() -> Session {
+    Session::new(Arc::default())
+}
+
+/// Executes a command given as whitespace-separated arguments.
+fn run(session: &mut Session, command: &str) -> Reply<Value> {
+    session
+        .execute(
+            command
+                .split_whitespace()
+                .map(|arg| arg.as_bytes().to_vec())
+                .collect(),
+        )
+        .and_then(Response::into_reply)
+}
+
+fn bulk(items: &[&str]) -> Value {
+    data(items.iter().map(|item| item.as_bytes().to_vec()))
+}
+
+fn text(item: &str) -> Value {
+    Value::Data(item.as_bytes().to_vec())
+}
+
+#[test]
+fn pushes_and_pops() {
+    let mut session = session();
+    assert_eq!(run(&mut session, "RPUSH list b c"), Ok(Value::Int(2)));
+    assert_eq!(run(&mut session, "LPUSH list a"), Ok(Value::Int(3)));
+    assert_eq!(
+        run(&mut session, "LRANGE list 0 -1"),
+        Ok(bulk(&["a", "b", "c"]))
+    );
+    assert_eq!(run(&mut session, "LPOP list"), Ok(text("a")));
+    assert_eq!(run(&mut session, "RPOP list"), Ok(text("c")));
+    assert_eq!(run(&mut session, "RPOP list"), Ok(text("b")));
+    // Emptied lists are removed.
+    assert_eq!(run(&mut session, "RPOP list"), Ok(Value::Nil));
+    assert_eq!(run(&mut session, "EXISTS list"), Ok(Value::Int(0)));
+    assert_eq!(
+        run(&mut session, "TYPE list"),
+        Ok(Value::Status("none".to_owned()))
+    );
+}
+
+#[test]
+fn indexes_and_ranges() {
+    let mut session = session();
+    run(&mut session, "RPUSH list a b c d").unwrap();
+    assert_eq!(run(&mut session, "LINDEX list -1"), Ok(text("d")));
+    assert_eq!(run(&mut session, "LINDEX list 4"), Ok(Value::Nil));
+    assert_eq!(
+        run(&mut session, "LRANGE list -2 10"),
+        Ok(bulk(&["c", "d"]))
+    );
+    assert_eq!(run(&mut session, "LRANGE list 3 1"), Ok(bulk(&[])));
+    assert_eq!(run(&mut session, "LSET list -2 x"), Ok(Value::Okay));
+    assert_eq!(
+        run(&mut session, "LSET list 4 x"),
+        Err("ERR index out of range".to_owned())
+    );
+    assert_eq!(
+        run(&mut session, "LSET missing 0 x"),
+        Err("ERR no such key".to_owned())
+    );
+    assert_eq!(run(&mut session, "LTRIM list 1 -2"), Ok(Value::Okay));
+    assert_eq!(run(&mut session, "LRANGE list 0 -1"), Ok(bulk(&["b", "x"])));
+    assert_eq!(run(&mut session, "LLEN list"), Ok(Value::Int(2)));
+    assert_eq!(run(&mut session, "LTRIM list 5 10"), Ok(Value::Okay));
+    assert_eq!(run(&mut session, "EXISTS list"), Ok(Value::Int(0)));
+}
+
+#[test]
+fn removes_inserts_and_positions() {
+    let mut session = session();
+    run(&mut session, "RPUSH list a b a c a").unwrap();
+    assert_eq!(run(&mut session, "LPOS list a"), Ok(Value::Int(0)));
+    assert_eq!(
+        run(&mut session, "LPOS list a RANK -1 COUNT 2"),
+        Ok(Value::Bulk(vec![Value::Int(4), Value::Int(2)]))
+    );
+    assert_eq!(run(&mut session, "LPOS list z"), Ok(Value::Nil));
+    assert!(run(&mut session, "LPOS list a RANK 0").is_err());
+    assert_eq!(run(&mut session, "LREM list -2 a"), Ok(Value::Int(2)));
+    assert_eq!(
+        run(&mut session, "LRANGE list 0 -1"),
+        Ok(bulk(&["a", "b", "c"]))
+    );
+    assert_eq!(
+        run(&mut session, "LINSERT list AFTER b x"),
+        Ok(Value::Int(4))
+    );
+    assert_eq!(
+        run(&mut session, "LINSERT list BEFORE z x"),
+        Ok(Value::Int(-1))
+    );
+    assert_eq!(
+        run(&mut session, "LINSERT missing BEFORE a x"),
+        Ok(Value::Int(0))
+    );
+    assert_eq!(
+        run(&mut session, "LRANGE list 0 -1"),
+        Ok(bulk(&["a", "b", "x", "c"]))
+    );
+    assert_eq!(run(&mut session, "LREM list 0 x"), Ok(Value::Int(1)));
+}
+
+#[test]
+fn moves_between_lists() {
+    let mut session = session();
+    run(&mut session, "RPUSH source a b").unwrap();
+    assert_eq!(
+        run(&mut session, "LMOVE source destination LEFT RIGHT"),
+        Ok(text("a"))
+    );
+    assert_eq!(
+        run(&mut session, "RPOPLPUSH source destination"),
+        Ok(text("b"))
+    );
+    assert_eq!(
+        run(&mut session, "LRANGE destination 0 -1"),
+        Ok(bulk(&["b", "a"]))
+    );
+    assert_eq!(run(&mut session, "EXISTS source"), Ok(Value::Int(0)));
+    assert_eq!(
+        run(&mut session, "LMOVE source destination LEFT RIGHT"),
+        Ok(Value::Nil)
+    );
+    assert!(run(&mut session, "LMOVE destination source UP RIGHT").is_err());
+}
+
+#[test]
+fn rejects_wrong_types_and_arities() {
+    let mut session = session();
+    run(&mut session, "SET string value").unwrap();
+    assert_eq!(run(&mut session, "LPUSH string a"), Err(wrong_type()));
+    assert_eq!(run(&mut session, "ZADD string 1 a"), Err(wrong_type()));
+    assert_eq!(
+        run(&mut session, "LLEN"),
+        Err("ERR wrong number of arguments for 'llen' command".to_owned())
+    );
+    assert!(run(&mut session, "NOSUCHCOMMAND")
+        .unwrap_err()
+        .starts_with("ERR unknown command 'nosuchcommand'"));
+}
+
+#[test]
+fn sorted_sets() {
+    let mut session = session();
+    assert_eq!(run(&mut session, "ZADD set 2 b 1 a 3 c"), Ok(Value::Int(3)));
+    // Updating the score of a member reorders it without adding a member.
+    assert_eq!(run(&mut session, "ZADD set 0 c"), Ok(Value::Int(0)));
+    assert_eq!(
+        run(&mut session, "ZRANGE set 0 -1"),
+        Ok(bulk(&["c", "a", "b"]))
+    );
+    assert_eq!(run(&mut session, "ZSCORE set a"), Ok(text("1")));
+    assert_eq!(run(&mut session, "ZSCORE set z"), Ok(Value::Nil));
+    assert_eq!(run(&mut session, "ZADD set 1 d"), Ok(Value::Int(1)));
+    // Members with equal scores are ordered lexicographically.
+    assert_eq!(run(&mut session, "ZRANGE set 1 2"), Ok(bulk(&["a", "d"])));
+    assert_eq!(run(&mut session, "ZREM set a z"), Ok(Value::Int(1)));
+    assert_eq!(run(&mut session, "ZCARD set"), Ok(Value::Int(3)));
+    assert!(run(&mut session, "ZADD set x a").is_err());
+    run(&mut session, "ZREM set b c d").unwrap();
+    assert_eq!(run(&mut session, "EXISTS set"), Ok(Value::Int(0)));
+}
+
+#[test]
+fn blocking_moves_time_out() {
+    let mut session = session();
+    let started = Instant::now();
+    assert_eq!(
+        run(&mut session, "BLMOVE source destination RIGHT LEFT 0.1"),
+        Ok(Value::Nil)
+    );
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    assert_eq!(
+        run(&mut session, "BRPOPLPUSH source destination -1"),
+        Err("ERR timeout is negative".to_owned())
+    );
+}
+
+#[test]
+fn blocking_moves_wake_on_push() {
+    let memory = Arc::new(Memory::default());
+    let mut blocked = Session::new(memory.clone());
+    let waiter = thread::spawn(move || run(&mut blocked, "BRPOPLPUSH source destination 0"));
+    thread::sleep(Duration::from_millis(50));
+    let mut session = Session::new(memory);
+    run(&mut session, "RPUSH source a").unwrap();
+    assert_eq!(waiter.join().unwrap(), Ok(text("a")));
+    assert_eq!(
+        run(&mut session, "LRANGE destination 0 -1"),
+        Ok(bulk(&["a"]))
+    );
+}
+
+#[test]
+fn transactions_perform_every_command() {
+    let mut session = session();
+    run(&mut session, "SET string value").unwrap();
+    assert_eq!(run(&mut session, "MULTI"), Ok(Value::Okay));
+    assert_eq!(
+        run(&mut session, "RPUSH list a"),
+        Ok(Value::Status("QUEUED".to_owned()))
+    );
+    run(&mut session, "LPUSH string a").unwrap();
+    run(&mut session, "RPUSH list b").unwrap();
+    let replies = match session.execute(vec![b"EXEC".to_vec()]) {
+        Ok(Response::Transaction(replies)) => replies,
+        _ => panic!("EXEC did not reply with the replies of the transaction"),
+    };
+    assert_eq!(
+        replies,
+        vec![Ok(Value::Int(1)), Err(wrong_type()), Ok(Value::Int(2))]
+    );
+    assert_eq!(run(&mut session, "LRANGE list 0 -1"), Ok(bulk(&["a", "b"])));
+}
+
+#[test]
+fn transactions_are_discarded_and_not_nested() {
+    let mut session = session();
+    assert!(run(&mut session, "EXEC").is_err());
+    assert!(run(&mut session, "DISCARD").is_err());
+    run(&mut session, "MULTI").unwrap();
+    assert!(run(&mut session, "MULTI").is_err());
+    run(&mut session, "RPUSH list a").unwrap();
+    assert_eq!(run(&mut session, "DISCARD"), Ok(Value::Okay));
+    assert_eq!(run(&mut session, "EXISTS list"), Ok(Value::Int(0)));
+    // An empty transaction replies with an empty array.
+    run(&mut session, "MULTI").unwrap();
+    assert_eq!(run(&mut session, "EXEC"), Ok(Value::Bulk(vec![])));
+}
+
+#[test]
+fn connections_report_the_first_error_of_a_transaction() {
+    let memory = Arc::new(Memory::default());
+    let mut connection = MemoryConnection::new(memory.clone());
+    let result: RedisResult<(i64, i64)> = redis::pipe()
+        .atomic()
+        .cmd("RPUSH")
+        .arg("list")
+        .arg("a")
+        .cmd("LSET")
+        .arg("list")
+        .arg(5)
+        .arg("b")
+        .query(&mut connection);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("index out of range"));
+    // The commands of the transaction that succeeded were nonetheless performed.
+    assert_eq!(
+        run(&mut Session::new(memory), "LRANGE list 0 -1"),
+        Ok(bulk(&["a"]))
+    );
+}
+
+#[test]
+fn transactions_with_rejected_commands_are_aborted() {
+    let mut session = session();
+    run(&mut session, "MULTI").unwrap();
+    run(&mut session, "RPUSH list a").unwrap();
+    assert_eq!(
+        run(&mut session, "LLEN"),
+        Err("ERR wrong number of arguments for 'llen' command".to_owned())
+    );
+    assert!(run(&mut session, "NOSUCHCOMMAND list")
+        .unwrap_err()
+        .starts_with("ERR unknown command 'nosuchcommand'"));
+    run(&mut session, "RPUSH list b").unwrap();
+    assert!(run(&mut session, "EXEC")
+        .unwrap_err()
+        .starts_with("EXECABORT"));
+    assert_eq!(run(&mut session, "EXISTS list"), Ok(Value::Int(0)));
+    // The transaction is closed, and a following one is not affected.
+    assert!(run(&mut session, "EXEC").is_err());
+    run(&mut session, "MULTI").unwrap();
+    run(&mut session, "RPUSH list a").unwrap();
+    assert_eq!(
+        run(&mut session, "EXEC"),
+        Ok(Value::Bulk(vec![Value::Int(1)]))
+    );
+}
+
+#[test]
+fn pipelines_perform_every_command() {
+    let memory = Arc::new(Memory::default());
+    let mut connection = MemoryConnection::new(memory.clone());
+    let result: RedisResult<(i64, i64, i64)> = redis::pipe()
+        .cmd("RPUSH")
+        .arg("list")
+        .arg("a")
+        .cmd("LSET")
+        .arg("list")
+        .arg(5)
+        .arg("b")
+        .cmd("RPUSH")
+        .arg("list")
+        .arg("c")
+        .query(&mut connection);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("index out of range"));
+    // The commands following the one that failed were nonetheless performed.
+    assert_eq!(
+        run(&mut Session::new(memory.clone()), "LRANGE list 0 -1"),
+        Ok(bulk(&["a", "c"]))
+    );
+    // A transaction within a pipeline is discarded if one of its commands is rejected, while the commands around it
+    // are performed.
+    let result: RedisResult<(i64, i64)> = redis::pipe()
+        .cmd("RPUSH")
+        .arg("other")
+        .arg("a")
+        .cmd("MULTI")
+        .cmd("RPUSH")
+        .arg("list")
+        .arg("d")
+        .cmd("LLEN")
+        .cmd("EXEC")
+        .cmd("RPUSH")
+        .arg("other")
+        .arg("b")
+        .query(&mut connection);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("wrong number of arguments"));
+    let mut session = Session::new(memory);
+    assert_eq!(run(&mut session, "LRANGE list 0 -1"), Ok(bulk(&["a", "c"])));
+    assert_eq!(
+        run(&mut session, "LRANGE other 0 -1"),
+        Ok(bulk(&["a", "b"]))
+    );
+}
+
+/// Executes a command given as separate arguments.
+fn call(session: &mut Session, command: &[&[u8]]) -> Reply<Value> {
+    session
+        .execute(command.iter().map(|arg| arg.to_vec()).collect())
+        .and_then(Response::into_reply)
+}
+
+fn element(age: Option<serde_cbor::Value>) -> Vec<u8> {
+    let mut fields = std::collections::BTreeMap::new();
+    fields.insert("name".to_owned(), serde_cbor::Value::String("x".to_owned()));
+    if let Some(age) = age {
+        fields.insert("age".to_owned(), age);
+    }
+    serde_cbor::to_vec(&fields).unwrap()
+}
+
+#[test]
+fn scripts_are_loaded_by_digest() {
+    let mut session = session();
+    let sha = redis::Script::new(PUSH_EVICTING_SCRIPT)
+        .get_hash()
+        .to_owned();
+    let evalsha = |session: &mut Session| {
+        call(
+            session,
+            &[
+                b"EVALSHA",
+                sha.as_bytes(),
+                b"1",
+                b"list",
+                b"RPUSH",
+                b"2",
+                b"a",
+                b"LPOP",
+            ],
+        )
+    };
+    assert_eq!(
+        evalsha(&mut session),
+        Err("NOSCRIPT No matching script. Please use EVAL.".to_owned())
+    );
+    assert_eq!(
+        call(
+            &mut session,
+            &[b"SCRIPT", b"LOAD", PUSH_EVICTING_SCRIPT.as_bytes()]
+        ),
+        Ok(Value::Data(sha.clone().into_bytes()))
+    );
+    assert_eq!(evalsha(&mut session), Ok(Value::Bulk(vec![])));
+    assert_eq!(
+        call(&mut session, &[b"SCRIPT", b"EXISTS", sha.as_bytes(), b"0"]),
+        Ok(Value::Bulk(vec![Value::Int(1), Value::Int(0)]))
+    );
+    run(&mut session, "SCRIPT FLUSH").unwrap();
+    assert!(evalsha(&mut session).unwrap_err().starts_with("NOSCRIPT"));
+    // Scripts other than those of the collections are rejected.
+    assert!(call(&mut session, &[b"EVAL", b"return 1", b"0"]).is_err());
+    assert!(call(&mut session, &[b"SCRIPT", b"LOAD", b"return 1"]).is_err());
+    // Errors of the commands performed by a script are reported as errors of the script.
+    run(&mut session, "SET string value").unwrap();
+    assert!(call(
+        &mut session,
+        &[
+            b"EVAL",
+            PUSH_EVICTING_SCRIPT.as_bytes(),
+            b"1",
+            b"string",
+            b"RPUSH",
+            b"2",
+            b"a",
+            b"LPOP"
+        ],
+    )
+    .unwrap_err()
+    .contains("WRONGTYPE"));
+}
+
+#[test]
+fn evicting_pushes() {
+    let mut session = session();
+    let push = |session: &mut Session, element: &[u8]| {
+        call(
+            session,
+            &[
+                b"EVAL",
+                PUSH_EVICTING_SCRIPT.as_bytes(),
+                b"1",
+                b"list",
+                b"RPUSH",
+                b"2",
+                element,
+                b"LPOP",
+            ],
+        )
+    };
+    assert_eq!(push(&mut session, b"a"), Ok(Value::Bulk(vec![])));
+    push(&mut session, b"b").unwrap();
+    assert_eq!(push(&mut session, b"c"), Ok(bulk(&["a"])));
+    assert_eq!(run(&mut session, "LRANGE list 0 -1"), Ok(bulk(&["b", "c"])));
+}
+
+#[test]
+fn expired_leases_are_requeued() {
+    let mut session = session();
+    let requeue = |session: &mut Session, now: &[u8]| {
+        call(
+            session,
+            &[
+                b"EVAL",
+                REQUEUE_SCRIPT.as_bytes(),
+                b"3",
+                b"queue",
+                b"processing",
+                b"leases",
+                now,
+                b"100",
+            ],
+        )
+    };
+    run(&mut session, "RPUSH processing a b").unwrap();
+    // Elements without a lease are granted one.
+    assert_eq!(requeue(&mut session, b"1000"), Ok(Value::Int(0)));
+    assert_eq!(run(&mut session, "ZSCORE leases a"), Ok(text("1100")));
+    run(&mut session, "ZADD leases 900 b").unwrap();
+    assert_eq!(requeue(&mut session, b"1050"), Ok(Value::Int(1)));
+    assert_eq!(run(&mut session, "LRANGE queue 0 -1"), Ok(bulk(&["b"])));
+    assert_eq!(
+        run(&mut session, "LRANGE processing 0 -1"),
+        Ok(bulk(&["a"]))
+    );
+    assert_eq!(run(&mut session, "ZCARD leases"), Ok(Value::Int(1)));
+}
+
+#[test]
+fn elements_are_found_and_sorted_by_field() {
+    let mut session = session();
+    let elements = [
+        element(Some(serde_cbor::Value::U64(30))),
+        element(None),
+        element(Some(serde_cbor::Value::String("unknown".to_owned()))),
+        element(Some(serde_cbor::Value::F64(2.5))),
+        element(Some(serde_cbor::Value::U64(30))),
+    ];
+    for element in &elements {
+        call(&mut session, &[b"RPUSH", b"list", element]).unwrap();
+    }
+    let find = with_cbor(FIND_BY_FIELD_SCRIPT);
+    let expected = serde_cbor::to_vec(&30).unwrap();
+    assert_eq!(
+        call(
+            &mut session,
+            &[
+                b"EVAL",
+                find.as_bytes(),
+                b"1",
+                b"list",
+                &expected,
+                b"1",
+                b"age"
+            ]
+        ),
+        Ok(Value::Bulk(vec![
+            Value::Int(0),
+            Value::Data(elements[0].clone())
+        ]))
+    );
+    assert_eq!(
+        call(
+            &mut session,
+            &[
+                b"EVAL",
+                find.as_bytes(),
+                b"1",
+                b"list",
+                &expected,
+                b"0",
+                b"age"
+            ]
+        )
+        .map(|matches| match matches {
+            Value::Bulk(matches) => matches.len() / 2,
+            _ => 0,
+        }),
+        Ok(2)
+    );
+
+    let sort = with_cbor(SORT_BY_KEY_SCRIPT);
+    let order = |indices: &[usize]| data(indices.iter().map(|i| elements[*i].clone()));
+    assert_eq!(
+        call(
+            &mut session,
+            &[b"EVAL", sort.as_bytes(), b"1", b"list", b"0", b"age"]
+        ),
+        Ok(order(&[1, 3, 0, 4, 2]))
+    );
+    assert_eq!(
+        call(
+            &mut session,
+            &[
+                b"EVAL",
+                sort.as_bytes(),
+                b"2",
+                b"list",
+                b"sorted",
+                b"1",
+                b"age"
+            ]
+        ),
+        Ok(Value::Int(5))
+    );
+    assert_eq!(
+        run(&mut session, "LRANGE sorted 0 -1"),
+        Ok(order(&[2, 0, 4, 3, 1]))
+    );
+}
+
+#[test]
+fn upgrades_are_written_back_unless_modified() {
+    let mut session = session();
+    run(&mut session, "RPUSH list a b").unwrap();
+    assert_eq!(
+        call(
+            &mut session,
+            &[
+                b"EVAL",
+                WRITE_BACK_SCRIPT.as_bytes(),
+                b"1",
+                b"list",
+                b"0",
+                b"a",
+                b"A",
+                b"1",
+                b"x",
+                b"X"
+            ],
+        ),
+        Ok(Value::Int(1))
+    );
+    assert_eq!(run(&mut session, "LRANGE list 0 -1"), Ok(bulk(&["A", "b"])));
+}
+
+#[test]
+fn sorts_lists_and_sets() {
+    let mut session = session();
+    run(&mut session, "RPUSH list 3 1 2").unwrap();
+    assert_eq!(run(&mut session, "SORT list"), Ok(bulk(&["1", "2", "3"])));
+    assert_eq!(
+        run(&mut session, "SORT list DESC LIMIT 1 5"),
+        Ok(bulk(&["2", "1"]))
+    );
+    run(&mut session, "RPUSH words b a").unwrap();
+    assert!(run(&mut session, "SORT words")
+        .unwrap_err()
+        .contains("can't be converted into double"));
+    assert_eq!(run(&mut session, "SORT words ALPHA"), Ok(bulk(&["a", "b"])));
+    run(&mut session, "SADD set b a").unwrap();
+    assert_eq!(run(&mut session, "SORT set ALPHA"), Ok(bulk(&["a", "b"])));
+    assert_eq!(run(&mut session, "SORT missing"), Ok(bulk(&[])));
+    run(&mut session, "SET string value").unwrap();
+    assert_eq!(run(&mut session, "SORT string"), Err(wrong_type()));
+    assert_eq!(run(&mut session, "SORT list LIMIT 0"), Err(syntax_error()));
+}
+
+#[test]
+fn sorts_by_external_keys() {
+    let mut session = session();
+    run(&mut session, "RPUSH list a b c").unwrap();
+    run(&mut session, "SET weight_a 3").unwrap();
+    run(&mut session, "SET weight_b 1").unwrap();
+    run(&mut session, "HSET object_a name Ada").unwrap();
+    // Missing weights are zero.
+    assert_eq!(
+        run(&mut session, "SORT list BY weight_*"),
+        Ok(bulk(&["c", "b", "a"]))
+    );
+    assert_eq!(
+        run(&mut session, "SORT list BY nosort LIMIT 1 1"),
+        Ok(bulk(&["b"]))
+    );
+    assert_eq!(
+        run(
+            &mut session,
+            "SORT list BY weight_* DESC GET # GET object_*->name"
+        ),
+        Ok(Value::Bulk(vec![
+            text("a"),
+            text("Ada"),
+            text("b"),
+            Value::Nil,
+            text("c"),
+            Value::Nil
+        ]))
+    );
+    assert_eq!(
+        run(
+            &mut session,
+            "SORT list BY weight_* GET weight_* STORE sorted"
+        ),
+        Ok(Value::Int(3))
+    );
+    assert_eq!(
+        run(&mut session, "LRANGE sorted 0 -1"),
+        Ok(bulk(&["", "1", "3"]))
+    );
+    // Storing an empty result removes the destination.
+    assert_eq!(
+        run(&mut session, "SORT missing STORE sorted"),
+        Ok(Value::Int(0))
+    );
+    assert_eq!(run(&mut session, "EXISTS sorted"), Ok(Value::Int(0)));
+}
@@ -0,0 +1,137 @@
+use crate::KeyType;
+
+fn has_hash_tag(name: &str) -> bool {
+    name.find('{')
+        .and_then(|open| name[open + 1..].find('}'))
+        .is_some_and(|length| length > 0)
+}
+
+/// The scheme by which the logical names of collections are mapped to redis keys.
+///
+/// A key consists of the application prefix (if any), the prefix of the collection type (if type prefixes are
+/// enabled, e.g. `_orm_list` for a List, or as overridden with `type_prefix`) and the logical name, joined by the
+/// separator. The default namespace has no application prefix, uses `:` as its separator and includes type prefixes,
+/// such that the List `people` is stored at `_orm_list:people`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Namespace {
+    prefix: Option<String>,
+    separator: String,
+    type_prefixes: bool,
+    type_prefix_overrides: Vec<(KeyType, Option<String>)>,
+    hash_tags: bool,
+}
+
+impl Default for Namespace {
+    fn default() -> Self {
+        Namespace {
+            prefix: None,
+            separator: ":".to_owned(),
+            type_prefixes: true,
+            type_prefix_overrides: vec![],
+            hash_tags: false,
+        }
+    }
+}
+
+impl Namespace {
+    /// Creates the default namespace.
+    pub fn new() -> Self {
+        Namespace::default()
+    }
+    /// Sets the application prefix prepended to every key, allowing several applications to share a database.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_owned());
+        self
+    }
+    /// Sets the separator placed between the components of a key.
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_owned();
+        self
+    }
+    /// Sets whether the prefix of the collection type is included in keys. Without type prefixes, collections of
+    /// different types must not share a name and keys are identical to logical names unless an application prefix is set.
+    pub fn type_prefixes(mut self, type_prefixes: bool) -> Self {
+        self.type_prefixes = type_prefixes;
+        self
+    }
+    /// Overrides the prefix of collections of the provided type, e.g. `KeyType::List`, where `None` omits the prefix
+    /// for that type. This takes precedence over `type_prefixes`, so that for example `type_prefixes(false)` combined
+    /// with `type_prefix(KeyType::List, Some("list"))` prefixes only lists.
+    pub fn type_prefix(mut self, key_type: KeyType, prefix: Option<&str>) -> Self {
+        self.type_prefix_overrides
+            .retain(|(overridden, _)| *overridden != key_type);
+        self.type_prefix_overrides
+            .push((key_type, prefix.map(str::to_owned)));
+        self
+    }
+    /// Sets whether logical names are wrapped in a hash tag, i.e. `{people}`, unless they already contain one. As only the hash
+    /// tag of a key determines its cluster slot this places a collection and any keys derived from it (such as the leases of a
+    /// `ReliableQueue`) in the same slot. Collections that must be used together, such as the lists of a `ReliableQueue`,
+    /// should be given names with the same hash tag, e.g. `{jobs}` and `{jobs}:processing`. Note that the patterns passed
+    /// to `Database::keys` are matched against names including any hash tag added. Hash tags are always added to the names
+    /// of a database connected to a cluster.
+    pub fn hash_tags(mut self, hash_tags: bool) -> Self {
+        self.hash_tags = hash_tags;
+        self
+    }
+    /// Returns the prefix included in the keys of collections of type `key_type`, whose default prefix is `default`,
+    /// or `None` if their keys have no type prefix.
+    pub(crate) fn collection_prefix<'a>(
+        &'a self,
+        key_type: &KeyType,
+        default: &'a str,
+    ) -> Option<&'a str> {
+        match self
+            .type_prefix_overrides
+            .iter()
+            .find(|(overridden, _)| overridden == key_type)
+        {
+            Some((_, prefix)) => prefix.as_deref(),
+            None if self.type_prefixes => Some(default),
+            None => None,
+        }
+    }
+    /// Returns the redis key of the collection with the provided type prefix (see `collection_prefix`) and logical name.
+    pub(crate) fn key(&self, type_prefix: Option<&str>, name: &str) -> String {
+        let mut key = String::new();
+        if let Some(ref prefix) = self.prefix {
+            key.push_str(prefix);
+            key.push_str(&self.separator);
+        }
+        if let Some(type_prefix) = type_prefix {
+            key.push_str(type_prefix);
+            key.push_str(&self.separator);
+        }
+        if self.hash_tags && !name.is_empty() && !has_hash_tag(name) {
+            key.push('{');
+            key.push_str(name);
+            key.push('}');
+        } else {
+            key.push_str(name);
+        }
+        key
+    }
+    /// Returns the logical name of the collection with the provided type prefix stored at `key`, or `None` if the key is
+    /// not part of this namespace.
+    pub(crate) fn name(&self, type_prefix: Option<&str>, key: &str) -> Option<String> {
+        let prefix = self.key(type_prefix, "");
+        if !key.starts_with(&prefix) {
+            return None;
+        }
+        let name = &key[prefix.len()..];
+        if self.hash_tags && name.starts_with('{') && name.ends_with('}') {
+            let inner = &name[1..name.len() - 1];
+            if !inner.is_empty() && !has_hash_tag(inner) {
+                return Some(inner.to_owned());
+            }
+        }
+        Some(name.to_owned())
+    }
+    /// Returns the prefix shared by every key in this namespace.
+    pub(crate) fn application_prefix(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{}{}", prefix, self.separator),
+            None => String::new(),
+        }
+    }
+}
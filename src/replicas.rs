@@ -0,0 +1,153 @@
+use redis::{ConnectionLike, RedisResult, Value};
+
+use crate::{retry::disconnected, timeout::Timeouts};
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// The server from which a collection handle performs reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadFrom {
+    /// Reads are performed on the primary and so observe every write that has completed.
+    Primary,
+    /// Reads are performed on a replica, if the database has any, and so may not observe recent writes.
+    Replica,
+}
+
+/// The manner in which a replica is chosen for each read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplicaSelection {
+    /// Replicas are used in turn.
+    RoundRobin,
+    /// The replica with the fewest reads in progress through the database is used.
+    LeastLoaded,
+}
+
+/// A primary and its read replicas.
+pub(crate) struct Replicas {
+    pub(crate) primary: redis::Client,
+    replicas: Vec<redis::Client>,
+    selection: ReplicaSelection,
+    next: AtomicUsize,
+    loads: Vec<AtomicUsize>,
+}
+
+impl Replicas {
+    pub(crate) fn new(
+        primary: redis::Client,
+        replicas: Vec<redis::Client>,
+        selection: ReplicaSelection,
+    ) -> Self {
+        Replicas {
+            primary,
+            loads: replicas.iter().map(|_| AtomicUsize::new(0)).collect(),
+            replicas,
+            selection,
+            next: AtomicUsize::new(0),
+        }
+    }
+    fn select(&self) -> Option<usize> {
+        if self.replicas.is_empty() {
+            return None;
+        }
+        Some(match self.selection {
+            ReplicaSelection::RoundRobin => {
+                self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len()
+            }
+            ReplicaSelection::LeastLoaded => self
+                .loads
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, load)| load.load(Ordering::Relaxed))
+                .map(|(index, _)| index)
+                .unwrap(),
+        })
+    }
+}
+
+/// A connection to a primary through which writes are performed, along with lazily established connections to
+/// its replicas for reads.
+pub(crate) struct ReplicatedConnection {
+    replicas: Arc<Replicas>,
+    pub(crate) primary: redis::Connection,
+    connections: Vec<Option<redis::Connection>>,
+    timeouts: Timeouts,
+}
+
+impl ReplicatedConnection {
+    pub(crate) fn connect(replicas: Arc<Replicas>, timeouts: Timeouts) -> RedisResult<Self> {
+        Ok(ReplicatedConnection {
+            primary: timeouts.connect(&replicas.primary)?,
+            connections: replicas.replicas.iter().map(|_| None).collect(),
+            replicas,
+            timeouts,
+        })
+    }
+    /// Sets the timeouts of the connections to the primary and replicas.
+    pub(crate) fn set_timeouts(&mut self, timeouts: Timeouts) -> RedisResult<()> {
+        self.timeouts = timeouts;
+        timeouts.apply(&self.primary)?;
+        for connection in self.connections.iter().flatten() {
+            timeouts.apply(connection)?;
+        }
+        Ok(())
+    }
+    /// Performs a read-only request on a replica, returning `None` if the database has no replicas or the replica
+    /// cannot be reached, in which case the request should be performed on the primary. A request that times out on
+    /// the replica fails rather than falling back, since the replica may yet perform it.
+    pub(crate) fn read<R, F>(&mut self, request: F) -> RedisResult<Option<R>>
+    where
+        F: FnOnce(&mut dyn ConnectionLike) -> RedisResult<R>,
+    {
+        let index = match self.replicas.select() {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        if self.connections[index].is_none() {
+            match self.timeouts.connect(&self.replicas.replicas[index]) {
+                Ok(connection) => self.connections[index] = Some(connection),
+                Err(err) if err.is_timeout() => return Err(err),
+                Err(_) => return Ok(None),
+            }
+        }
+        let connection = self.connections[index].as_mut().unwrap();
+        let load = &self.replicas.loads[index];
+        load.fetch_add(1, Ordering::Relaxed);
+        let result = request(connection).map_err(disconnected);
+        load.fetch_sub(1, Ordering::Relaxed);
+        match result {
+            Err(err) if err.is_io_error() => {
+                self.connections[index] = None;
+                if err.is_timeout() {
+                    Err(err)
+                } else {
+                    Ok(None)
+                }
+            }
+            result => result.map(Some),
+        }
+    }
+}
+
+impl ConnectionLike for ReplicatedConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.primary.req_packed_command(cmd).map_err(disconnected)
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.primary
+            .req_packed_commands(cmd, offset, count)
+            .map_err(disconnected)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.primary.get_db()
+    }
+}
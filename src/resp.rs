@@ -0,0 +1,116 @@
+//! Minimal parsing of packed RESP commands, i.e. arrays of bulk strings as produced by `redis::Cmd`, and encoding of
+//! replies.
+
+use std::convert::TryFrom;
+
+fn line(bytes: &[u8], position: &mut usize) -> Option<i64> {
+    let start = *position;
+    let end = start
+        + bytes[start..]
+            .windows(2)
+            .position(|window| window == b"\r\n")?;
+    *position = end + 2;
+    std::str::from_utf8(&bytes[start + 1..end])
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Returns the bulk string at `position` and advances past it, or `None` if it is incomplete or malformed.
+fn bulk<'a>(bytes: &'a [u8], position: &mut usize) -> Option<&'a [u8]> {
+    if bytes.get(*position) != Some(&b'$') {
+        return None;
+    }
+    let length = usize::try_from(line(bytes, position)?).ok()?;
+    let end = position.checked_add(length)?;
+    let arg = bytes.get(*position..end)?;
+    if bytes.get(end..end.checked_add(2)?)? != b"\r\n" {
+        return None;
+    }
+    *position = end + 2;
+    Some(arg)
+}
+
+/// Parses the packed command at the start of `bytes` into its arguments, returning them along with the length of the
+/// command, or `None` if the input is incomplete or malformed.
+pub(crate) fn parse_command(bytes: &[u8]) -> Option<(Vec<Vec<u8>>, usize)> {
+    let mut position = 0;
+    if bytes.first() != Some(&b'*') {
+        return None;
+    }
+    let count = line(bytes, &mut position)?;
+    // The count is not trusted for the capacity, since each argument takes at least four bytes of the input.
+    let mut args = Vec::with_capacity(usize::try_from(count).ok()?.min(bytes.len() / 4));
+    for _ in 0..count {
+        args.push(bulk(bytes, &mut position)?.to_vec());
+    }
+    Some((args, position))
+}
+
+/// Parses a sequence of packed commands into their arguments, returning `None` if the input is malformed.
+pub(crate) fn parse_commands(bytes: &[u8]) -> Option<Vec<Vec<Vec<u8>>>> {
+    let mut commands = vec![];
+    let mut position = 0;
+    while position < bytes.len() {
+        let (args, length) = parse_command(&bytes[position..])?;
+        commands.push(args);
+        position += length;
+    }
+    Some(commands)
+}
+
+/// Appends the encoding of a reply to `out`.
+#[cfg(feature = "test-util")]
+pub(crate) fn encode(value: &redis::Value, out: &mut Vec<u8>) {
+    use redis::Value;
+
+    match *value {
+        Value::Nil => out.extend_from_slice(b"$-1\r\n"),
+        Value::Int(value) => out.extend_from_slice(format!(":{}\r\n", value).as_bytes()),
+        Value::Data(ref data) => {
+            out.extend_from_slice(format!("${}\r\n", data.len()).as_bytes());
+            out.extend_from_slice(data);
+            out.extend_from_slice(b"\r\n");
+        }
+        Value::Bulk(ref values) => {
+            out.extend_from_slice(format!("*{}\r\n", values.len()).as_bytes());
+            for value in values {
+                encode(value, out);
+            }
+        }
+        Value::Status(ref status) => out.extend_from_slice(format!("+{}\r\n", status).as_bytes()),
+        Value::Okay => out.extend_from_slice(b"+OK\r\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_parsed() {
+        assert_eq!(
+            parse_commands(b"*2\r\n$3\r\nGET\r\n$1\r\nk\r\n*1\r\n$4\r\nPING\r\n"),
+            Some(vec![
+                vec![b"GET".to_vec(), b"k".to_vec()],
+                vec![b"PING".to_vec()]
+            ])
+        );
+    }
+
+    #[test]
+    fn malformed_commands_are_rejected() {
+        for bytes in [
+            &b"*1\r\n$-1\r\n"[..],
+            b"*1\r\n$-5\r\nGET\r\n",
+            b"*1\r\n$18446744073709551615\r\nGET\r\n",
+            b"*1\r\n$9223372036854775807\r\nGET\r\n",
+            b"*1\r\n$3\r\nGETxx",
+            b"*9223372036854775807\r\n$3\r\nGET\r\n",
+            b"*-1\r\n",
+            b"*1\r\n$3\r\nGE",
+        ] {
+            assert_eq!(parse_commands(bytes), None, "{:?}", bytes);
+        }
+    }
+}
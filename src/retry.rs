@@ -0,0 +1,313 @@
+use redis::{ErrorKind, RedisError};
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io,
+    time::Duration,
+};
+
+/// Commands that may be performed more than once with the same effect as performing them once, whatever their
+/// arguments. A request is idempotent if every command it consists of is, see `is_idempotent_command` for those that
+/// only are in some forms.
+const IDEMPOTENT_COMMANDS: &[&str] = &[
+    "DEL",
+    "DISCARD",
+    "EXEC",
+    "EXISTS",
+    "GET",
+    "HEXISTS",
+    "HGET",
+    "HGETALL",
+    "HKEYS",
+    "HLEN",
+    "HMGET",
+    "HSCAN",
+    "HSET",
+    "HVALS",
+    "LINDEX",
+    "LLEN",
+    "LPOS",
+    "LRANGE",
+    "LSET",
+    "LTRIM",
+    "MGET",
+    "MULTI",
+    "PERSIST",
+    "PING",
+    "SADD",
+    "SCAN",
+    "SCARD",
+    "SELECT",
+    "SENTINEL",
+    "SISMEMBER",
+    "SMEMBERS",
+    "SORT",
+    "SREM",
+    "SSCAN",
+    "TYPE",
+    "UNLINK",
+    "UNWATCH",
+    "WATCH",
+    "ZADD",
+    "ZCARD",
+    "ZRANGE",
+    "ZRANGEBYSCORE",
+    "ZREM",
+    "ZSCAN",
+    "ZSCORE",
+];
+
+/// The kinds of error that are transient, i.e. those of a broken connection or a server that is loading its dataset.
+const TRANSIENT_KINDS: &[ErrorKind] = &[ErrorKind::IoError, ErrorKind::BusyLoadingError];
+
+/// The error codes of clusters and replicated deployments that are transient.
+const TRANSIENT_CODES: &[&str] = &["TRYAGAIN", "CLUSTERDOWN", "MASTERDOWN"];
+
+/// Returns true if `error` is of a kind or has a code that the default policy retries.
+pub(crate) fn is_transient(error: &RedisError) -> bool {
+    TRANSIENT_KINDS.contains(&error.kind())
+        || error
+            .extension_error_code()
+            .is_some_and(|code| TRANSIENT_CODES.contains(&code))
+}
+
+/// Converts the error reported when the server closes a connection, which redis reports as a response error, into the
+/// I/O error that it is, so that the connection is reestablished and the request retried as for any other I/O error.
+pub(crate) fn disconnected(error: RedisError) -> RedisError {
+    if error.kind() == ErrorKind::ResponseError
+        && error.to_string().contains("Could not read enough bytes")
+    {
+        RedisError::from(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "The server closed the connection",
+        ))
+    } else {
+        error
+    }
+}
+
+/// Returns true if the command given by `args` may be performed more than once with the same effect, considering the
+/// subcommands and options of those commands that are only idempotent in some forms.
+fn is_idempotent_command(args: &[Vec<u8>]) -> bool {
+    let is = |arg: &[u8], names: &[&str]| {
+        names
+            .iter()
+            .any(|name| name.as_bytes().eq_ignore_ascii_case(arg))
+    };
+    let (name, args) = match args.split_first() {
+        Some(split) => split,
+        None => return false,
+    };
+    match name.to_ascii_uppercase().as_slice() {
+        b"CLUSTER" => args
+            .first()
+            .is_some_and(|subcommand| is(subcommand, &["INFO", "KEYSLOT", "NODES", "SLOTS"])),
+        b"SCRIPT" => args
+            .first()
+            .is_some_and(|subcommand| is(subcommand, &["EXISTS", "LOAD"])),
+        // With NX, XX, GT or LT, whether the expiry is set depends on whether a first attempt set it.
+        b"EXPIRE" | b"PEXPIRE" => args.len() == 2,
+        // Likewise with NX or XX, while with GET the reply of a second attempt is the value set by the first.
+        b"SET" => !args
+            .iter()
+            .skip(2)
+            .any(|option| is(option, &["GET", "NX", "XX"])),
+        name => IDEMPOTENT_COMMANDS
+            .iter()
+            .any(|command| command.as_bytes() == name),
+    }
+}
+
+/// Returns true if the packed request `cmd` consists only of idempotent commands.
+pub(crate) fn is_idempotent(cmd: &[u8]) -> bool {
+    crate::resp::parse_commands(cmd).is_some_and(|commands| {
+        !commands.is_empty()
+            && commands
+                .iter()
+                .all(|command| is_idempotent_command(command))
+    })
+}
+
+/// The policy by which a database retries requests that fail and reconnects broken connections.
+///
+/// A request is retried if its error is of a retryable kind and fewer than the maximum number of attempts have been
+/// made, waiting with exponential backoff between attempts. Since a request that fails with an I/O error may or may not
+/// have been performed by the server, such requests are only retried if they are idempotent (i.e. reads, LSET and LTRIM
+/// but not pushes, pops or scripts) unless `retry_non_idempotent` is set. A connection that fails with an I/O error,
+/// including one closed by the server, is transparently reestablished before its next request. Requests that time out
+/// are never retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    kinds: Vec<ErrorKind>,
+    codes: Vec<String>,
+    retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Makes up to 3 attempts with backoff from 50ms up to 2s with jitter, retrying I/O errors, LOADING errors and the
+    /// TRYAGAIN, CLUSTERDOWN and MASTERDOWN errors of clusters and replicated deployments.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+            jitter: true,
+            kinds: TRANSIENT_KINDS.to_vec(),
+            codes: TRANSIENT_CODES
+                .iter()
+                .map(|code| (*code).to_owned())
+                .collect(),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates the default policy, see the `Default` implementation.
+    pub fn new() -> Self {
+        RetryPolicy::default()
+    }
+    /// Creates a policy that makes a single attempt at each request. Broken connections are still reestablished
+    /// before the following request.
+    pub fn none() -> Self {
+        RetryPolicy::default().max_attempts(1)
+    }
+    /// Sets the maximum number of attempts made at each request, including the first. This is at least 1.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+    /// Sets the backoff before the first retry, which doubles with each following retry up to `max`.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+    /// Sets whether each backoff is randomized between half and the whole of its duration, so that clients that failed
+    /// together do not retry together. This is enabled by default.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+    /// Adds a kind of error that is retried.
+    pub fn retry_on(mut self, kind: ErrorKind) -> Self {
+        if !self.kinds.contains(&kind) {
+            self.kinds.push(kind);
+        }
+        self
+    }
+    /// Adds an error code reported by the server, such as `TRYAGAIN`, that is retried.
+    pub fn retry_on_code(mut self, code: &str) -> Self {
+        self.codes.push(code.to_owned());
+        self
+    }
+    /// Sets whether non-idempotent requests that fail with an I/O error are retried, at the risk of performing them
+    /// twice, e.g. pushing an element twice. This is disabled by default.
+    pub fn retry_non_idempotent(mut self, retry_non_idempotent: bool) -> Self {
+        self.retry_non_idempotent = retry_non_idempotent;
+        self
+    }
+    fn is_retryable(&self, error: &RedisError) -> bool {
+        self.kinds.contains(&error.kind())
+            || error
+                .extension_error_code()
+                .is_some_and(|code| self.codes.iter().any(|retryable| retryable == code))
+    }
+    /// Returns true if a request that failed on its `attempt`th attempt should be attempted again. `sent` is false if
+    /// the request failed before it was sent, i.e. while reconnecting, in which case it may always be retried.
+    /// `idempotent` is only called if the answer depends on whether the request is idempotent.
+    pub(crate) fn should_retry(
+        &self,
+        attempt: u32,
+        error: &RedisError,
+        sent: bool,
+        idempotent: impl FnOnce() -> bool,
+    ) -> bool {
+        attempt < self.max_attempts
+            && !error.is_timeout()
+            && self.is_retryable(error)
+            && (!sent || !error.is_io_error() || self.retry_non_idempotent || idempotent())
+    }
+    /// Returns the duration to wait after the `attempt`th attempt.
+    pub(crate) fn backoff_after(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .checked_mul(1 << (attempt - 1).min(31))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff));
+        if self.jitter {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u32(attempt);
+            let fraction = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+            backoff / 2 + backoff.mul_f64(fraction / 2.0)
+        } else {
+            backoff
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idempotent(command: &str) -> bool {
+        let mut args = command.split_whitespace();
+        let mut cmd = redis::cmd(args.next().unwrap());
+        for arg in args {
+            cmd.arg(arg);
+        }
+        is_idempotent(&cmd.get_packed_command())
+    }
+
+    #[test]
+    fn commands_are_idempotent_in_their_safe_forms() {
+        for command in [
+            "LRANGE list 0 -1",
+            "lset list 0 a",
+            "CLUSTER SLOTS",
+            "cluster nodes",
+            "SCRIPT LOAD return",
+            "SCRIPT EXISTS sha",
+            "EXPIRE key 10",
+            "SET key value",
+            "SET key value EX 10",
+        ] {
+            assert!(idempotent(command), "{}", command);
+        }
+        for command in [
+            "RPUSH list a",
+            "CLUSTER",
+            "CLUSTER FAILOVER",
+            "CLUSTER RESET HARD",
+            "CLUSTER ADDSLOTS 1",
+            "SCRIPT FLUSH",
+            "SCRIPT KILL",
+            "EXPIRE key 10 NX",
+            "PEXPIRE key 10 GT",
+            "SET key value NX",
+            "SET key value EX 10 get",
+        ] {
+            assert!(!idempotent(command), "{}", command);
+        }
+    }
+
+    #[test]
+    fn requests_are_idempotent_if_every_command_is() {
+        let mut pipeline = redis::pipe();
+        pipeline
+            .cmd("LLEN")
+            .arg("list")
+            .cmd("LSET")
+            .arg("list")
+            .arg(0)
+            .arg("a");
+        assert!(is_idempotent(&pipeline.get_packed_pipeline(true)));
+        pipeline.cmd("SCRIPT").arg("FLUSH");
+        assert!(!is_idempotent(&pipeline.get_packed_pipeline(true)));
+        assert!(!is_idempotent(b""));
+    }
+}
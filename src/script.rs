@@ -0,0 +1,216 @@
+use futures::{lazy, Future};
+
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
+
+use serde::{
+    de::{self, DeserializeOwned},
+    Serialize,
+};
+
+use crate::{
+    codec::Encoding,
+    collections::{Collection, List},
+    Connection, Error,
+};
+
+use std::sync::{Arc, RwLock};
+
+/// A Lua script that runs atomically on the server.
+///
+/// Scripts are cached on the server by their SHA1 digest: invocations are sent with EVALSHA and fall back to sending
+/// the full source with EVAL if the server has not cached the script, for example because it restarted. `load` may
+/// be used to cache the script in advance.
+///
+/// Clones of a script share its connection.
+#[derive(Clone)]
+pub struct Script {
+    connection: Arc<RwLock<Connection>>,
+    code: Arc<String>,
+    hash: String,
+    encoding: Encoding,
+}
+
+impl Script {
+    pub(crate) fn new(code: &str, connection: Connection) -> Script {
+        Script {
+            encoding: Encoding::new(connection.codec()),
+            connection: Arc::new(RwLock::new(connection)),
+            hash: redis::Script::new(code).get_hash().to_owned(),
+            code: Arc::new(code.to_owned()),
+        }
+    }
+    /// Returns the SHA1 digest of the script in hexadecimal.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+    /// Caches the script on the server with SCRIPT LOAD.
+    pub fn load(&self) -> impl Future<Item = (), Error = Error> {
+        let code = self.code.clone();
+        let connection = self.connection.clone();
+        lazy(move || {
+            let _: String = redis::cmd("SCRIPT")
+                .arg("LOAD")
+                .arg(code.as_str())
+                .query(&mut *connection.write().unwrap())?;
+            Ok(())
+        })
+    }
+    /// Begins an invocation of the script with no keys or arguments.
+    pub fn invocation(&self) -> Invocation {
+        Invocation {
+            connection: self.connection.clone(),
+            code: self.code.clone(),
+            hash: self.hash.clone(),
+            encoding: self.encoding.clone(),
+            elements_key: None,
+            keys: vec![],
+            args: vec![],
+            error: None,
+        }
+    }
+}
+
+/// A pending invocation of a Script, built up by adding keys and arguments.
+pub struct Invocation {
+    connection: Arc<RwLock<Connection>>,
+    code: Arc<String>,
+    hash: String,
+    encoding: Encoding,
+    elements_key: Option<String>,
+    keys: Vec<String>,
+    args: Vec<Vec<u8>>,
+    error: Option<Error>,
+}
+
+impl Invocation {
+    /// Appends the key of `collection` to `KEYS`.
+    pub fn key<C: Collection>(mut self, collection: &C) -> Self {
+        self.keys.push(collection.key());
+        self
+    }
+    /// Appends a raw key name to `KEYS`.
+    pub fn raw_key(mut self, key: &str) -> Self {
+        self.keys.push(key.to_owned());
+        self
+    }
+    /// Appends `arg` to `ARGV` serialized in the same manner as collection elements. This permits
+    /// comparing arguments with elements, but the script cannot otherwise easily interpret them. Arguments are
+    /// transformed by the codec of the database, if any, but not by those set on individual lists.
+    pub fn arg<A: Serialize>(mut self, arg: &A) -> Self {
+        match self.encoding.encode(arg) {
+            Ok(data) => self.args.push(data),
+            Err(err) => self.error = self.error.or(Some(err)),
+        }
+        self
+    }
+    /// Appends `arg` to `ARGV` in its redis representation, i.e. numbers and strings as their textual form, such
+    /// that the script may interpret them directly.
+    pub fn raw_arg<A: redis::ToRedisArgs>(mut self, arg: A) -> Self {
+        self.args.extend(arg.to_redis_args());
+        self
+    }
+    /// Decodes the elements in the reply of `invoke_decoded` with the codec and versioning of `list`, in place of the
+    /// codec of the database. This does not append the key of the list to `KEYS`.
+    pub fn elements_of<T: Serialize + DeserializeOwned>(mut self, list: &List<T>) -> Self {
+        self.encoding = list.encoding.clone();
+        self.elements_key = Some(list.key.clone());
+        self
+    }
+    /// Runs the script and converts its reply to `R`. Elements of collections in the reply, which are stored
+    /// serialized, may be deserialized by wrapping the relevant part of `R` in `Decoded`, provided they are stored
+    /// without a codec or versioning; otherwise see `invoke_decoded`.
+    pub fn invoke<R: FromRedisValue>(mut self) -> impl Future<Item = R, Error = Error> {
+        lazy(move || Ok(R::from_redis_value(&self.run()?)?))
+    }
+    /// Runs the script, whose reply must be an element of a collection, an array of elements or nil, and decodes the
+    /// elements in the same manner as those of a collection: with the codec of the database, or the codec and
+    /// versioning of the list given to `elements_of`. Elements stored at an earlier version are upgraded but not
+    /// rewritten. A reply of nil produces no elements, and an element that cannot be decoded fails the invocation with
+    /// `Error::Decode` or `Error::Codec`.
+    pub fn invoke_decoded<T: DeserializeOwned>(
+        mut self,
+    ) -> impl Future<Item = Vec<T>, Error = Error> {
+        lazy(move || {
+            let reply = self.run()?;
+            let key = self
+                .elements_key
+                .as_ref()
+                .or_else(|| self.keys.first())
+                .map_or("", String::as_str);
+            let decode = |value: &Value| match *value {
+                Value::Data(ref data) => self.encoding.decode(key, None, data),
+                _ => Err(Error::Decode {
+                    key: key.to_owned(),
+                    index: None,
+                    source: de::Error::custom(format!("{:?} is not an element", value)),
+                }),
+            };
+            match reply {
+                Value::Nil => Ok(vec![]),
+                Value::Bulk(ref values) => values.iter().map(decode).collect(),
+                ref value => Ok(vec![decode(value)?]),
+            }
+        })
+    }
+    /// Runs the script, returning its reply.
+    fn run(&mut self) -> Result<Value, Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        let mut connection = self.connection.write().unwrap();
+        let keys: Vec<&str> = self.keys.iter().map(String::as_str).collect();
+        connection.check_slots(&keys)?;
+        let result = redis::cmd("EVALSHA")
+            .arg(&self.hash)
+            .arg(self.keys.len())
+            .arg(self.keys.as_slice())
+            .arg(self.args.as_slice())
+            .query(&mut *connection);
+        match result {
+            Err(ref err) if err.kind() == ErrorKind::NoScriptError => redis::cmd("EVAL")
+                .arg(self.code.as_str())
+                .arg(self.keys.len())
+                .arg(self.keys.as_slice())
+                .arg(self.args.as_slice())
+                .query(&mut *connection)
+                .map_err(Error::script),
+            result => result.map_err(Error::script),
+        }
+    }
+}
+
+/// A value in a script reply that is deserialized in the same manner as collection elements, without the codec or
+/// versioning of any collection. See `Invocation::invoke_decoded` for elements stored with either.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded<T>(pub T);
+
+impl<T> Decoded<T> {
+    /// Unwraps the decoded value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DeserializeOwned> FromRedisValue for Decoded<T> {
+    fn from_redis_value(value: &Value) -> RedisResult<Self> {
+        match *value {
+            Value::Data(ref data) => serde_cbor::from_slice(data.as_slice())
+                .map(Decoded)
+                .map_err(|err| {
+                    RedisError::from((
+                        ErrorKind::TypeError,
+                        "Response could not be decoded",
+                        err.to_string(),
+                    ))
+                }),
+            _ => Err(RedisError::from((
+                ErrorKind::TypeError,
+                "Response was of incompatible type",
+                format!("{:?} (response was not binary data)", value),
+            ))),
+        }
+    }
+    fn from_redis_values(values: &[Value]) -> RedisResult<Vec<Self>> {
+        values.iter().map(Decoded::from_redis_value).collect()
+    }
+}
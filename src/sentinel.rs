@@ -0,0 +1,266 @@
+use redis::{
+    Client, ConnectionAddr, ConnectionInfo, ConnectionLike, ErrorKind, RedisError, RedisResult,
+    Value,
+};
+
+use crate::{retry::disconnected, timeout::Timeouts};
+
+use std::{
+    sync::{Arc, RwLock, Weak},
+    thread,
+    time::Duration,
+};
+
+const MONITOR_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The current master of a set of redis servers monitored by Sentinel, along with a generation that is incremented
+/// whenever the master changes.
+pub(crate) struct Sentinel {
+    sentinels: Vec<ConnectionInfo>,
+    master_name: String,
+    db: i64,
+    passwd: Option<String>,
+    master: RwLock<Option<(ConnectionInfo, u64)>>,
+}
+
+impl Sentinel {
+    /// Creates a handle to the master named `master_name` monitored by `sentinels`. The database and password of the
+    /// master are taken from the first sentinel address. This begins monitoring the sentinels for failovers on a
+    /// background thread, which exits once the handle is dropped.
+    pub(crate) fn new(sentinels: Vec<ConnectionInfo>, master_name: String) -> Arc<Sentinel> {
+        let db = sentinels.first().map_or(0, |sentinel| sentinel.db);
+        let passwd = sentinels
+            .first()
+            .and_then(|sentinel| sentinel.passwd.clone());
+        let sentinel = Arc::new(Sentinel {
+            sentinels: sentinels
+                .into_iter()
+                .map(|sentinel| ConnectionInfo {
+                    db: 0,
+                    passwd: None,
+                    ..sentinel
+                })
+                .collect(),
+            master_name,
+            db,
+            passwd,
+            master: RwLock::new(None),
+        });
+        let monitored = Arc::downgrade(&sentinel);
+        thread::spawn(move || Sentinel::monitor(&monitored));
+        sentinel
+    }
+    fn master_info(&self, host: String, port: u16) -> ConnectionInfo {
+        ConnectionInfo {
+            addr: Box::new(ConnectionAddr::Tcp(host, port)),
+            db: self.db,
+            passwd: self.passwd.clone(),
+        }
+    }
+    fn update(&self, info: ConnectionInfo) -> (ConnectionInfo, u64) {
+        let mut master = self.master.write().unwrap();
+        let current = match master.take() {
+            Some((ref current, generation)) if current.addr == info.addr => {
+                (current.clone(), generation)
+            }
+            Some((_, generation)) => (info, generation + 1),
+            None => (info, 0),
+        };
+        *master = Some(current.clone());
+        current
+    }
+    /// Asks each sentinel in turn for the address of the master, connecting to and awaiting the reply of each within
+    /// `timeouts`, so that an unreachable sentinel is skipped.
+    pub(crate) fn discover(&self, timeouts: &Timeouts) -> RedisResult<(ConnectionInfo, u64)> {
+        let mut last_error = None;
+        for sentinel in &self.sentinels {
+            let address = Client::open(sentinel.clone()).and_then(|client| {
+                redis::cmd("SENTINEL")
+                    .arg("get-master-addr-by-name")
+                    .arg(&self.master_name)
+                    .query::<Option<(String, u16)>>(&mut timeouts.connect(&client)?)
+            });
+            match address {
+                Ok(Some((host, port))) => return Ok(self.update(self.master_info(host, port))),
+                Ok(None) => {
+                    last_error = Some(RedisError::from((
+                        ErrorKind::ResponseError,
+                        "Sentinel does not know the master",
+                        self.master_name.clone(),
+                    )))
+                }
+                Err(err) => last_error = Some(err),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            RedisError::from((ErrorKind::InvalidClientConfig, "No sentinels were provided"))
+        }))
+    }
+    /// Returns the current master, discovering it within `timeouts` if it is not yet known.
+    pub(crate) fn master(&self, timeouts: &Timeouts) -> RedisResult<(ConnectionInfo, u64)> {
+        let master = self.master.read().unwrap().clone();
+        match master {
+            Some(master) => Ok(master),
+            None => self.discover(timeouts),
+        }
+    }
+    /// Returns the generation of the current master.
+    pub(crate) fn generation(&self) -> Option<u64> {
+        self.master
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|(_, generation)| *generation)
+    }
+    /// Opens a connection to the current master, returning it along with the generation of the master.
+    pub(crate) fn connect(&self, timeouts: &Timeouts) -> RedisResult<(redis::Connection, u64)> {
+        let (info, generation) = self.master(timeouts)?;
+        Ok((timeouts.connect(&Client::open(info)?)?, generation))
+    }
+    /// Follows `+switch-master` announcements of each sentinel in turn until the handle is dropped.
+    fn monitor(sentinel: &Weak<Sentinel>) {
+        let mut index = 0;
+        loop {
+            let info = match sentinel.upgrade() {
+                Some(sentinel) => {
+                    if sentinel.sentinels.is_empty() {
+                        return;
+                    }
+                    sentinel.sentinels[index % sentinel.sentinels.len()].clone()
+                }
+                None => return,
+            };
+            index += 1;
+            let timeouts = Timeouts {
+                connect: Some(MONITOR_TIMEOUT),
+                read: Some(MONITOR_TIMEOUT),
+                write: Some(MONITOR_TIMEOUT),
+            };
+            let connection = Client::open(info).and_then(|client| timeouts.connect(&client));
+            let mut connection = match connection {
+                Ok(connection) => connection,
+                Err(_) => {
+                    thread::sleep(MONITOR_TIMEOUT);
+                    continue;
+                }
+            };
+            let mut pubsub = connection.as_pubsub();
+            if pubsub.subscribe("+switch-master").is_err()
+                || pubsub.set_read_timeout(Some(MONITOR_TIMEOUT)).is_err()
+            {
+                thread::sleep(MONITOR_TIMEOUT);
+                continue;
+            }
+            loop {
+                let message = pubsub.get_message();
+                let sentinel = match sentinel.upgrade() {
+                    Some(sentinel) => sentinel,
+                    None => return,
+                };
+                match message {
+                    Ok(message) => {
+                        // The payload is of the form `<name> <old host> <old port> <new host> <new port>`.
+                        let payload: String = message.get_payload().unwrap_or_default();
+                        let parts: Vec<&str> = payload.split(' ').collect();
+                        if let [name, _, _, host, port] = parts.as_slice() {
+                            if *name == sentinel.master_name {
+                                if let Ok(port) = port.parse() {
+                                    sentinel.update(sentinel.master_info((*host).to_owned(), port));
+                                }
+                            }
+                        }
+                    }
+                    Err(ref err) if err.is_timeout() => {}
+                    Err(_) => break,
+                }
+            }
+            // A sentinel that accepts connections but fails them immediately would otherwise be retried in a busy loop.
+            thread::sleep(MONITOR_TIMEOUT);
+        }
+    }
+}
+
+/// A connection to the master of a Sentinel-monitored set of servers that follows failovers.
+///
+/// The connection is re-established when the sentinels announce a new master, after an I/O error and when the server
+/// rejects a write because it has been demoted to a replica. Requests that were rejected by a demoted master are
+/// retried on the new master, but requests interrupted by an I/O error are not, since they may have been performed.
+pub(crate) struct SentinelConnection {
+    sentinel: Arc<Sentinel>,
+    connection: Option<(redis::Connection, u64)>,
+    db: i64,
+    timeouts: Timeouts,
+}
+
+impl SentinelConnection {
+    pub(crate) fn connect(sentinel: Arc<Sentinel>, timeouts: Timeouts) -> RedisResult<Self> {
+        let connection = sentinel.connect(&timeouts)?;
+        Ok(SentinelConnection {
+            db: sentinel.db,
+            connection: Some(connection),
+            sentinel,
+            timeouts,
+        })
+    }
+    /// Sets the timeouts of the connection to the master.
+    pub(crate) fn set_timeouts(&mut self, timeouts: Timeouts) -> RedisResult<()> {
+        self.timeouts = timeouts;
+        match self.connection {
+            Some((ref connection, _)) => timeouts.apply(connection),
+            None => Ok(()),
+        }
+    }
+    /// Returns the connection to the current master, reconnecting if the master has changed.
+    pub(crate) fn connection(&mut self) -> RedisResult<&mut redis::Connection> {
+        let current = self.sentinel.generation();
+        let stale = match self.connection {
+            Some((_, generation)) => current != Some(generation),
+            None => true,
+        };
+        if stale {
+            self.connection = None;
+            self.connection = Some(self.sentinel.connect(&self.timeouts)?);
+        }
+        Ok(&mut self.connection.as_mut().unwrap().0)
+    }
+    fn request<R, F>(&mut self, mut request: F) -> RedisResult<R>
+    where
+        F: FnMut(&mut redis::Connection) -> RedisResult<R>,
+    {
+        let result = request(self.connection()?).map_err(disconnected);
+        match result {
+            Err(ref err) if err.extension_error_code() == Some("READONLY") => {
+                self.connection = None;
+                self.sentinel.discover(&self.timeouts)?;
+                request(self.connection()?).map_err(disconnected)
+            }
+            Err(err) => {
+                if err.is_io_error() {
+                    self.connection = None;
+                    let _ = self.sentinel.discover(&self.timeouts);
+                }
+                Err(err)
+            }
+            result => result,
+        }
+    }
+}
+
+impl ConnectionLike for SentinelConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.request(|connection| connection.req_packed_command(cmd))
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.request(|connection| connection.req_packed_commands(cmd, offset, count))
+    }
+
+    fn get_db(&self) -> i64 {
+        self.db
+    }
+}
@@ -1,9 +1,6 @@
-use redis_backed::collections::List;
-use redis_backed::Database;
+use redis_backed::blocking::{Database, List};
 use serde::{Deserialize, Serialize};
 
-use futures::Future;
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Person {
     name: String,
@@ -11,33 +8,15 @@ pub struct Person {
 }
 
 fn main() {
-    tokio::run(
-        Database::new("redis://127.0.0.1/")
-            .map_err(|e| {
-                eprintln!("{:?}", e);
-                ()
-            })
-            .and_then(|mut database| {
-                database
-                    .get::<List<Person>>("people")
-                    .map_err(|e| {
-                        eprintln!("{:?}", e);
-                        ()
-                    })
-                    .and_then(|mut list| {
-                        list.push_front(Person {
-                            name: "john".to_owned(),
-                            age: 52,
-                        })
-                        .map_err(|e| {
-                            eprintln!("{:?}", e);
-                            ()
-                        })
-                        .and_then(|item| {
-                            println!("{:?}", item);
-                            Ok(())
-                        })
-                    })
-            }),
-    );
-}
\ No newline at end of file
+    let result = Database::new("redis://127.0.0.1/").and_then(|database| {
+        let mut list = database.get::<List<Person>>("people")?;
+        list.push_front(Person {
+            name: "john".to_owned(),
+            age: 52,
+        })
+    });
+    match result {
+        Ok(item) => println!("{:?}", item),
+        Err(e) => eprintln!("{:?}", e),
+    }
+}
@@ -0,0 +1,490 @@
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use redis::{ConnectionAddr, ConnectionInfo, RedisResult, Value};
+
+use crate::{
+    cluster::{command_keys, key_slot, SLOTS},
+    memory::{Memory, Reply, Response, Session},
+    resp::{encode, parse_command},
+};
+
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind, Read, Write},
+    mem,
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A server speaking the redis protocol on an ephemeral port of the loopback interface, backed by the same emulation
+/// as `Database::in_memory`, including keyspace notifications published to subscribers of `__keyspace@0__:<key>`
+/// channels. This permits tests to exercise the network code of a `Database` without a redis server, i.e.
+/// `Database::new(server.connection_info())`.
+///
+/// Every connection shares a single database, which is discarded along with the server. Dropping the server closes
+/// its connections, which permits testing the behaviour of clients when the server goes away.
+pub struct FakeServer {
+    address: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    disconnects: Arc<AtomicUsize>,
+}
+
+impl FakeServer {
+    /// Starts a server with an empty database.
+    pub fn start() -> io::Result<FakeServer> {
+        FakeServer::listen(TcpListener::bind("127.0.0.1:0")?, Role::Server)
+    }
+    /// Serves clients accepted by `listener` in the provided role.
+    fn listen(listener: TcpListener, role: Role) -> io::Result<FakeServer> {
+        let address = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let disconnects = Arc::new(AtomicUsize::new(0));
+        let memory = Arc::new(Memory::default());
+        let stopped_cloned = stopped.clone();
+        let disconnects_cloned = disconnects.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped_cloned.load(Ordering::SeqCst) {
+                    return;
+                }
+                if let Ok(stream) = stream {
+                    let memory = memory.clone();
+                    let role = role.clone();
+                    let stopped = stopped_cloned.clone();
+                    let disconnects = disconnects_cloned.clone();
+                    thread::spawn(move || {
+                        let _ = serve(stream, memory, &role, address, &stopped, &disconnects);
+                    });
+                }
+            }
+        });
+        Ok(FakeServer {
+            address,
+            stopped,
+            disconnects,
+        })
+    }
+    /// Closes the connection that sent each of the next `count` commands once the command has been performed, without
+    /// replying to it, as if the connection failed before the reply arrived. This permits testing how clients recover
+    /// from I/O errors.
+    pub fn disconnect_next(&self, count: usize) {
+        self.disconnects.store(count, Ordering::SeqCst);
+    }
+    /// Returns the address on which the server is listening.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+    /// Returns the URL of the server, i.e. `redis://127.0.0.1:<port>/`.
+    pub fn url(&self) -> String {
+        format!("redis://{}/", self.address)
+    }
+    /// Returns the connection information of the server, suitable for `Database::new`.
+    pub fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            addr: Box::new(ConnectionAddr::Tcp(
+                self.address.ip().to_string(),
+                self.address.port(),
+            )),
+            db: 0,
+            passwd: None,
+        }
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes the listening thread so that it observes the server being stopped.
+        let _ = TcpStream::connect(self.address);
+    }
+}
+
+/// The role in which a fake server serves its clients, which determines the commands it handles itself rather than
+/// performing them on its database.
+#[derive(Clone)]
+enum Role {
+    Server,
+    Node(Arc<Topology>),
+    Sentinel(Arc<Monitored>),
+}
+
+/// The ranges of hash slots served by each node of a fake cluster, and the slots being migrated to other nodes.
+struct Topology {
+    slots: Vec<(u16, u16, SocketAddr)>,
+    migrating: Mutex<HashMap<u16, SocketAddr>>,
+}
+
+/// A redis cluster of fake servers, each serving an equal share of the hash slots with a database of its own. Nodes
+/// answer CLUSTER SLOTS and redirect commands on keys of the slots of other nodes with MOVED, or with ASK for slots
+/// being migrated (see `migrate`), which permits testing the routing of a `Database::cluster`, i.e.
+/// `Database::cluster(cluster.connection_infos())`.
+pub struct FakeCluster {
+    nodes: Vec<FakeServer>,
+    topology: Arc<Topology>,
+}
+
+impl FakeCluster {
+    /// Starts a cluster of `nodes` nodes with empty databases.
+    pub fn start(nodes: usize) -> io::Result<FakeCluster> {
+        let listeners = (0..nodes.max(1))
+            .map(|_| TcpListener::bind("127.0.0.1:0"))
+            .collect::<io::Result<Vec<_>>>()?;
+        let share = SLOTS / listeners.len() as u16;
+        let slots = listeners
+            .iter()
+            .enumerate()
+            .map(|(i, listener)| {
+                let start = i as u16 * share;
+                let end = if i + 1 == listeners.len() {
+                    SLOTS - 1
+                } else {
+                    start + share - 1
+                };
+                Ok((start, end, listener.local_addr()?))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let topology = Arc::new(Topology {
+            slots,
+            migrating: Mutex::default(),
+        });
+        let nodes = listeners
+            .into_iter()
+            .map(|listener| FakeServer::listen(listener, Role::Node(topology.clone())))
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(FakeCluster { nodes, topology })
+    }
+    /// Starts migrating the slot of `key` to the node following its owner. The owner then redirects commands on keys of
+    /// the slot to that node with ASK, which serves them only when they follow ASKING, while CLUSTER SLOTS continues to
+    /// report the owner. Keys are not moved, so the slot should hold no keys when its migration begins.
+    pub fn migrate(&self, key: &str) {
+        let slot = key_slot(key.as_bytes());
+        let owner = self
+            .topology
+            .slots
+            .iter()
+            .position(|(start, end, _)| *start <= slot && slot <= *end)
+            .unwrap();
+        let target = self.nodes[(owner + 1) % self.nodes.len()].address();
+        self.topology.migrating.lock().unwrap().insert(slot, target);
+    }
+    /// Returns the nodes of the cluster, in the order of the slots they serve.
+    pub fn nodes(&self) -> &[FakeServer] {
+        &self.nodes
+    }
+    /// Returns the connection information of every node, suitable for `Database::cluster`.
+    pub fn connection_infos(&self) -> Vec<ConnectionInfo> {
+        self.nodes.iter().map(FakeServer::connection_info).collect()
+    }
+}
+
+/// The master monitored by a fake sentinel.
+struct Monitored {
+    name: String,
+    master: Mutex<SocketAddr>,
+}
+
+/// A sentinel monitoring a single master, which answers SENTINEL GET-MASTER-ADDR-BY-NAME with the address of the
+/// master and announces failovers with `+switch-master` messages, which permits testing how a `Database::sentinel`
+/// follows failovers, i.e. `Database::sentinel(vec![sentinel.connection_info()], "master")`. Other commands are
+/// performed on a database of its own.
+pub struct FakeSentinel {
+    server: FakeServer,
+    monitored: Arc<Monitored>,
+}
+
+impl FakeSentinel {
+    /// Starts a sentinel monitoring `master` under the name `name`.
+    pub fn start(name: &str, master: &FakeServer) -> io::Result<FakeSentinel> {
+        let monitored = Arc::new(Monitored {
+            name: name.to_owned(),
+            master: Mutex::new(master.address()),
+        });
+        let server = FakeServer::listen(
+            TcpListener::bind("127.0.0.1:0")?,
+            Role::Sentinel(monitored.clone()),
+        )?;
+        Ok(FakeSentinel { server, monitored })
+    }
+    /// Makes `master` the monitored master and announces the failover, returning the number of subscribers to
+    /// `+switch-master` that received the announcement.
+    pub fn failover(&self, master: &FakeServer) -> RedisResult<usize> {
+        let previous = mem::replace(
+            &mut *self.monitored.master.lock().unwrap(),
+            master.address(),
+        );
+        let message = format!(
+            "{} {} {} {} {}",
+            self.monitored.name,
+            previous.ip(),
+            previous.port(),
+            master.address().ip(),
+            master.address().port()
+        );
+        let mut connection = redis::Client::open(self.connection_info())?.get_connection()?;
+        redis::cmd("PUBLISH")
+            .arg("+switch-master")
+            .arg(message)
+            .query(&mut connection)
+    }
+    /// Returns the connection information of the sentinel, suitable for `Database::sentinel`.
+    pub fn connection_info(&self) -> ConnectionInfo {
+        self.server.connection_info()
+    }
+}
+
+/// Replies to the commands that a server in `role` at `address` handles itself, if any.
+fn role_reply(
+    role: &Role,
+    address: SocketAddr,
+    args: &[Vec<u8>],
+    asking: bool,
+) -> Option<Reply<Value>> {
+    match role {
+        Role::Server => None,
+        Role::Node(topology) => cluster_reply(topology, address, args, asking),
+        Role::Sentinel(monitored) => sentinel_reply(monitored, args),
+    }
+}
+
+/// Replies to SENTINEL GET-MASTER-ADDR-BY-NAME with the address of the master if it is asked for by name.
+fn sentinel_reply(monitored: &Monitored, args: &[Vec<u8>]) -> Option<Reply<Value>> {
+    match args {
+        [command, subcommand, name]
+            if command.eq_ignore_ascii_case(b"SENTINEL")
+                && subcommand.eq_ignore_ascii_case(b"GET-MASTER-ADDR-BY-NAME") =>
+        {
+            if *name != monitored.name.as_bytes() {
+                return Some(Ok(Value::Nil));
+            }
+            let master = *monitored.master.lock().unwrap();
+            Some(Ok(Value::Bulk(vec![
+                data(master.ip().to_string().as_bytes()),
+                data(master.port().to_string().as_bytes()),
+            ])))
+        }
+        _ => None,
+    }
+}
+
+/// Replies to the commands that a node of the cluster `topology` at `address` handles itself, i.e. CLUSTER SLOTS and
+/// commands on keys of slots served by other nodes or being migrated, which are redirected. Commands on keys of a slot
+/// being migrated to the node are served if `asking`.
+fn cluster_reply(
+    topology: &Topology,
+    address: SocketAddr,
+    args: &[Vec<u8>],
+    asking: bool,
+) -> Option<Reply<Value>> {
+    if args
+        .first()
+        .is_some_and(|name| name.eq_ignore_ascii_case(b"CLUSTER"))
+    {
+        return Some(Ok(Value::Bulk(
+            topology
+                .slots
+                .iter()
+                .map(|(start, end, address)| {
+                    Value::Bulk(vec![
+                        Value::Int(i64::from(*start)),
+                        Value::Int(i64::from(*end)),
+                        Value::Bulk(vec![
+                            data(address.ip().to_string().as_bytes()),
+                            Value::Int(i64::from(address.port())),
+                        ]),
+                    ])
+                })
+                .collect(),
+        )));
+    }
+    command_keys(args)
+        .into_iter()
+        .map(key_slot)
+        .find_map(|slot| {
+            let (_, _, owner) = topology
+                .slots
+                .iter()
+                .find(|(start, end, _)| *start <= slot && slot <= *end)?;
+            match topology.migrating.lock().unwrap().get(&slot) {
+                Some(target) if *owner == address => Some(Err(format!("ASK {} {}", slot, target))),
+                Some(target) if *target == address && asking => None,
+                _ => (*owner != address).then(|| Err(format!("MOVED {} {}", slot, owner))),
+            }
+        })
+}
+
+fn reply(value: &Value) -> Vec<u8> {
+    let mut out = vec![];
+    encode(value, &mut out);
+    out
+}
+
+/// Appends the encoding of a reply, which may be an error, to `out`.
+fn encode_reply(reply: Reply<Value>, out: &mut Vec<u8>) {
+    match reply {
+        Ok(value) => encode(&value, out),
+        Err(line) => out.extend(format!("-{}\r\n", line).into_bytes()),
+    }
+}
+
+fn data(data: &[u8]) -> Value {
+    Value::Data(data.to_vec())
+}
+
+/// Writes the messages received on `receiver` to the client as messages of `channel` while `active`.
+fn forward(
+    receiver: Receiver<Vec<u8>>,
+    channel: Vec<u8>,
+    writer: Arc<Mutex<TcpStream>>,
+    active: Arc<AtomicBool>,
+) {
+    while active.load(Ordering::SeqCst) {
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(message) => {
+                let message = reply(&Value::Bulk(vec![
+                    data(b"message"),
+                    data(&channel),
+                    Value::Data(message),
+                ]));
+                if writer.lock().unwrap().write_all(&message).is_err() {
+                    return;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+/// Serves a client until it disconnects or the server is stopped.
+fn serve(
+    mut stream: TcpStream,
+    memory: Arc<Memory>,
+    role: &Role,
+    address: SocketAddr,
+    stopped: &AtomicBool,
+    disconnects: &AtomicUsize,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let writer = Arc::new(Mutex::new(stream.try_clone()?));
+    let mut session = Session::new(memory.clone());
+    let mut subscriptions: Vec<(Vec<u8>, Arc<AtomicBool>)> = vec![];
+    // As in redis, ASKING applies to the next command, or to every command of a transaction that it begins.
+    let (mut asking, mut transaction) = (false, false);
+    let mut buffer = vec![];
+    let mut chunk = [0; 4096];
+    let result = 'serve: loop {
+        while let Some((args, length)) = parse_command(&buffer) {
+            buffer.drain(..length);
+            let name = args
+                .first()
+                .map(|name| name.to_ascii_uppercase())
+                .unwrap_or_default();
+            let mut out = vec![];
+            match name.as_slice() {
+                b"SUBSCRIBE" => {
+                    for channel in &args[1..] {
+                        let active = Arc::new(AtomicBool::new(true));
+                        let receiver = memory.subscribe(channel);
+                        let (channel_cloned, writer, active_cloned) =
+                            (channel.clone(), writer.clone(), active.clone());
+                        thread::spawn(move || {
+                            forward(receiver, channel_cloned, writer, active_cloned)
+                        });
+                        subscriptions.push((channel.clone(), active));
+                        out.extend(reply(&Value::Bulk(vec![
+                            data(b"subscribe"),
+                            data(channel),
+                            Value::Int(subscriptions.len() as i64),
+                        ])));
+                    }
+                }
+                b"UNSUBSCRIBE" => {
+                    let channels: Vec<Vec<u8>> = if args.len() > 1 {
+                        args[1..].to_vec()
+                    } else {
+                        subscriptions
+                            .iter()
+                            .map(|(channel, _)| channel.clone())
+                            .collect()
+                    };
+                    for channel in channels {
+                        subscriptions.retain(|(subscribed, active)| {
+                            let retained = *subscribed != channel;
+                            if !retained {
+                                active.store(false, Ordering::SeqCst);
+                            }
+                            retained
+                        });
+                        out.extend(reply(&Value::Bulk(vec![
+                            data(b"unsubscribe"),
+                            data(&channel),
+                            Value::Int(subscriptions.len() as i64),
+                        ])));
+                    }
+                }
+                b"ASKING" => {
+                    asking = true;
+                    out.extend(b"+OK\r\n");
+                }
+                b"QUIT" => {
+                    writer.lock().unwrap().write_all(b"+OK\r\n")?;
+                    break 'serve Ok(());
+                }
+                _ => match role_reply(role, address, &args, asking) {
+                    Some(reply) => encode_reply(reply, &mut out),
+                    None => match session.execute(args) {
+                        Ok(Response::Value(value)) => encode(&value, &mut out),
+                        Ok(Response::Transaction(replies)) => {
+                            out.extend(format!("*{}\r\n", replies.len()).into_bytes());
+                            for reply in replies {
+                                encode_reply(reply, &mut out);
+                            }
+                        }
+                        Err(line) => encode_reply(Err(line), &mut out),
+                    },
+                },
+            }
+            let disconnect = disconnects
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                    count.checked_sub(1)
+                })
+                .is_ok();
+            if disconnect {
+                let _ = stream.shutdown(Shutdown::Both);
+                break 'serve Ok(());
+            }
+            match name.as_slice() {
+                b"ASKING" => {}
+                b"MULTI" => transaction = true,
+                b"EXEC" | b"DISCARD" => {
+                    transaction = false;
+                    asking = false;
+                }
+                _ => asking &= transaction,
+            }
+            writer.lock().unwrap().write_all(&out)?;
+        }
+        match stream.read(&mut chunk) {
+            Ok(0) => break Ok(()),
+            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+            Err(ref err)
+                if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut =>
+            {
+                if stopped.load(Ordering::SeqCst) {
+                    break 'serve Ok(());
+                }
+            }
+            Err(err) => break Err(err),
+        }
+    };
+    for (_, active) in subscriptions {
+        active.store(false, Ordering::SeqCst);
+    }
+    result
+}
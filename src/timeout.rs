@@ -0,0 +1,146 @@
+use futures::{Future, Poll, Stream};
+use redis::{RedisError, RedisResult};
+
+use crate::{collections::list::Iter, CollectionNames, Keys};
+
+use serde::de::DeserializeOwned;
+use std::{cell::Cell, io, thread, time::Duration};
+
+thread_local! {
+    static OVERRIDE: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// The timeouts applied to the connections of a database. A timeout of `None` is unbounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Timeouts {
+    pub(crate) connect: Option<Duration>,
+    pub(crate) read: Option<Duration>,
+    pub(crate) write: Option<Duration>,
+}
+
+fn timed_out(description: &'static str) -> RedisError {
+    RedisError::from(io::Error::new(io::ErrorKind::TimedOut, description))
+}
+
+impl Timeouts {
+    /// Returns these timeouts as overridden by the `WithTimeout` being polled, if any, and with the read timeout
+    /// extended by `blocking` for a command that blocks on the server for that long, where zero blocks indefinitely.
+    pub(crate) fn current(self, blocking: Option<Duration>) -> Timeouts {
+        let mut timeouts = match OVERRIDE.with(Cell::get) {
+            Some(timeout) => Timeouts {
+                connect: Some(timeout),
+                read: Some(timeout),
+                write: Some(timeout),
+            },
+            None => self,
+        };
+        match blocking {
+            Some(blocking) if blocking == Duration::from_secs(0) => timeouts.read = None,
+            Some(blocking) => timeouts.read = timeouts.read.map(|read| read + blocking),
+            None => {}
+        }
+        timeouts
+    }
+    /// Applies the read and write timeouts to `connection`.
+    pub(crate) fn apply(&self, connection: &redis::Connection) -> RedisResult<()> {
+        connection.set_read_timeout(self.read)?;
+        connection.set_write_timeout(self.write)
+    }
+    /// Opens a connection with `client` and applies the read and write timeouts to it. If the connection is not
+    /// established within the connect timeout the attempt is abandoned and a timeout error returned.
+    pub(crate) fn connect(&self, client: &redis::Client) -> RedisResult<redis::Connection> {
+        let connection = match self.connect {
+            None => client.get_connection()?,
+            Some(timeout) => {
+                let (sender, receiver) = crossbeam_channel::bounded(1);
+                let client = client.clone();
+                thread::spawn(move || {
+                    let _ = sender.send(client.get_connection());
+                });
+                receiver
+                    .recv_timeout(timeout)
+                    .map_err(|_| timed_out("Timed out while connecting"))??
+            }
+        };
+        self.apply(&connection)?;
+        Ok(connection)
+    }
+}
+
+/// A future or stream whose requests are bounded by a timeout, see `TimeoutExt::with_timeout`.
+pub struct WithTimeout<F> {
+    inner: F,
+    timeout: Duration,
+}
+
+/// Restores the timeout override that was in effect before a `WithTimeout` was polled, even if polling panics.
+struct Restore(Option<Duration>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        OVERRIDE.with(|current| current.set(self.0));
+    }
+}
+
+impl<F> WithTimeout<F> {
+    fn scope<R>(&mut self, poll: impl FnOnce(&mut F) -> R) -> R {
+        let _restore = Restore(OVERRIDE.with(|current| current.replace(Some(self.timeout))));
+        poll(&mut self.inner)
+    }
+}
+
+impl<F: Future> Future for WithTimeout<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.scope(Future::poll)
+    }
+}
+
+impl<S: Stream> Stream for WithTimeout<S> {
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        self.scope(Stream::poll)
+    }
+}
+
+/// Bounds the requests made by the operations of collections. This is implemented by every future and by the streams
+/// of this crate that make requests as they are polled.
+pub trait TimeoutExt: Sized {
+    /// Overrides the connect, read and write timeouts of the database for the requests made while polling this
+    /// future or stream, i.e. `list.len().with_timeout(Duration::from_millis(100))`. A request that exceeds the
+    /// timeout fails with `Error::Timeout` and is not retried. Note that the timeout bounds each step of each request
+    /// rather than the operation as a whole.
+    fn with_timeout(self, timeout: Duration) -> WithTimeout<Self> {
+        WithTimeout {
+            inner: self,
+            timeout,
+        }
+    }
+}
+
+impl<F: Future> TimeoutExt for F {}
+
+impl<T: DeserializeOwned> TimeoutExt for Iter<T> {}
+
+impl TimeoutExt for Keys {}
+
+impl TimeoutExt for CollectionNames {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::poll_fn;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn overrides_are_restored_when_polling_panics() {
+        let mut panicking = poll_fn(|| -> Poll<(), ()> { panic!("polled") })
+            .with_timeout(Duration::from_millis(100));
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| panicking.poll())).is_err());
+        assert_eq!(OVERRIDE.with(Cell::get), None);
+    }
+}
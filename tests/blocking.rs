@@ -0,0 +1,70 @@
+//! Checks the blocking facade against the in-memory backend.
+
+use redis_backed::{
+    blocking::{Database, List, ReliableQueue},
+    collections::End,
+};
+
+use std::time::Duration;
+
+#[test]
+fn lists_round_trip() {
+    let database = Database::in_memory();
+    let mut list: List<u32> = database.get("numbers").unwrap();
+    assert!(list.is_empty().unwrap());
+    list.push_front(2).unwrap();
+    list.push_back(1).unwrap();
+    list.push_front(3).unwrap();
+    assert_eq!(list.range(0, -1).unwrap(), vec![1, 2, 3]);
+    assert_eq!(list.len().unwrap(), 3);
+    assert!(list.insert_after(2, 4).unwrap());
+    assert_eq!(
+        list.iter(2).collect::<Result<Vec<_>, _>>().unwrap(),
+        vec![1, 2, 4, 3]
+    );
+    assert_eq!(list.pop_front().unwrap(), Some(3));
+    assert_eq!(list.pop_back().unwrap(), Some(1));
+    assert_eq!(list.sort_by_key(&[], true).unwrap(), vec![4, 2]);
+}
+
+#[test]
+fn sinks_push_in_batches_and_when_flushed_or_closed() {
+    let database = Database::in_memory();
+    let mut list: List<u32> = database.get("numbers").unwrap();
+    let mut sink = list.sink(End::Front).batch_size(2);
+    for i in 0..3 {
+        sink.send(i).unwrap();
+    }
+    // The first batch has been pushed, while the last element remains buffered.
+    assert_eq!(list.range(0, -1).unwrap(), vec![0, 1]);
+    sink.flush().unwrap();
+    assert_eq!(list.range(0, -1).unwrap(), vec![0, 1, 2]);
+    sink.send(3).unwrap();
+    sink.close().unwrap();
+    assert_eq!(list.range(0, -1).unwrap(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn queues_round_trip() {
+    let database = Database::in_memory();
+    let mut queue = ReliableQueue::new(
+        database.get("queue").unwrap(),
+        database.get("processing").unwrap(),
+        Duration::from_secs(0),
+    );
+    queue.push("job".to_owned()).unwrap();
+    assert_eq!(queue.take().unwrap().unwrap().item(), "job");
+    assert!(queue.take().unwrap().is_none());
+    assert_eq!(queue.requeue_expired().unwrap(), 1);
+    let receipt = queue
+        .take_blocking(Duration::from_millis(100))
+        .unwrap()
+        .unwrap();
+    assert_eq!(receipt.item(), "job");
+    assert!(queue.ack(&receipt).unwrap());
+    assert!(!queue.ack(&receipt).unwrap());
+    assert!(queue
+        .take_blocking(Duration::from_millis(100))
+        .unwrap()
+        .is_none());
+}
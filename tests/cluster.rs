@@ -0,0 +1,179 @@
+//! Checks that a cluster database routes scripts to the nodes serving their keys, against a fake cluster whose nodes
+//! each have a script cache of their own, follows redirections of slots being migrated, and keeps the keys derived from
+//! a collection in its slot.
+#![cfg(feature = "test-util")]
+
+mod common;
+
+use common::{person, Person};
+use futures::Future;
+use redis_backed::{
+    collections::{CappedList, List, ReliableQueue, Sort},
+    test_util::FakeCluster,
+    Database, Error, Namespace,
+};
+use std::time::Duration;
+
+#[test]
+fn scripts_run_on_every_node() {
+    let cluster = FakeCluster::start(3).unwrap();
+    let database = Database::cluster(cluster.connection_infos())
+        .wait()
+        .unwrap();
+    // Enough lists that their keys reside on every node, including those other than the first.
+    for i in 0..12 {
+        let name = format!("people:{}", i);
+        let mut people = CappedList::new(database.get::<List<Person>>(&name).wait().unwrap(), 2);
+        people.push_front(person("Grace", Some(85))).wait().unwrap();
+        people.push_front(person("Alan", Some(41))).wait().unwrap();
+        assert_eq!(
+            people
+                .push_front_evicting(person("Ada", Some(36)))
+                .wait()
+                .unwrap(),
+            vec![person("Grace", Some(85))]
+        );
+        let people = people.list();
+        assert_eq!(
+            people.find_by_field(&["age"], 36, 0).wait().unwrap(),
+            vec![(1, person("Ada", Some(36)))]
+        );
+        assert_eq!(
+            people.sort_by_key(&["age"], false).wait().unwrap(),
+            vec![person("Ada", Some(36)), person("Alan", Some(41))]
+        );
+    }
+}
+
+#[test]
+fn nodes_redirect_to_the_owner_of_a_slot() {
+    let cluster = FakeCluster::start(2).unwrap();
+    let database = Database::cluster(cluster.connection_infos())
+        .wait()
+        .unwrap();
+    for i in 0..8 {
+        let mut list: List<u32> = database.get(&format!("list:{}", i)).wait().unwrap();
+        list.push_front(i).wait().unwrap();
+        assert_eq!(list.range(0, -1).wait().unwrap(), vec![i]);
+    }
+    // Each node holds only the keys of its own slots.
+    let lens: Vec<usize> = cluster
+        .nodes()
+        .iter()
+        .map(|node| {
+            let mut connection = redis::Client::open(node.connection_info())
+                .unwrap()
+                .get_connection()
+                .unwrap();
+            let (_, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(0)
+                .arg("COUNT")
+                .arg(100)
+                .query(&mut connection)
+                .unwrap();
+            keys.len()
+        })
+        .collect();
+    assert_eq!(lens.iter().sum::<usize>(), 8);
+    assert!(lens.iter().all(|len| *len > 0));
+}
+
+#[test]
+fn namespaces_keep_hash_tags() {
+    let cluster = FakeCluster::start(3).unwrap();
+    let database = Database::cluster(cluster.connection_infos())
+        .wait()
+        .unwrap()
+        .with_namespace(Namespace::new().prefix("app").hash_tags(false));
+    let queue: List<u32> = database.get("jobs").wait().unwrap();
+    let processing: List<u32> = database.get("{jobs}:processing").wait().unwrap();
+    let mut queue = ReliableQueue::new(queue, processing, Duration::from_secs(30));
+    queue.push(1).wait().unwrap();
+    let receipt = queue.take().wait().unwrap().unwrap();
+    assert!(queue.ack(&receipt).wait().unwrap());
+}
+
+#[test]
+fn pipelines_follow_ask_redirections() {
+    let cluster = FakeCluster::start(2).unwrap();
+    let database = Database::cluster(cluster.connection_infos())
+        .wait()
+        .unwrap();
+    cluster.migrate("{people}");
+    let mut people = CappedList::new(database.get::<List<Person>>("people").wait().unwrap(), 2);
+    for person in [
+        person("Grace", Some(85)),
+        person("Alan", Some(41)),
+        person("Ada", Some(36)),
+    ] {
+        people.push_front(person).wait().unwrap();
+    }
+    assert_eq!(
+        people.list().range(0, -1).wait().unwrap(),
+        vec![person("Alan", Some(41)), person("Ada", Some(36))]
+    );
+}
+
+#[test]
+fn sort_patterns_stay_in_the_slot_of_the_list() {
+    let cluster = FakeCluster::start(3).unwrap();
+    let database = Database::cluster(cluster.connection_infos())
+        .wait()
+        .unwrap();
+    let mut numbers: List<u32> = database.get("numbers").wait().unwrap();
+    for i in 0..3 {
+        numbers.push_back(i).wait().unwrap();
+    }
+    // Without a hash tag, the keys named by a pattern may reside on any node.
+    assert!(matches!(
+        numbers.sorted(&Sort::new().by("weight_*")).wait(),
+        Err(Error::CrossSlot { .. })
+    ));
+    assert!(matches!(
+        numbers.sorted(&Sort::new().by("{weight_*}")).wait(),
+        Err(Error::CrossSlot { .. })
+    ));
+    assert!(matches!(
+        numbers
+            .sorted_get(&Sort::new().by("nosort"), &["#", "name_*"])
+            .wait(),
+        Err(Error::CrossSlot { .. })
+    ));
+    assert_eq!(
+        numbers
+            .sorted(&Sort::new().by("{numbers}:weight_*").descending())
+            .wait()
+            .unwrap()
+            .len(),
+        3
+    );
+    assert_eq!(
+        numbers
+            .sorted_get(&Sort::new().by("nosort"), &["{numbers}:name_*"])
+            .wait()
+            .unwrap(),
+        vec![vec![None]; 3]
+    );
+}
+
+#[test]
+fn queues_need_lists_in_the_same_slot() {
+    let cluster = FakeCluster::start(3).unwrap();
+    let database = Database::cluster(cluster.connection_infos())
+        .wait()
+        .unwrap();
+    let queue: List<u32> = database.get("jobs").wait().unwrap();
+    let mut queue = ReliableQueue::new(
+        queue,
+        database.get("processing").wait().unwrap(),
+        Duration::from_secs(30),
+    );
+    queue.push(1).wait().unwrap();
+    assert!(matches!(queue.take().wait(), Err(Error::CrossSlot { .. })));
+    let queue: List<u32> = database.get("jobs").wait().unwrap();
+    let processing: List<u32> = database.get("{jobs}:processing").wait().unwrap();
+    let mut queue = ReliableQueue::new(queue, processing, Duration::from_secs(30));
+    let receipt = queue.take().wait().unwrap().unwrap();
+    assert!(queue.ack(&receipt).wait().unwrap());
+    assert_eq!(queue.requeue_expired().wait().unwrap(), 0);
+}
@@ -0,0 +1,24 @@
+//! Element types shared by the integration tests.
+
+use redis_backed::{Migrate, Migrations};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Person {
+    pub name: String,
+    pub age: Option<u32>,
+}
+
+impl Migrate for Person {
+    const VERSION: u32 = 1;
+    fn migrations() -> Migrations {
+        Migrations::new()
+    }
+}
+
+pub fn person(name: &str, age: Option<u32>) -> Person {
+    Person {
+        name: name.to_owned(),
+        age,
+    }
+}
@@ -0,0 +1,58 @@
+//! Checks that compressed lists round-trip elements with each enabled algorithm, leave small elements uncompressed
+//! and read elements stored before compression was enabled.
+#![cfg(any(feature = "zstd", feature = "lz4", feature = "snappy"))]
+
+use futures::Future;
+use redis_backed::{collections::List, Compressed, Compression, Database, Error};
+
+fn algorithms() -> Vec<Compression> {
+    vec![
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(0),
+        #[cfg(feature = "lz4")]
+        Compression::Lz4,
+        #[cfg(feature = "snappy")]
+        Compression::Snappy,
+    ]
+}
+
+fn document(size: usize) -> String {
+    "lorem ipsum dolor sit amet ".repeat(size / 27 + 1)
+}
+
+#[test]
+fn round_trip() {
+    for compression in algorithms() {
+        let database = Database::in_memory();
+        let mut plain: List<String> = database.get("documents").wait().unwrap();
+        plain.push_front(document(1024)).wait().unwrap();
+
+        let mut documents = database
+            .get::<List<String>>("documents")
+            .wait()
+            .unwrap()
+            .with_codec(Compressed::new(compression).threshold(64));
+        documents.push_front(document(16)).wait().unwrap();
+        documents.push_front(document(1024)).wait().unwrap();
+        assert_eq!(
+            documents.range(0, -1).wait().unwrap(),
+            vec![document(1024), document(16), document(1024)],
+            "{:?}",
+            compression
+        );
+
+        // Only the large element written through the codec is compressed, and equal elements compress identically.
+        assert_eq!(plain.index(1).wait().unwrap(), document(16));
+        assert!(matches!(plain.index(2).wait(), Err(Error::Decode { .. })));
+        assert_eq!(documents.remove(0, document(1024)).wait().unwrap(), 1);
+        assert_eq!(documents.len().wait().unwrap(), 2);
+    }
+}
+
+#[test]
+fn database_codec() {
+    let database = Database::in_memory().with_codec(Compressed::new(algorithms()[0]));
+    let mut documents: List<String> = database.get("documents").wait().unwrap();
+    documents.push_back(document(4096)).wait().unwrap();
+    assert_eq!(documents.pop_front().wait().unwrap(), Some(document(4096)));
+}
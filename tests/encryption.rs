@@ -0,0 +1,127 @@
+//! Checks that encrypted lists round-trip elements with each enabled cipher, only read plaintext elements when it is
+//! accepted, decrypt elements encrypted with retained keys following a rotation, support equality-based operations
+//! only when encryption is deterministic, and acknowledge reliable queue elements in either mode.
+#![cfg(any(feature = "aes-gcm", feature = "chacha20poly1305"))]
+
+use futures::Future;
+use redis_backed::{
+    collections::{List, ReliableQueue},
+    Cipher, Database, Encrypted, Error, Keyring,
+};
+use std::time::Duration;
+
+fn ciphers() -> Vec<Cipher> {
+    vec![
+        #[cfg(feature = "aes-gcm")]
+        Cipher::Aes256Gcm,
+        #[cfg(feature = "chacha20poly1305")]
+        Cipher::ChaCha20Poly1305,
+    ]
+}
+
+fn list(database: &Database, codec: Encrypted) -> List<String> {
+    database
+        .get::<List<String>>("people")
+        .wait()
+        .unwrap()
+        .with_codec(codec)
+}
+
+#[test]
+fn round_trip() {
+    for cipher in ciphers() {
+        let database = Database::in_memory();
+        let mut plain: List<String> = database.get("people").wait().unwrap();
+        plain.push_front("legacy".to_owned()).wait().unwrap();
+
+        let mut people = list(&database, Encrypted::new(cipher, Keyring::new(1, [7; 32])));
+        people.push_front("ada".to_owned()).wait().unwrap();
+        assert_eq!(people.index(1).wait().unwrap(), "ada", "{:?}", cipher);
+        assert!(matches!(plain.index(1).wait(), Err(Error::Decode { .. })));
+        // Elements written before encryption was enabled are only read once plaintext is accepted.
+        match people.index(0).wait() {
+            Err(Error::Codec { index, .. }) => assert_eq!(index, Some(0)),
+            result => panic!("unexpected result {:?}", result),
+        }
+        let mut people = list(
+            &database,
+            Encrypted::new(cipher, Keyring::new(1, [7; 32])).accept_plaintext(),
+        );
+        assert_eq!(
+            people.range(0, -1).wait().unwrap(),
+            vec!["legacy".to_owned(), "ada".to_owned()]
+        );
+        // Elements encrypted with random nonces never compare equal.
+        assert_eq!(people.remove(0, "ada".to_owned()).wait().unwrap(), 0);
+    }
+}
+
+#[test]
+fn deterministic() {
+    for cipher in ciphers() {
+        let database = Database::in_memory();
+        let mut people = list(
+            &database,
+            Encrypted::new(cipher, Keyring::new(1, [7; 32])).deterministic(),
+        );
+        people.push_front("ada".to_owned()).wait().unwrap();
+        people.push_front("alan".to_owned()).wait().unwrap();
+        assert!(people
+            .insert_before("alan".to_owned(), "grace".to_owned())
+            .wait()
+            .unwrap());
+        assert_eq!(people.remove(0, "ada".to_owned()).wait().unwrap(), 1);
+        assert_eq!(
+            people.range(0, -1).wait().unwrap(),
+            vec!["grace".to_owned(), "alan".to_owned()]
+        );
+    }
+}
+
+#[test]
+fn queues_acknowledge_randomly_encrypted_elements() {
+    let cipher = ciphers()[0];
+    let database = Database::in_memory();
+    let codec = || Encrypted::new(cipher, Keyring::new(1, [7; 32]));
+    let queue = database
+        .get::<List<String>>("queue")
+        .wait()
+        .unwrap()
+        .with_codec(codec());
+    let processing = database
+        .get::<List<String>>("processing")
+        .wait()
+        .unwrap()
+        .with_codec(codec());
+    let mut queue = ReliableQueue::new(queue, processing, Duration::from_secs(0));
+    queue.push("job".to_owned()).wait().unwrap();
+    let receipt = queue.take().wait().unwrap().unwrap();
+    assert_eq!(receipt.item(), "job");
+    assert!(queue.ack(&receipt).wait().unwrap());
+    assert_eq!(queue.requeue_expired().wait().unwrap(), 0);
+}
+
+#[test]
+fn rotation() {
+    let cipher = ciphers()[0];
+    let database = Database::in_memory();
+    let mut people = list(&database, Encrypted::new(cipher, Keyring::new(1, [7; 32])));
+    people.push_front("ada".to_owned()).wait().unwrap();
+
+    let keyring = Keyring::new(2, [9; 32]).with_key(1, [7; 32]);
+    let mut people = list(&database, Encrypted::new(cipher, keyring));
+    people.push_front("alan".to_owned()).wait().unwrap();
+    assert_eq!(
+        people.range(0, -1).wait().unwrap(),
+        vec!["ada".to_owned(), "alan".to_owned()]
+    );
+
+    // Without the previous key its elements cannot be read, nor can elements be read with the wrong key.
+    let mut people = list(&database, Encrypted::new(cipher, Keyring::new(2, [9; 32])));
+    match people.index(0).wait() {
+        Err(Error::Codec { index, .. }) => assert_eq!(index, Some(0)),
+        result => panic!("unexpected result {:?}", result),
+    }
+    let mut people = list(&database, Encrypted::new(cipher, Keyring::new(2, [8; 32])));
+    assert!(matches!(people.index(1).wait(), Err(Error::Codec { .. })));
+}
@@ -0,0 +1,84 @@
+//! Checks that databases and collection handles can be stored in shared application state.
+
+use futures::Future;
+use redis_backed::{
+    blocking,
+    collections::{CappedList, List, Receipt, ReliableQueue},
+    Database, Script,
+};
+
+use std::{
+    marker::PhantomData,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// An element type that is neither `Send` nor `Sync`.
+type Unshared = PhantomData<*const u32>;
+
+fn assert_handle<T: Clone + Send + Sync + 'static>() {}
+
+#[test]
+fn handles_are_clone_send_sync_and_static() {
+    assert_handle::<Database>();
+    assert_handle::<Script>();
+    // Handles do not hold elements, so this holds even for elements that are neither `Send` nor `Sync`.
+    assert_handle::<List<Unshared>>();
+    assert_handle::<CappedList<Unshared>>();
+    assert_handle::<ReliableQueue<Unshared>>();
+    assert_handle::<blocking::Database>();
+    assert_handle::<blocking::List<Unshared>>();
+    assert_handle::<blocking::CappedList<Unshared>>();
+    assert_handle::<blocking::ReliableQueue<Unshared>>();
+}
+
+#[test]
+fn clones_are_usable_from_many_threads() {
+    let database = Database::in_memory();
+    let list: List<u32> = database.get("shared").wait().unwrap();
+    let threads: Vec<_> = (0..4)
+        .map(|i| {
+            let database = database.clone();
+            let mut list = list.clone();
+            thread::spawn(move || {
+                for j in 0..25 {
+                    list.push_front(i * 25 + j).wait().unwrap();
+                }
+                database.get::<List<u32>>("shared").wait().unwrap()
+            })
+        })
+        .collect();
+    for thread in threads {
+        let mut list = thread.join().unwrap();
+        list.len().wait().unwrap();
+    }
+    let mut elements = list.clone().range(0, -1).wait().unwrap();
+    elements.sort();
+    assert_eq!(elements, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn sub_second_blocking_timeouts_elapse() {
+    let database = Database::in_memory();
+    let queue: List<u32> = database.get("queue").wait().unwrap();
+    let processing: List<u32> = database.get("processing").wait().unwrap();
+    let mut queue = ReliableQueue::new(queue, processing, Duration::from_secs(30));
+    let mut clone = queue.clone();
+    let started = Instant::now();
+    assert!(queue
+        .take_blocking(Duration::from_millis(200))
+        .wait()
+        .unwrap()
+        .is_none());
+    assert!(started.elapsed() < Duration::from_secs(1));
+    // The clone shares the connection, which is released once the take times out.
+    clone.push(1).wait().unwrap();
+    assert_eq!(
+        queue
+            .take_blocking(Duration::from_millis(200))
+            .wait()
+            .unwrap()
+            .map(Receipt::into_item),
+        Some(1)
+    );
+}
@@ -0,0 +1,88 @@
+//! Checks that the operations of collections that execute as scripts, and server-side sorting, are performed by the
+//! in-memory backend.
+
+mod common;
+
+use common::{person, Person};
+use futures::Future;
+use redis_backed::{
+    collections::{CappedList, List, ReliableQueue, Sort},
+    Database,
+};
+
+use std::time::Duration;
+
+#[test]
+fn capped_lists_evict() {
+    let database = Database::in_memory();
+    let list: List<u32> = database.get("capped").wait().unwrap();
+    let mut capped = CappedList::new(list, 2);
+    for i in 0..3 {
+        capped.push_front(i).wait().unwrap();
+    }
+    assert_eq!(capped.push_front_evicting(3).wait().unwrap(), vec![1]);
+    assert_eq!(capped.list().range(0, -1).wait().unwrap(), vec![2, 3]);
+}
+
+#[test]
+fn expired_elements_are_requeued() {
+    let database = Database::in_memory();
+    let queue: List<u32> = database.get("queue").wait().unwrap();
+    let processing: List<u32> = database.get("processing").wait().unwrap();
+    let mut queue = ReliableQueue::new(queue, processing, Duration::from_secs(0));
+    queue.push(1).wait().unwrap();
+    assert_eq!(queue.take().wait().unwrap().unwrap().into_item(), 1);
+    // The element was leased for no time at all when it was taken.
+    std::thread::sleep(Duration::from_millis(5));
+    assert_eq!(queue.requeue_expired().wait().unwrap(), 1);
+    let receipt = queue.take().wait().unwrap().unwrap();
+    assert_eq!(*receipt.item(), 1);
+    assert!(queue.ack(&receipt).wait().unwrap());
+    assert!(!queue.ack(&receipt).wait().unwrap());
+}
+
+#[test]
+fn lists_are_searched_and_sorted() {
+    let database = Database::in_memory();
+    let mut people: List<Person> = database.get("people").wait().unwrap();
+    for person in &[
+        person("Grace", Some(85)),
+        person("Ada", None),
+        person("Alan", Some(41)),
+    ] {
+        people.push_front(person.clone()).wait().unwrap();
+    }
+    assert_eq!(
+        people.find_by_field(&["age"], Some(41), 0).wait().unwrap(),
+        vec![(2, person("Alan", Some(41)))]
+    );
+    let names = |people: Vec<Person>| -> Vec<String> {
+        people.into_iter().map(|person| person.name).collect()
+    };
+    assert_eq!(
+        names(people.sort_by_key(&["age"], false).wait().unwrap()),
+        vec!["Ada", "Alan", "Grace"]
+    );
+    let sorted: List<Person> = database.get("sorted").wait().unwrap();
+    assert_eq!(
+        people
+            .sort_by_key_into(&["name"], true, &sorted)
+            .wait()
+            .unwrap(),
+        3
+    );
+    assert_eq!(
+        names(sorted.clone().range(0, -1).wait().unwrap()),
+        vec!["Grace", "Alan", "Ada"]
+    );
+    assert_eq!(
+        names(
+            people
+                .sorted(&Sort::new().alpha().limit(0, 1))
+                .wait()
+                .unwrap()
+        )
+        .len(),
+        1
+    );
+}
@@ -0,0 +1,47 @@
+//! Checks that enumerating the keys of a database honours its timeouts and retry policy.
+
+use futures::{Future, Stream};
+use redis_backed::{Database, Error};
+
+use std::{
+    net::TcpListener,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn scans_time_out() {
+    // The connection is accepted by the operating system, but nothing ever replies to it.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("redis://{}/", listener.local_addr().unwrap());
+    let database = Database::new(url.as_str())
+        .wait()
+        .unwrap()
+        .with_read_timeout(Duration::from_millis(100));
+    let started = Instant::now();
+    assert!(matches!(
+        database.all_keys("*").collect().wait(),
+        Err(Error::Timeout)
+    ));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn scans_survive_closed_connections() {
+    use redis_backed::{collections::List, test_util::FakeServer};
+
+    let server = FakeServer::start().unwrap();
+    let database = Database::new(server.url().as_str()).wait().unwrap();
+    for i in 0..5 {
+        let mut list: List<u32> = database.get(&format!("list:{}", i)).wait().unwrap();
+        list.push_front(i).wait().unwrap();
+    }
+    let mut keys = database.all_keys("*").count(1).wait();
+    let mut found = vec![keys.next().unwrap().unwrap().key];
+    // Both the following SCAN and the TYPE pipeline of its keys fail once, and are retried on a new connection.
+    server.disconnect_next(2);
+    found.extend(keys.map(|key| key.unwrap().key));
+    found.sort();
+    found.dedup();
+    assert_eq!(found.len(), 5);
+}
@@ -0,0 +1,255 @@
+//! Checks random sequences of `List` operations against a `VecDeque` model of the redis list they operate on.
+//!
+//! The model is ordered from the head of the redis list (index 0) to its tail, so it pins down which end each
+//! operation acts upon: `push_front` appends to the tail with RPUSH and `pop_front` takes from it with RPOP, while
+//! `push_back` and `pop_back` act on the head.
+//!
+//! The operations are always checked against the in-memory backend, against the fake server when the `test-util`
+//! feature is enabled, and against the redis server at `REDIS_URL` when that variable is set.
+
+use futures::Future;
+use proptest::{collection::vec, prelude::*};
+use redis_backed::{collections::List, Database, Error};
+
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[derive(Debug, Clone)]
+enum Op {
+    PushFront(u8),
+    PushBack(u8),
+    PopFront,
+    PopBack,
+    Index(i64),
+    Get(i64),
+    SetIndex(i64, u8),
+    InsertBefore(u8, u8),
+    InsertAfter(u8, u8),
+    Remove(u32, u8),
+    Trim(i64, i64),
+    Range(i64, i64),
+    Len,
+}
+
+/// Values are drawn from a small domain so that pivots and removed items are usually present.
+fn value() -> impl Strategy<Value = u8> {
+    0u8..8
+}
+
+fn index() -> impl Strategy<Value = i64> {
+    -12i64..12
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => value().prop_map(Op::PushFront),
+        3 => value().prop_map(Op::PushBack),
+        1 => Just(Op::PopFront),
+        1 => Just(Op::PopBack),
+        1 => index().prop_map(Op::Index),
+        1 => index().prop_map(Op::Get),
+        1 => (index(), value()).prop_map(|(index, value)| Op::SetIndex(index, value)),
+        1 => (value(), value()).prop_map(|(pivot, value)| Op::InsertBefore(pivot, value)),
+        1 => (value(), value()).prop_map(|(pivot, value)| Op::InsertAfter(pivot, value)),
+        1 => (0u32..3, value()).prop_map(|(count, item)| Op::Remove(count, item)),
+        1 => (index(), index()).prop_map(|(start, stop)| Op::Trim(start, stop)),
+        1 => (index(), index()).prop_map(|(start, stop)| Op::Range(start, stop)),
+        1 => Just(Op::Len),
+    ]
+}
+
+/// The result of an operation, with errors other than those of accessing a missing element reduced to their
+/// occurrence.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Unit,
+    Value(Option<u8>),
+    Values(Vec<u8>),
+    Count(u32),
+    Inserted(bool),
+    MissingElement,
+    IndexOutOfRange,
+    Failed,
+}
+
+/// Resolves a redis index, which counts from the tail if negative.
+fn resolve(len: usize, index: i64) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    if index >= 0 && (index as usize) < len {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+/// Resolves an inclusive redis range to the exclusive range of indices it covers, which may be empty.
+fn resolve_range(len: usize, start: i64, stop: i64) -> (usize, usize) {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+    if start > stop || start >= len {
+        (0, 0)
+    } else {
+        (start as usize, stop as usize + 1)
+    }
+}
+
+fn insert(model: &mut VecDeque<u8>, pivot: u8, value: u8, offset: usize) -> Outcome {
+    match model.iter().position(|item| *item == pivot) {
+        Some(position) => {
+            model.insert(position + offset, value);
+            Outcome::Inserted(true)
+        }
+        None => Outcome::Inserted(false),
+    }
+}
+
+fn apply_model(model: &mut VecDeque<u8>, op: &Op) -> Outcome {
+    match *op {
+        Op::PushFront(value) => {
+            model.push_back(value);
+            Outcome::Unit
+        }
+        Op::PushBack(value) => {
+            model.push_front(value);
+            Outcome::Unit
+        }
+        Op::PopFront => Outcome::Value(model.pop_back()),
+        Op::PopBack => Outcome::Value(model.pop_front()),
+        Op::Index(index) => match resolve(model.len(), index) {
+            Some(index) => Outcome::Value(Some(model[index])),
+            None => Outcome::MissingElement,
+        },
+        Op::Get(index) => Outcome::Value(resolve(model.len(), index).map(|index| model[index])),
+        Op::SetIndex(index, value) => match resolve(model.len(), index) {
+            Some(index) => {
+                model[index] = value;
+                Outcome::Unit
+            }
+            None => Outcome::IndexOutOfRange,
+        },
+        Op::InsertBefore(pivot, value) => insert(model, pivot, value, 0),
+        Op::InsertAfter(pivot, value) => insert(model, pivot, value, 1),
+        Op::Remove(count, item) => {
+            let limit = if count == 0 {
+                usize::MAX
+            } else {
+                count as usize
+            };
+            let mut removed = 0;
+            model.retain(|element| {
+                if *element == item && removed < limit {
+                    removed += 1;
+                    false
+                } else {
+                    true
+                }
+            });
+            Outcome::Count(removed as u32)
+        }
+        Op::Trim(start, stop) => {
+            let (start, stop) = resolve_range(model.len(), start, stop);
+            model.truncate(stop);
+            model.drain(..start);
+            Outcome::Unit
+        }
+        Op::Range(start, stop) => {
+            let (start, stop) = resolve_range(model.len(), start, stop);
+            Outcome::Values(model.range(start..stop).cloned().collect())
+        }
+        Op::Len => Outcome::Count(model.len() as u32),
+    }
+}
+
+fn apply_list(list: &mut List<u8>, op: &Op) -> Outcome {
+    let outcome = match *op {
+        Op::PushFront(value) => list.push_front(value).wait().map(|_| Outcome::Unit),
+        Op::PushBack(value) => list.push_back(value).wait().map(|_| Outcome::Unit),
+        Op::PopFront => list.pop_front().wait().map(Outcome::Value),
+        Op::PopBack => list.pop_back().wait().map(Outcome::Value),
+        Op::Index(index) => list
+            .index(index)
+            .wait()
+            .map(|value| Outcome::Value(Some(value))),
+        Op::Get(index) => list.get(index).wait().map(Outcome::Value),
+        Op::SetIndex(index, value) => list.set_index(index, value).wait().map(|_| Outcome::Unit),
+        Op::InsertBefore(pivot, value) => list
+            .insert_before(pivot, value)
+            .wait()
+            .map(Outcome::Inserted),
+        Op::InsertAfter(pivot, value) => list
+            .insert_after(pivot, value)
+            .wait()
+            .map(Outcome::Inserted),
+        Op::Remove(count, item) => list.remove(count, item).wait().map(Outcome::Count),
+        Op::Trim(start, stop) => list.trim(start, stop).wait().map(|_| Outcome::Unit),
+        Op::Range(start, stop) => list.range(start, stop).wait().map(Outcome::Values),
+        Op::Len => list.len().wait().map(Outcome::Count),
+    };
+    match outcome {
+        Ok(outcome) => outcome,
+        Err(Error::MissingElement { .. }) => Outcome::MissingElement,
+        Err(Error::IndexOutOfRange { .. }) => Outcome::IndexOutOfRange,
+        Err(_) => Outcome::Failed,
+    }
+}
+
+static CASES: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs `ops` against a fresh list of `database` and the model, comparing every result and the contents of the list
+/// after every operation.
+fn check(database: &Database, ops: &[Op]) -> Result<(), TestCaseError> {
+    let name = format!(
+        "conformance:{}:{}",
+        std::process::id(),
+        CASES.fetch_add(1, Ordering::SeqCst)
+    );
+    let mut list: List<u8> = database.get(&name).wait().unwrap();
+    let mut model = VecDeque::new();
+    for (step, op) in ops.iter().enumerate() {
+        let expected = apply_model(&mut model, op);
+        let actual = apply_list(&mut list, op);
+        prop_assert_eq!(actual, expected, "result of {:?} at step {}", op, step);
+        let contents = list.range(0, -1).wait().unwrap();
+        prop_assert_eq!(
+            contents,
+            model.iter().cloned().collect::<Vec<_>>(),
+            "contents after {:?} at step {}",
+            op,
+            step
+        );
+    }
+    let _: () = redis_backed::collections::Key::remove(list).wait().unwrap();
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn in_memory(ops in vec(op(), 1..48)) {
+        check(&Database::in_memory(), &ops)?;
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn fake_server(ops in vec(op(), 1..48)) {
+        let server = redis_backed::test_util::FakeServer::start().unwrap();
+        check(&Database::new(server.connection_info()).wait().unwrap(), &ops)?;
+    }
+
+    #[test]
+    fn server(ops in vec(op(), 1..48)) {
+        if let Ok(url) = std::env::var("REDIS_URL") {
+            check(&Database::new(url.as_str()).wait().unwrap(), &ops)?;
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Checks that the namespace of a database maps collection names to keys with the configured type prefixes.
+
+use futures::{Future, Stream};
+use redis_backed::{collections::List, Database, KeyType, Namespace};
+
+fn keys(database: &Database) -> Vec<String> {
+    let mut keys: Vec<String> = database
+        .all_keys("*")
+        .map(|info| info.key)
+        .collect()
+        .wait()
+        .unwrap();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn type_prefix_overrides() {
+    let namespace = Namespace::new()
+        .prefix("app")
+        .type_prefix(KeyType::List, Some("list"));
+    let database = Database::in_memory().with_namespace(namespace.clone());
+    let mut people: List<u32> = database.get("people").wait().unwrap();
+    people.push_front(1).wait().unwrap();
+    assert_eq!(keys(&database), vec!["app:list:people".to_owned()]);
+    assert_eq!(
+        database.keys::<List<u32>>("*").collect().wait().unwrap(),
+        vec!["people".to_owned()]
+    );
+
+    // Overrides take precedence over disabling type prefixes, and the last override of a type applies.
+    let database = database.with_namespace(
+        namespace
+            .type_prefixes(false)
+            .type_prefix(KeyType::List, None),
+    );
+    let mut people: List<u32> = database.get("people").wait().unwrap();
+    people.push_front(1).wait().unwrap();
+    assert_eq!(
+        keys(&database),
+        vec!["app:list:people".to_owned(), "app:people".to_owned()]
+    );
+}
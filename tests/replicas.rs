@@ -0,0 +1,80 @@
+//! Checks that reads on replicas fall back to the primary when a replica fails, but not when it times out, against fake
+//! servers whose databases are not replicated, so that the server performing each read can be told apart.
+#![cfg(feature = "test-util")]
+
+use futures::Future;
+use redis_backed::{
+    collections::{List, Sort},
+    test_util::FakeServer,
+    Database, Error, ReadFrom, ReplicaSelection,
+};
+
+use std::{
+    net::TcpListener,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn failed_replicas_fall_back_to_the_primary() {
+    let primary = FakeServer::start().unwrap();
+    let replica = FakeServer::start().unwrap();
+    let database = Database::replicated(
+        primary.url().as_str(),
+        vec![replica.url().as_str()],
+        ReplicaSelection::RoundRobin,
+    )
+    .wait()
+    .unwrap();
+    let mut list: List<u32> = database.get("numbers").wait().unwrap();
+    list.push_front(1).wait().unwrap();
+    // The replica holds nothing, so reads that it performs find the list empty.
+    assert_eq!(list.len().wait().unwrap(), 0);
+    replica.disconnect_next(1);
+    assert_eq!(list.len().wait().unwrap(), 1);
+    // The connection to the replica is reestablished for the following read.
+    assert_eq!(list.len().wait().unwrap(), 0);
+}
+
+#[test]
+fn replica_timeouts_fail() {
+    let primary = FakeServer::start().unwrap();
+    // The connection is accepted by the operating system, but nothing ever replies to it.
+    let replica = TcpListener::bind("127.0.0.1:0").unwrap();
+    let replica = format!("redis://{}/", replica.local_addr().unwrap());
+    let database = Database::replicated(
+        primary.url().as_str(),
+        vec![replica.as_str()],
+        ReplicaSelection::RoundRobin,
+    )
+    .wait()
+    .unwrap()
+    .with_read_timeout(Duration::from_millis(100));
+    let mut list: List<u32> = database.get("numbers").wait().unwrap();
+    list.push_front(1).wait().unwrap();
+    let started = Instant::now();
+    assert!(matches!(list.len().wait(), Err(Error::Timeout)));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn sorts_read_from_replicas() {
+    let primary = FakeServer::start().unwrap();
+    let replica = FakeServer::start().unwrap();
+    let database = Database::replicated(
+        primary.url().as_str(),
+        vec![replica.url().as_str()],
+        ReplicaSelection::RoundRobin,
+    )
+    .wait()
+    .unwrap();
+    let mut list: List<u32> = database.get("numbers").wait().unwrap();
+    list.push_front(1).wait().unwrap();
+    assert!(list.sorted(&Sort::new().alpha()).wait().unwrap().is_empty());
+    assert!(list
+        .sorted_get(&Sort::new().alpha(), &["#"])
+        .wait()
+        .unwrap()
+        .is_empty());
+    let mut list = list.with_read_from(ReadFrom::Primary);
+    assert_eq!(list.sorted(&Sort::new().alpha()).wait().unwrap(), vec![1]);
+}
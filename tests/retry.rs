@@ -0,0 +1,45 @@
+//! Checks that requests interrupted by an I/O error are retried only when they are idempotent, against a fake server
+//! that drops connections.
+#![cfg(feature = "test-util")]
+
+use futures::Future;
+use redis_backed::{collections::List, test_util::FakeServer, Database, Error, RetryPolicy};
+
+use std::time::Duration;
+
+fn database(server: &FakeServer, retry: RetryPolicy) -> Database {
+    Database::new(server.connection_info())
+        .wait()
+        .unwrap()
+        .with_retry_policy(retry.backoff(Duration::from_millis(1), Duration::from_millis(1)))
+}
+
+#[test]
+fn non_idempotent_requests_are_not_retried() {
+    let server = FakeServer::start().unwrap();
+    let mut list: List<u32> = database(&server, RetryPolicy::new())
+        .get("numbers")
+        .wait()
+        .unwrap();
+    // The push is performed before the connection is dropped, so retrying it would push the element twice.
+    server.disconnect_next(1);
+    assert!(matches!(
+        list.push_front(1).wait(),
+        Err(Error::Connection(_))
+    ));
+    // Reads are retried, and the connection is reestablished.
+    server.disconnect_next(1);
+    assert_eq!(list.len().wait().unwrap(), 1);
+}
+
+#[test]
+fn non_idempotent_requests_are_retried_when_permitted() {
+    let server = FakeServer::start().unwrap();
+    let mut list: List<u32> = database(&server, RetryPolicy::new().retry_non_idempotent(true))
+        .get("numbers")
+        .wait()
+        .unwrap();
+    server.disconnect_next(1);
+    list.push_front(1).wait().unwrap();
+    assert_eq!(list.len().wait().unwrap(), 2);
+}
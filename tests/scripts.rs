@@ -0,0 +1,96 @@
+//! Checks scripts against the redis server at `REDIS_URL`, since the in-memory backend does not interpret Lua.
+
+mod common;
+
+use common::{person, Person};
+use futures::Future;
+use redis_backed::{
+    collections::{Collection, List},
+    Codec, Database, Error,
+};
+
+use std::io;
+
+/// A codec that reverses elements behind a reserved initial byte, standing in for compression or encryption.
+struct Reversed;
+
+impl Codec for Reversed {
+    fn encode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(std::iter::once(0x1c)
+            .chain(data.iter().rev().cloned())
+            .collect())
+    }
+    fn decode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match data.split_first() {
+            Some((0x1c, data)) => Ok(data.iter().rev().cloned().collect()),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "not reversed")),
+        }
+    }
+}
+
+fn database() -> Database {
+    let url = std::env::var("REDIS_URL").expect("REDIS_URL is not set");
+    Database::new(url.as_str()).wait().unwrap()
+}
+
+fn name(suffix: &str) -> String {
+    format!("scripts:{}:{}", std::process::id(), suffix)
+}
+
+#[test]
+#[ignore = "requires a redis server at REDIS_URL"]
+fn replies_are_decoded_with_the_encoding_of_the_list() {
+    let database = database();
+    let mut people: List<Person> = database
+        .get::<List<Person>>(&name("people"))
+        .wait()
+        .unwrap()
+        .with_codec(Reversed)
+        .versioned();
+    people.push_back(person("Ada", None)).wait().unwrap();
+    people.push_back(person("Alan", None)).wait().unwrap();
+    let script = database
+        .script("return redis.call('LRANGE', KEYS[1], 0, -1)")
+        .wait()
+        .unwrap();
+    assert_eq!(
+        script
+            .invocation()
+            .key(&people)
+            .elements_of(&people)
+            .invoke_decoded::<Person>()
+            .wait()
+            .unwrap(),
+        vec![person("Ada", None), person("Alan", None)]
+    );
+    // Without the encoding of the list, its elements are not understood.
+    match script
+        .invocation()
+        .key(&people)
+        .invoke_decoded::<Person>()
+        .wait()
+    {
+        Err(Error::Decode { key, .. }) => assert_eq!(key, people.key()),
+        result => panic!("unexpected result {:?}", result.map(|_| ())),
+    }
+    let single = database
+        .script("return redis.call('LINDEX', KEYS[1], ARGV[1])")
+        .wait()
+        .unwrap();
+    for (index, expected) in [(1, vec![person("Alan", None)]), (2, vec![])] {
+        assert_eq!(
+            single
+                .invocation()
+                .key(&people)
+                .raw_arg(index)
+                .elements_of(&people)
+                .invoke_decoded::<Person>()
+                .wait()
+                .unwrap(),
+            expected
+        );
+    }
+    let _: () = redis_backed::collections::Key::remove(people)
+        .wait()
+        .unwrap();
+}
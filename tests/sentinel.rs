@@ -0,0 +1,63 @@
+//! Checks that sentinel-based databases bound their requests to sentinels by the timeouts of the database, and move
+//! their connections to the new master when a sentinel announces a failover.
+
+use futures::Future;
+use redis_backed::{collections::List, Database, Error};
+
+use std::{
+    net::TcpListener,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn unresponsive_sentinels_time_out() {
+    // The connection is accepted by the operating system, but nothing ever replies to it.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("redis://{}/", listener.local_addr().unwrap());
+    let database = Database::sentinel(vec![url.as_str()], "master")
+        .wait()
+        .unwrap()
+        .with_connect_timeout(Duration::from_millis(100))
+        .with_read_timeout(Duration::from_millis(100));
+    let started = Instant::now();
+    assert!(matches!(
+        database.get::<List<u32>>("numbers").wait(),
+        Err(Error::Timeout)
+    ));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+#[cfg(feature = "test-util")]
+fn connections_follow_switch_master() {
+    use redis_backed::test_util::{FakeSentinel, FakeServer};
+    use std::thread;
+
+    let first = FakeServer::start().unwrap();
+    let second = FakeServer::start().unwrap();
+    let sentinel = FakeSentinel::start("master", &first).unwrap();
+    let database = Database::sentinel(vec![sentinel.connection_info()], "master")
+        .wait()
+        .unwrap();
+    let mut list: List<u32> = database.get("numbers").wait().unwrap();
+    list.push_front(1).wait().unwrap();
+    // The sentinels are monitored on a background thread, which may not have subscribed to announcements yet.
+    let started = Instant::now();
+    while sentinel.failover(&second).unwrap() == 0 {
+        assert!(started.elapsed() < Duration::from_secs(5));
+        thread::sleep(Duration::from_millis(10));
+    }
+    // The list is empty on the new master, whose databases are not replicated.
+    while list.len().wait().unwrap() != 0 {
+        assert!(started.elapsed() < Duration::from_secs(5));
+        thread::sleep(Duration::from_millis(10));
+    }
+    list.push_front(2).wait().unwrap();
+    let mut moved: List<u32> = Database::new(second.connection_info())
+        .wait()
+        .unwrap()
+        .get("numbers")
+        .wait()
+        .unwrap();
+    assert_eq!(moved.range(0, -1).wait().unwrap(), vec![2]);
+}
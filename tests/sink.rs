@@ -0,0 +1,76 @@
+//! Checks that list sinks push buffered elements once their flush interval elapses, even while nothing is sent, and
+//! that sinks whose push fails are dropped quietly.
+
+use futures::{sync::mpsc, Future, Sink, Stream};
+use redis_backed::{
+    collections::{End, List},
+    Database, Error,
+};
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn flush_interval_elapses_while_the_stream_stalls() {
+    let database = Database::in_memory();
+    let mut list: List<u32> = database.get("numbers").wait().unwrap();
+    let sink = list
+        .sink(End::Front)
+        .flush_interval(Duration::from_millis(200));
+    let (sender, receiver) = mpsc::unbounded::<u32>();
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.spawn(
+        receiver
+            .map_err(|()| -> Error { unreachable!() })
+            .forward(sink)
+            .map(|_| ())
+            .map_err(|err| panic!("{}", err)),
+    );
+    sender.unbounded_send(1).unwrap();
+    sender.unbounded_send(2).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(list.len().wait().unwrap(), 0);
+    // The sender remains open, so only the timer pushes the elements.
+    let start = Instant::now();
+    while list.len().wait().unwrap() == 0 {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(list.range(0, -1).wait().unwrap(), vec![1, 2]);
+    drop(sender);
+    runtime.shutdown_on_idle().wait().unwrap();
+}
+
+#[test]
+fn closing_pushes_at_once() {
+    let database = Database::in_memory();
+    let mut list: List<u32> = database.get("numbers").wait().unwrap();
+    let sink = list
+        .sink(End::Front)
+        .flush_interval(Duration::from_secs(60));
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let _ = runtime
+        .block_on(sink.send_all(futures::stream::iter_ok::<_, Error>(vec![1, 2, 3])))
+        .unwrap();
+    assert_eq!(list.range(0, -1).wait().unwrap(), vec![1, 2, 3]);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn sinks_that_fail_to_push_are_dropped() {
+    use redis_backed::test_util::FakeServer;
+
+    let server = FakeServer::start().unwrap();
+    let database = Database::new(server.url().as_str()).wait().unwrap();
+    let mut list: List<u32> = database.get("numbers").wait().unwrap();
+    // The push is not idempotent, so the closed connection fails it rather than retrying, and the sink that still
+    // buffers its elements is dropped.
+    server.disconnect_next(1);
+    assert!(list
+        .sink(End::Front)
+        .send_all(futures::stream::iter_ok::<_, Error>(vec![1, 2, 3]))
+        .wait()
+        .is_err());
+}
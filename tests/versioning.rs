@@ -0,0 +1,227 @@
+//! Checks that versioned lists upgrade elements stored at earlier versions, including those stored before versioning
+//! was enabled, that `write_back_upgrades` and `migrate_all` rewrite them, and that reliable queues acknowledge the
+//! elements they upgrade.
+
+use futures::{Future, Stream};
+use redis_backed::{
+    collections::{List, ReliableQueue},
+    Database, Error, Migrate, Migrations,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PersonV0 {
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PersonV1 {
+    first_name: String,
+    last_name: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Person {
+    first_name: String,
+    last_name: String,
+    email: Option<String>,
+}
+
+impl Migrate for Person {
+    const VERSION: u32 = 2;
+    fn migrations() -> Migrations {
+        Migrations::new()
+            .register(0, |person: PersonV0| {
+                let mut names = person.name.splitn(2, ' ');
+                PersonV1 {
+                    first_name: names.next().unwrap_or_default().to_owned(),
+                    last_name: names.next().unwrap_or_default().to_owned(),
+                }
+            })
+            .register(1, |person: PersonV1| Person {
+                first_name: person.first_name,
+                last_name: person.last_name,
+                email: None,
+            })
+    }
+}
+
+/// A version of `Person` that predates the current one and so cannot read what it stores.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Outdated {
+    first_name: String,
+    last_name: String,
+}
+
+impl Migrate for Outdated {
+    const VERSION: u32 = 1;
+    fn migrations() -> Migrations {
+        Migrations::new()
+    }
+}
+
+fn person(first_name: &str, last_name: &str, email: Option<&str>) -> Person {
+    Person {
+        first_name: first_name.to_owned(),
+        last_name: last_name.to_owned(),
+        email: email.map(str::to_owned),
+    }
+}
+
+#[test]
+fn upgrades_on_read() {
+    let database = Database::in_memory();
+    let mut legacy: List<PersonV0> = database.get("people").wait().unwrap();
+    legacy
+        .push_front(PersonV0 {
+            name: "Ada Lovelace".to_owned(),
+        })
+        .wait()
+        .unwrap();
+    let mut people = database
+        .get::<List<Person>>("people")
+        .wait()
+        .unwrap()
+        .versioned();
+    people
+        .push_front(person("Alan", "Turing", Some("alan@example.com")))
+        .wait()
+        .unwrap();
+    let expected = vec![
+        person("Ada", "Lovelace", None),
+        person("Alan", "Turing", Some("alan@example.com")),
+    ];
+    assert_eq!(people.range(0, -1).wait().unwrap(), expected);
+    assert_eq!(people.iter(1).collect().wait().unwrap(), expected);
+    assert_eq!(people.index(0).wait().unwrap(), expected[0]);
+
+    // Elements in an envelope cannot be read as plain CBOR, nor at a newer version than the handle's.
+    match legacy.index(1).wait() {
+        Err(Error::Decode { index, .. }) => assert_eq!(index, Some(1)),
+        result => panic!("unexpected result {:?}", result),
+    }
+    let mut outdated = database
+        .get::<List<Outdated>>("people")
+        .wait()
+        .unwrap()
+        .versioned();
+    assert!(matches!(
+        outdated.index(1).wait(),
+        Err(Error::Decode { .. })
+    ));
+}
+
+/// Pushes people stored before versioning was enabled onto the list `name`, returning a versioned handle to it.
+fn legacy_people(database: &Database, name: &str) -> List<Person> {
+    let mut legacy: List<PersonV0> = database.get(name).wait().unwrap();
+    for name in &["Ada Lovelace", "Alan Turing", "Grace Hopper"] {
+        legacy
+            .push_front(PersonV0 {
+                name: (*name).to_owned(),
+            })
+            .wait()
+            .unwrap();
+    }
+    database
+        .get::<List<Person>>(name)
+        .wait()
+        .unwrap()
+        .versioned()
+}
+
+#[test]
+fn write_back_on_read() {
+    let database = Database::in_memory();
+    let mut people = legacy_people(&database, "people").write_back_upgrades();
+    // Equality is determined by the stored representation, so upgraded elements are only found once rewritten.
+    assert!(!people
+        .contains(person("Alan", "Turing", None))
+        .wait()
+        .unwrap());
+    assert_eq!(
+        people.range(1, -1).wait().unwrap(),
+        vec![
+            person("Alan", "Turing", None),
+            person("Grace", "Hopper", None)
+        ]
+    );
+    assert!(people
+        .contains(person("Alan", "Turing", None))
+        .wait()
+        .unwrap());
+    assert!(!people
+        .contains(person("Ada", "Lovelace", None))
+        .wait()
+        .unwrap());
+    // Elements read without their index, such as by a negative range, are not rewritten.
+    people.range(-3, 0).wait().unwrap();
+    assert!(!people
+        .contains(person("Ada", "Lovelace", None))
+        .wait()
+        .unwrap());
+    // Matches of `find_by_field` are located in the stored representation, here that of the legacy element.
+    assert_eq!(
+        people
+            .find_by_field(&["name"], "Ada Lovelace", 0)
+            .wait()
+            .unwrap(),
+        vec![(0, person("Ada", "Lovelace", None))]
+    );
+    assert!(people
+        .contains(person("Ada", "Lovelace", None))
+        .wait()
+        .unwrap());
+
+    // Handles that do not write back upgrades leave elements as they are.
+    let mut people = legacy_people(&database, "others");
+    people.range(0, -1).wait().unwrap();
+    assert!(!people
+        .contains(person("Ada", "Lovelace", None))
+        .wait()
+        .unwrap());
+}
+
+#[test]
+fn upgraded_elements_are_acknowledged() {
+    let database = Database::in_memory();
+    let queue = legacy_people(&database, "queue");
+    let processing = database
+        .get::<List<Person>>("processing")
+        .wait()
+        .unwrap()
+        .versioned();
+    let mut queue = ReliableQueue::new(queue, processing, Duration::from_secs(0));
+    let receipt = queue.take().wait().unwrap().unwrap();
+    assert_eq!(*receipt.item(), person("Grace", "Hopper", None));
+    // The receipt holds the legacy bytes that were taken, which re-encoding the upgraded element would not match.
+    assert!(queue.ack(&receipt).wait().unwrap());
+    assert_eq!(queue.requeue_expired().wait().unwrap(), 0);
+}
+
+fn check_migrate_all(database: &Database, name: &str) {
+    let mut people = legacy_people(database, name);
+    assert_eq!(people.migrate_all(2).wait().unwrap(), 3);
+    assert_eq!(people.migrate_all(2).wait().unwrap(), 0);
+    // Once migrated, elements are found by their current encoding.
+    assert!(people
+        .contains(person("Grace", "Hopper", None))
+        .wait()
+        .unwrap());
+    let _: () = redis_backed::collections::Key::remove(people)
+        .wait()
+        .unwrap();
+}
+
+#[test]
+fn migrate_all() {
+    check_migrate_all(&Database::in_memory(), "people");
+}
+
+#[test]
+#[ignore = "requires a redis server at REDIS_URL"]
+fn migrate_all_on_server() {
+    let url = std::env::var("REDIS_URL").expect("REDIS_URL is not set");
+    let database = Database::new(url.as_str()).wait().unwrap();
+    check_migrate_all(&database, &format!("versioning:{}", std::process::id()));
+}